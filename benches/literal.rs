@@ -0,0 +1,82 @@
+// 普通字符串查找的基准：literal::Finder（候选字节 + SWAR）和标准库的 str::find，
+// 以及忽略大小写时直接按 ASCII 查找和每行先转小写再查找的做法。运行：cargo bench --bench literal
+use rust_study::literal::Finder;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const LINES: usize = 200_000;
+
+// 像源代码一样的行，每次运行生成的数据相同
+fn lines() -> Vec<String> {
+    let words = [
+        "let", "mut", "self", "value", "return", "match", "config", "Some", "None", "=>", "fn",
+        "pub", "struct", "impl", "for", "in", "if", "else", "&str", "String", "Vec<u8>", "Ok(())",
+        "//", "the", "of", "a", "to", "error:", "file", "line", "count", "+=", "1;", "{", "}",
+    ];
+    let mut seed = 42u64;
+    (0..LINES)
+        .map(|_| {
+            let len = 4 + (seed >> 60) as usize;
+            (0..len)
+                .map(|_| {
+                    seed = seed
+                        .wrapping_mul(6364136223846793005)
+                        .wrapping_add(1442695040888963407);
+                    words[(seed >> 33) as usize % words.len()]
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect()
+}
+
+fn time(lines: &[String], find: impl Fn(&str) -> usize) -> (Duration, usize) {
+    let start = Instant::now();
+    let found = lines.iter().map(|line| find(black_box(line))).sum();
+    (start.elapsed(), found)
+}
+
+fn report(name: &str, fast: (Duration, usize), slow: (Duration, usize)) {
+    assert_eq!(fast.1, slow.1);
+    println!(
+        "{name:<28}  {:>12?}  {:>12?}  {:>7.1}x",
+        fast.0,
+        slow.0,
+        slow.0.as_secs_f64() / fast.0.as_secs_f64()
+    );
+}
+
+fn main() {
+    let lines = lines();
+    let bytes: usize = lines.iter().map(|l| l.len() + 1).sum();
+    println!("{LINES} lines, {bytes} bytes");
+    println!(
+        "{:<28}  {:>12}  {:>12}  {:>8}",
+        "", "Finder", "std", "speedup"
+    );
+    for query in [
+        "fn",
+        "config",
+        "error: file",
+        "Vec<u8> {",
+        "not_in_the_text",
+    ] {
+        let finder = Finder::new(query);
+        let fast = time(&lines, |line| {
+            finder.find(line.as_bytes()).map_or(0, |i| i + 1)
+        });
+        let slow = time(&lines, |line| line.find(query).map_or(0, |i| i + 1));
+        report(&format!("find {query:?}"), fast, slow);
+    }
+    for query in ["CONFIG", "Error: File", "not_in_the_text"] {
+        let lowered = query.to_lowercase();
+        let finder = Finder::new_ignore_ascii_case(&lowered);
+        let fast = time(&lines, |line| {
+            finder.find(line.as_bytes()).map_or(0, |i| i + 1)
+        });
+        let slow = time(&lines, |line| {
+            line.to_lowercase().find(&lowered).map_or(0, |i| i + 1)
+        });
+        report(&format!("-i find {query:?}"), fast, slow);
+    }
+}
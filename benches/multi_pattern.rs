@@ -0,0 +1,66 @@
+// 多模式查找的基准：Aho-Corasick 自动机（find_all）和逐个模式查找（find_all_naive）
+// 在模式数量增加时的耗时。运行：cargo bench --bench multi_pattern
+use rust_study::multi::MultiPattern;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const LINES: usize = 20_000;
+const WORDS_PER_LINE: usize = 12;
+
+// 简单的线性同余随机数，每次运行生成的数据相同
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> usize {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (self.0 >> 33) as usize
+    }
+
+    fn word(&mut self) -> String {
+        let len = 3 + self.next() % 6;
+        (0..len)
+            .map(|_| (b'a' + (self.next() % 26) as u8) as char)
+            .collect()
+    }
+}
+
+fn time(lines: &[String], find: impl Fn(&str) -> usize) -> (Duration, usize) {
+    let start = Instant::now();
+    let found = lines.iter().map(|line| find(black_box(line))).sum();
+    (start.elapsed(), found)
+}
+
+fn main() {
+    let mut rng = Rng(42);
+    let lines: Vec<String> = (0..LINES)
+        .map(|_| {
+            (0..WORDS_PER_LINE)
+                .map(|_| rng.word())
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect();
+    let bytes: usize = lines.iter().map(|l| l.len() + 1).sum();
+    println!("{LINES} lines, {bytes} bytes");
+    println!(
+        "{:>8}  {:>12}  {:>12}  {:>8}",
+        "patterns", "automaton", "naive", "speedup"
+    );
+    for count in [1, 10, 100, 1000] {
+        let patterns: Vec<String> = (0..count).map(|_| rng.word()).collect();
+        let multi = MultiPattern::new(&patterns, false);
+        let count_spans = |found: Vec<(usize, Vec<_>)>| found.iter().map(|(_, s)| s.len()).sum();
+        let (automaton, found) = time(&lines, |line| count_spans(multi.find_all(line)));
+        let (naive, expected) = time(&lines, |line| count_spans(multi.find_all_naive(line)));
+        assert_eq!(found, expected);
+        println!(
+            "{count:>8}  {:>12?}  {:>12?}  {:>7.1}x",
+            automaton,
+            naive,
+            naive.as_secs_f64() / automaton.as_secs_f64()
+        );
+    }
+}
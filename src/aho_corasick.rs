@@ -0,0 +1,209 @@
+// Aho-Corasick 多模式匹配：把所有模式建成一棵按字节的字典树，再给每个节点加上失败指针
+// （当前匹配到的字符串的最长真后缀，且这个后缀也是某个模式的前缀）。扫描时每个字节只走一步，
+// 不需要回退，所以耗时与文本长度加上匹配数成正比，与模式的数量无关；逐个模式查找则是 模式数 × 文本长度
+use std::collections::VecDeque;
+use std::ops::Range;
+
+const ROOT: usize = 0;
+
+#[derive(Debug, Default)]
+struct Node {
+    // 按字节排序的子节点，用二分查找；大部分节点只有一两个子节点，比 256 项的数组省内存
+    children: Vec<(u8, usize)>,
+    fail: usize,
+    // 以这个节点结尾的模式
+    pattern: Option<usize>,
+    // 沿失败指针能到达的下一个有模式结尾的节点，输出匹配时不用走完整条失败链
+    output: Option<usize>,
+    depth: usize,
+}
+
+#[derive(Debug)]
+pub struct AhoCorasick {
+    nodes: Vec<Node>,
+    pattern_count: usize,
+}
+
+// 一次匹配：第几个模式，以及在文本中的字节范围
+#[derive(Debug, Clone, PartialEq)]
+pub struct Match {
+    pub pattern: usize,
+    pub span: Range<usize>,
+}
+
+impl AhoCorasick {
+    // 空模式被忽略；相同的模式出现多次时只有第一个会被报告
+    pub fn new(patterns: &[impl AsRef<[u8]>]) -> AhoCorasick {
+        let mut nodes = vec![Node::default()];
+        for (index, pattern) in patterns.iter().enumerate() {
+            let pattern = pattern.as_ref();
+            if pattern.is_empty() {
+                continue;
+            }
+            let mut node = ROOT;
+            for &byte in pattern {
+                node = match child(&nodes[node], byte) {
+                    Some(next) => next,
+                    None => {
+                        let next = nodes.len();
+                        let depth = nodes[node].depth + 1;
+                        nodes.push(Node {
+                            depth,
+                            ..Node::default()
+                        });
+                        let children = &mut nodes[node].children;
+                        let at = children.partition_point(|&(b, _)| b < byte);
+                        children.insert(at, (byte, next));
+                        next
+                    }
+                };
+            }
+            nodes[node].pattern.get_or_insert(index);
+        }
+
+        // 按层（广度优先）计算失败指针，处理一个节点时比它浅的节点都已经算好
+        let mut queue: VecDeque<usize> = nodes[ROOT].children.iter().map(|&(_, n)| n).collect();
+        while let Some(node) = queue.pop_front() {
+            for (byte, next) in nodes[node].children.clone() {
+                let mut fail = nodes[node].fail;
+                let target = loop {
+                    if let Some(target) = child(&nodes[fail], byte) {
+                        break target;
+                    }
+                    if fail == ROOT {
+                        break ROOT;
+                    }
+                    fail = nodes[fail].fail;
+                };
+                nodes[next].fail = target;
+                nodes[next].output = if nodes[target].pattern.is_some() {
+                    Some(target)
+                } else {
+                    nodes[target].output
+                };
+                queue.push_back(next);
+            }
+        }
+        AhoCorasick {
+            nodes,
+            pattern_count: patterns.len(),
+        }
+    }
+
+    pub fn pattern_count(&self) -> usize {
+        self.pattern_count
+    }
+
+    // 所有出现的位置，包括互相重叠的，按结束位置排列，结束位置相同时长的在前
+    pub fn find_overlapping(&self, haystack: &[u8]) -> Vec<Match> {
+        let mut matches = Vec::new();
+        let mut state = ROOT;
+        for (i, &byte) in haystack.iter().enumerate() {
+            state = self.next_state(state, byte);
+            let mut node = if self.nodes[state].pattern.is_some() {
+                Some(state)
+            } else {
+                self.nodes[state].output
+            };
+            while let Some(n) = node {
+                let n_node = &self.nodes[n];
+                matches.push(Match {
+                    pattern: n_node.pattern.unwrap(),
+                    span: i + 1 - n_node.depth..i + 1,
+                });
+                node = n_node.output;
+            }
+        }
+        matches
+    }
+
+    pub fn is_match(&self, haystack: &[u8]) -> bool {
+        let mut state = ROOT;
+        haystack.iter().any(|&byte| {
+            state = self.next_state(state, byte);
+            self.nodes[state].pattern.is_some() || self.nodes[state].output.is_some()
+        })
+    }
+
+    fn next_state(&self, mut state: usize, byte: u8) -> usize {
+        loop {
+            if let Some(next) = child(&self.nodes[state], byte) {
+                return next;
+            }
+            if state == ROOT {
+                return ROOT;
+            }
+            state = self.nodes[state].fail;
+        }
+    }
+}
+
+fn child(node: &Node, byte: u8) -> Option<usize> {
+    node.children
+        .binary_search_by_key(&byte, |&(b, _)| b)
+        .ok()
+        .map(|i| node.children[i].1)
+}
+
+#[cfg(test)]
+mod aho_corasick_tests {
+    use super::*;
+
+    fn found(ac: &AhoCorasick, text: &str) -> Vec<(usize, &'static str)> {
+        let patterns = ["he", "she", "his", "hers"];
+        ac.find_overlapping(text.as_bytes())
+            .into_iter()
+            .map(|m| {
+                assert_eq!(&text[m.span.clone()], patterns[m.pattern]);
+                (m.span.start, patterns[m.pattern])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_classic_example() {
+        let ac = AhoCorasick::new(&["he", "she", "his", "hers"]);
+        assert_eq!(
+            found(&ac, "ushers"),
+            vec![(1, "she"), (2, "he"), (2, "hers")]
+        );
+        assert_eq!(
+            found(&ac, "ahishers"),
+            vec![(1, "his"), (3, "she"), (4, "he"), (4, "hers")]
+        );
+        assert!(ac.is_match(b"this"));
+        assert!(!ac.is_match(b"xyz"));
+        assert_eq!(ac.pattern_count(), 4);
+    }
+
+    // 和逐个模式、逐个位置比较的结果相同
+    #[test]
+    fn test_against_naive() {
+        let patterns = ["a", "aa", "ab", "bab", "abba", "b"];
+        let ac = AhoCorasick::new(&patterns);
+        let mut seed = 7u32;
+        for _ in 0..200 {
+            let text: Vec<u8> = (0..20)
+                .map(|_| {
+                    seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+                    b"abc"[(seed >> 16) as usize % 3]
+                })
+                .collect();
+            let mut expected = Vec::new();
+            for end in 1..=text.len() {
+                let mut here: Vec<Match> = patterns
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, p)| text[..end].ends_with(p.as_bytes()))
+                    .map(|(pattern, p)| Match {
+                        pattern,
+                        span: end - p.len()..end,
+                    })
+                    .collect();
+                here.sort_by_key(|m| m.span.start);
+                expected.extend(here);
+            }
+            assert_eq!(ac.find_overlapping(&text), expected);
+        }
+    }
+}
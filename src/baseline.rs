@@ -0,0 +1,167 @@
+// --baseline FILE：记下已知的发现，之后只报告新的。在老代码上开始使用规则包时，
+// 已有的几百个发现不可能一次改完，先把它们记进基线，以后只关心新增的问题。
+// 每个发现用文件路径、去掉首尾空白的行内容和匹配的规则计算一个哈希，不包括行号，
+// 所以在文件中移动、重新缩进都不算新发现；改了内容或者换了文件就算。
+// 基线文件不存在时这次找到的所有发现都写进去，这次也不报告；要重新记录就删掉文件
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::sync::Mutex;
+
+pub struct Baseline {
+    path: String,
+    known: HashSet<u64>,
+    // 基线文件还不存在时记下这次的发现。并发搜索时各个工作线程共用
+    recording: Option<Mutex<HashSet<u64>>>,
+}
+
+impl Baseline {
+    pub fn load(path: &str) -> io::Result<Baseline> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return Ok(Baseline {
+                    path: path.to_string(),
+                    known: HashSet::new(),
+                    recording: Some(Mutex::default()),
+                })
+            }
+            Err(err) => return Err(err),
+        };
+        let known = parse(&text).map_err(|msg| io::Error::new(io::ErrorKind::InvalidData, msg))?;
+        Ok(Baseline {
+            path: path.to_string(),
+            known,
+            recording: None,
+        })
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    // 这个发现是不是已知的、不用报告；记录时所有发现都记下来，都不报告
+    pub fn suppresses(&self, path: &str, line: &str, rules: &[&str]) -> bool {
+        let hash = fingerprint(path, line, rules);
+        match &self.recording {
+            Some(found) => {
+                found.lock().unwrap().insert(hash);
+                true
+            }
+            None => self.known.contains(&hash),
+        }
+    }
+
+    // 基线文件原来不存在时写入这次记下的发现，返回数量；已经有基线文件时什么都不做
+    pub fn save(&self) -> io::Result<Option<usize>> {
+        let Some(found) = &self.recording else {
+            return Ok(None);
+        };
+        let found = found.lock().unwrap();
+        fs::write(&self.path, format(&found))?;
+        Ok(Some(found.len()))
+    }
+}
+
+// 64 位的 FNV-1a。标准库的 DefaultHasher 不保证不同版本的结果相同，写进文件的哈希不能用它
+pub fn fingerprint(path: &str, line: &str, rules: &[&str]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    let mut add = |bytes: &[u8]| {
+        for &b in bytes {
+            hash ^= u64::from(b);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    };
+    add(path.as_bytes());
+    add(b"\0");
+    add(line.trim().as_bytes());
+    for rule in rules {
+        add(b"\0");
+        add(rule.as_bytes());
+    }
+    hash
+}
+
+// 排好序，同样的发现每次写出的文件相同，放进版本控制时差异也清楚
+fn format(found: &HashSet<u64>) -> String {
+    let mut hashes: Vec<&u64> = found.iter().collect();
+    hashes.sort();
+    let mut text = String::from(
+        "# minigrep baseline: one hash of path, line content and rules per known finding.\n\
+         # Delete this file and run again to record the current findings.\n",
+    );
+    for hash in hashes {
+        text.push_str(&format!("{hash:016x}\n"));
+    }
+    text
+}
+
+fn parse(text: &str) -> Result<HashSet<u64>, String> {
+    let mut known = HashSet::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match u64::from_str_radix(line, 16) {
+            Ok(hash) if line.len() == 16 => known.insert(hash),
+            _ => return Err(format!("line {}: not a baseline hash: {line}", index + 1)),
+        };
+    }
+    Ok(known)
+}
+
+#[cfg(test)]
+mod baseline_tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn test_fingerprint() {
+        let hash = fingerprint("src/a.rs", "let key = 1;", &["generic-api-key"]);
+        // 行号和首尾空白不影响结果，路径、内容和规则都会
+        assert_eq!(
+            hash,
+            fingerprint("src/a.rs", "    let key = 1;  ", &["generic-api-key"])
+        );
+        assert_ne!(
+            hash,
+            fingerprint("src/b.rs", "let key = 1;", &["generic-api-key"])
+        );
+        assert_ne!(
+            hash,
+            fingerprint("src/a.rs", "let key = 2;", &["generic-api-key"])
+        );
+        assert_ne!(hash, fingerprint("src/a.rs", "let key = 1;", &["jwt"]));
+        assert_ne!(
+            fingerprint("ab", "c", &[]),
+            fingerprint("a", "bc", &[]),
+            "fields are separated"
+        );
+    }
+
+    #[test]
+    fn test_record_then_suppress() {
+        let path = env::temp_dir().join("minigrep-baseline-test.txt");
+        let _ = fs::remove_file(&path);
+        let path = path.to_str().unwrap();
+        // 第一次：记下所有发现，都不报告
+        let baseline = Baseline::load(path).unwrap();
+        assert!(baseline.suppresses("a.rs", "old", &["r"]));
+        assert!(baseline.suppresses("a.rs", "old", &["r"]));
+        assert!(baseline.suppresses("b.rs", "older", &["r"]));
+        assert_eq!(baseline.save().unwrap(), Some(2));
+        let text = fs::read_to_string(path).unwrap();
+        assert_eq!(text.lines().filter(|l| !l.starts_with('#')).count(), 2);
+        // 之后：只报告新的发现，不再改写文件
+        let baseline = Baseline::load(path).unwrap();
+        assert!(baseline.suppresses("a.rs", "  old", &["r"]));
+        assert!(!baseline.suppresses("a.rs", "new", &["r"]));
+        assert_eq!(baseline.save().unwrap(), None);
+        assert_eq!(fs::read_to_string(path).unwrap(), text);
+
+        fs::write(path, "# comment\n\nnot-a-hash\n").unwrap();
+        let err = Baseline::load(path).err().unwrap();
+        assert_eq!(err.to_string(), "line 3: not a baseline hash: not-a-hash");
+    }
+}
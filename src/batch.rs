@@ -0,0 +1,101 @@
+// 批量查询文件（--batch FILE）：每行一个带名字的查询，写成 名字: 查询，
+// 空行和 # 开头的行忽略。查询是普通字符串，冒号后面的空白不算在查询中
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NamedQuery {
+    pub name: String,
+    pub query: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchError {
+    // 从 1 开始的行号，0 表示整个文件的问题
+    pub line: usize,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for BatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            0 => write!(f, "{}", self.reason),
+            line => write!(f, "line {line}: {}", self.reason),
+        }
+    }
+}
+
+impl Error for BatchError {}
+
+pub fn parse_batch(text: &str) -> Result<Vec<NamedQuery>, BatchError> {
+    let mut queries = Vec::new();
+    let mut names = HashSet::new();
+    for (index, line) in text.lines().enumerate() {
+        let error = |reason| BatchError {
+            line: index + 1,
+            reason,
+        };
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let (name, query) = trimmed
+            .split_once(':')
+            .ok_or_else(|| error("expected NAME: QUERY"))?;
+        let (name, query) = (name.trim(), query.trim_start());
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(error("query name must be a single word"));
+        }
+        if query.is_empty() {
+            return Err(error("empty query"));
+        }
+        if !names.insert(name) {
+            return Err(error("duplicate query name"));
+        }
+        queries.push(NamedQuery {
+            name: name.to_string(),
+            query: query.to_string(),
+        });
+    }
+    if queries.is_empty() {
+        return Err(BatchError {
+            line: 0,
+            reason: "no queries",
+        });
+    }
+    Ok(queries)
+}
+
+#[cfg(test)]
+mod batch_tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_batch() {
+        let queries = parse_batch("# audit\ntodo: TODO\n\nurl:  http://\n").unwrap();
+        assert_eq!(
+            queries,
+            vec![
+                NamedQuery {
+                    name: "todo".into(),
+                    query: "TODO".into()
+                },
+                // 查询中可以有冒号
+                NamedQuery {
+                    name: "url".into(),
+                    query: "http://".into()
+                },
+            ]
+        );
+        let error = |text| parse_batch(text).unwrap_err().to_string();
+        assert_eq!(error("a: x\nno colon"), "line 2: expected NAME: QUERY");
+        assert_eq!(error("a:\n"), "line 1: empty query");
+        assert_eq!(error("a: x\na: y"), "line 2: duplicate query name");
+        assert_eq!(
+            error("two words: x"),
+            "line 1: query name must be a single word"
+        );
+        assert_eq!(error("# nothing\n"), "no queries");
+    }
+}
@@ -0,0 +1,48 @@
+// 二进制文件检测：和 grep 一样，开头的一块内容中出现 NUL 字节就当作二进制文件。
+// 二进制文件不按行输出（行的内容没有意义，还可能弄乱终端），默认只报告 "Binary file X matches"
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum BinaryFiles {
+    // 有匹配时只输出一行提示
+    #[default]
+    Binary,
+    // 直接跳过，不输出任何内容
+    WithoutMatch,
+    // 当作文本文件正常搜索
+    Text,
+}
+
+impl FromStr for BinaryFiles {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<BinaryFiles, &'static str> {
+        match s {
+            "binary" => Ok(BinaryFiles::Binary),
+            "without-match" => Ok(BinaryFiles::WithoutMatch),
+            "text" => Ok(BinaryFiles::Text),
+            _ => Err("Binary file handling must be binary, without-match or text"),
+        }
+    }
+}
+
+// head 是文件开头的一块内容，通常就是 BufReader 第一次读到的缓冲区
+pub fn is_binary(head: &[u8]) -> bool {
+    head.contains(&0)
+}
+
+#[cfg(test)]
+mod binary_tests {
+    use super::*;
+
+    #[test]
+    fn test_binary_files() {
+        assert!(is_binary(b"\x7fELF\x02\x01\x01\x00\x00"));
+        assert!(!is_binary("hello 世界\n".as_bytes()));
+        // 不是合法的 UTF-8 但没有 NUL 字节，仍然算文本
+        assert!(!is_binary(b"caf\xe9\n"));
+        assert_eq!("without-match".parse(), Ok(BinaryFiles::WithoutMatch));
+        assert_eq!(BinaryFiles::default(), BinaryFiles::Binary);
+        assert!("skip".parse::<BinaryFiles>().is_err());
+    }
+}
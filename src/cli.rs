@@ -0,0 +1,505 @@
+// 命令行参数解析：把参数拆成选项和位置参数，并给出具体的错误信息（哪个选项、什么值、为什么不合法）
+// 选项的含义由 Config::build 决定，这里只负责通用的部分：--name=value 写法、-- 之后全部视为位置参数、--help 和 --version
+use crate::error::AppError;
+use crate::{Config, Environment};
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+pub const HELP: &str = "\
+Usage: minigrep [OPTIONS] QUERY [FILE]...
+       minigrep [OPTIONS] --query QUERY [--file FILE]... [FILE]...
+       minigrep [OPTIONS] --preset NAME [FILE]...
+       minigrep compare DIR_A DIR_B --query QUERY [OPTIONS]
+       minigrep rules list [PACK]...
+       minigrep rules lint FILE...
+       minigrep rules run PACK [OPTIONS] [FILE]...
+
+Search for QUERY in each FILE. With no FILE, or when FILE is -, read standard input.
+compare searches both directories and lists matching lines found only in DIR_A (<),
+only in DIR_B (>) or in both (=), pairing them by relative path and line content.
+rules runs a rule pack: a TOML file of named patterns with a severity and a
+description (see rules/secrets.toml), or the name of a built-in preset. Matching
+lines are tagged with the ids of the rules that found them and the highest severity
+among them; --fail-on turns the severities into the exit status. rules list shows the
+rules of each pack and rules lint checks pack files for mistakes.
+
+A directory FILE is searched recursively, skipping hidden files and paths listed
+in .gitignore files. Files ending in .gz are decompressed before searching.
+
+Matching:
+  -e, --query QUERY         Use QUERY as the search string (allows queries starting with -)
+      --file FILE           Search FILE (can be repeated)
+      --batch FILE          Run every query in FILE in one pass; each line is NAME: QUERY
+                            (plain text) and output lines are tagged with [NAME]
+      --preset NAME         Search with a built-in, versioned rule pack instead of QUERY:
+                            secrets finds AWS keys, private key headers, JWTs and API
+                            tokens, skipping low-entropy placeholders
+      --rules PACK          Search with the rules of a rule pack file (or preset name)
+                            instead of QUERY, like rules run
+      --fail-on LEVEL       With a rule pack, exit with 1 only if a match has at least
+                            severity LEVEL: warning (low), error (high) or a severity
+                            (info, low, medium, high, critical)
+      --baseline FILE       With a rule pack, only report findings not listed in FILE; if
+                            FILE does not exist, record every current finding in it
+  -i, --ignore-case         Ignore case (also enabled by the IGNORE_CASE environment variable);
+                            plain queries use Unicode case folding, so straße matches STRASSE
+                            and accented letters match in composed or decomposed form
+  -E, --regex               Treat QUERY as a regular expression
+  -w, --word-regexp         Only match whole words
+      --identifier          Only match whole identifiers (id never matches identifier)
+      --subword             Match identifiers by their camelCase/snake_case words, ignoring
+                            case (user_id matches userId and getUserIdFromToken)
+      --fuzzy               Also match text within a small edit distance of QUERY (one
+                            typo per 4 characters, so cofig finds config); each line
+                            gets a similarity score and lines are sorted by it
+  -v, --invert-match        Select non-matching lines
+      --anchor-start N      Only report matches within the first N lines (or bytes, e.g. 512b)
+      --anchor-end N        Only report matches within the last N lines (or bytes)
+      --skip-comments       Ignore matches inside comments
+      --only-strings        Only report matches inside string literals
+                            (Rust, C-like and Python files; others are searched as usual)
+      --include GLOB        In directories, only search files matching GLOB, e.g. '*.rs'
+                            (can be repeated)
+      --exclude GLOB        In directories, skip files and directories matching GLOB
+                            (can be repeated)
+      --binary-files TYPE   How to treat files containing NUL bytes: binary (just report
+                            \"Binary file X matches\"), without-match (skip) or text
+  -a, --text                Search binary files as text (--binary-files text)
+      --strict-utf8         Fail on files that aren't valid UTF-8 instead of replacing
+                            invalid bytes with U+FFFD
+      --hidden              In directories, also search hidden files and directories
+      --no-ignore           In directories, don't skip paths listed in .gitignore files
+      --no-dedup            In directories, search every path of a hardlinked or
+                            bind-mounted file instead of only the first one found
+      --io-backend TYPE     How to read files: std (default) or io-uring (Linux 5.6+; reads
+                            small files in batches, useful when scanning many small files)
+
+Output:
+  -c, --count               Print the number of matching lines per file
+  -o, --only-matching       Print only the matched parts of each line, one per line
+  -b, --byte-offset         Print the byte offset of each matching line after its line
+                            number (with -o, the offset of each match)
+  -m, --max-count N         Stop reading each file after N matching lines
+  -q, --quiet               Print nothing; exit with 0 if a match was found, 1 if not,
+                            2 on errors (stops at the first match)
+  -l, --files-with-matches  Only print the names of files containing a match
+  -L, --files-without-match Only print the names of files without a match
+  -A, --after-context N     Print N lines after each match
+  -B, --before-context N    Print N lines before each match
+  -C, --context N           Print N lines before and after each match
+      --no-merge-context    Keep overlapping context windows as separate hunks
+      --color=WHEN          Highlight matches: auto, always or never
+      --output FORMAT       Output format: text or json
+      --ascii-output        Escape non-ASCII and control characters as \\xNN or \\u{...}
+      --redact REGEX        Print *** instead of the parts of lines matching REGEX, e.g.
+                            e-mail addresses or tokens (can be repeated)
+      --sample P            Report a random fraction P of the matches
+      --sample-n N          Report a uniform random sample of N matches
+      --progress-json       Print progress events as JSON on stderr
+      --max-results N       Stop after N matching lines in total and say the results
+                            were truncated
+      --stats               Print a summary (files, matching lines, time) at the end
+      --scope               Show the enclosing function (Rust fn, Python def) or
+                            Markdown heading of each match
+      --blame               Show the commit, author and date that last changed each
+                            matching line (files in a git repository only)
+
+History:
+      --git-history[=RANGE] Search every version of the files in the git history
+                            (default range HEAD); FILE arguments filter paths
+
+Standard input:
+      --label NAME          Show NAME instead of (standard input) as the file name
+      --split-on REGEX      Split standard input into sources: each line matching REGEX
+                            starts a new source named by its first capture group (or the
+                            matched text) and is reported like a separate file,
+                            e.g. --split-on '^==> (.*) <==$'
+
+Replacing:
+      --replace TEXT        Print the input with every match replaced by TEXT
+      --in-place            With --replace, rewrite the files instead of printing
+
+Other:
+  -j, --threads N           Number of files to search in parallel
+      --nice[=N]            Lower the scheduling priority of the search by N (1-19,
+                            default 10), like nice(1)
+      --max-cpu PERCENT     Let each search thread work at most PERCENT of the time,
+                            pausing between files, so background scans stay out of the way
+      --follow              Keep watching FILE and print new matching lines as they are
+                            appended, like tail -f; stop with Ctrl-C
+      --estimate            Don't search; sample a few files and estimate the time and
+                            number of matching lines of the full search
+      --no-config           Don't read defaults from ~/.minigreprc or the nearest
+                            .minigreprc in the current directory or its parents
+  -h, --help                Print this help
+  -V, --version             Print the version
+
+Defaults for ignore_case, color, output, context, before_context, after_context and
+hidden can be set as NAME = VALUE lines in ~/.minigreprc or a project .minigreprc;
+the project file overrides the user file and command line options override both.
+
+Exit status is 0 if a match was found, 1 if not, and 2 if an error occurred.
+With --fail-on it is 1 if a match reached the given severity and 0 otherwise.
+For compare it is 0 if both directories have the same matches and 1 if they differ.
+";
+
+pub fn version() -> String {
+    format!("minigrep {}", env!("CARGO_PKG_VERSION"))
+}
+
+#[derive(Debug)]
+pub enum Command {
+    // Config 比另外两个变体大得多，放在 Box 里
+    Search(Box<Config>),
+    // minigrep compare DIR_A DIR_B --query Q，file_paths 是两个目录
+    Compare(Box<Config>),
+    // rules list / rules lint，rules run 就是使用 --rules 的搜索
+    Rules(RulesCommand),
+    Help,
+    Version,
+}
+
+// 第一个参数是程序名
+pub fn parse(args: impl Iterator<Item = String>) -> Result<Command, AppError> {
+    parse_with(args, Environment::current())
+}
+
+// 环境变量和配置文件用 environment 中给出的，见 Config::build_with
+pub fn parse_with(
+    args: impl Iterator<Item = String>,
+    environment: Environment,
+) -> Result<Command, AppError> {
+    let args: Vec<String> = args.collect();
+    // --help 和 --version 优先于其他选项，即使其他参数有错误也能正常显示帮助
+    for arg in args.iter().skip(1).take_while(|arg| *arg != "--") {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-V" | "--version" => return Ok(Command::Version),
+            _ => {}
+        }
+    }
+    // 只有用 --query 给出查询时 compare 才是子命令，否则 minigrep compare FILE 仍然是搜索 compare 这个词
+    let explicit_query = args
+        .iter()
+        .any(|arg| arg == "-e" || arg == "--query" || arg.starts_with("--query="));
+    if args.get(1).is_some_and(|arg| arg == "compare") && explicit_query {
+        let mut args = args.into_iter();
+        let program = args.next();
+        args.next();
+        return Config::build_with(program.into_iter().chain(args), environment)
+            .map(|config| Command::Compare(Box::new(config)));
+    }
+    let rules_action = args
+        .get(2)
+        .is_some_and(|arg| matches!(arg.as_str(), "list" | "lint" | "run"));
+    if args.get(1).is_some_and(|arg| arg == "rules") && rules_action && !explicit_query {
+        let mut args = args.into_iter();
+        let program = args.next().unwrap_or_default();
+        args.next();
+        return parse_rules(program, args, environment);
+    }
+    Config::build_with(args.into_iter(), environment)
+        .map(|config| Command::Search(Box::new(config)))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RulesCommand {
+    // 规则包文件或者内置预设的名字，没有给出时列出所有内置预设
+    List(Vec<String>),
+    // 要检查的规则包文件
+    Lint(Vec<String>),
+}
+
+// minigrep rules list [PACK]...、rules lint FILE...、rules run PACK [OPTIONS] [FILE]...
+// 和 compare 一样，只有第二个参数是这三个动作之一时 rules 才是子命令，
+// 要在名为 list 的文件中搜索 rules 这个词，用 --query rules list
+fn parse_rules(
+    program: String,
+    mut args: std::vec::IntoIter<String>,
+    environment: Environment,
+) -> Result<Command, AppError> {
+    let action = args.next().unwrap_or_default();
+    let rest: Vec<String> = args.collect();
+    if action == "run" {
+        let mut rest = rest.into_iter();
+        let Some(pack) = rest.next() else {
+            return Err(CliError::MissingValue(String::from("rules run")).into());
+        };
+        let args = [program, String::from("--rules"), pack]
+            .into_iter()
+            .chain(rest);
+        return Config::build_with(args, environment)
+            .map(|config| Command::Search(Box::new(config)));
+    }
+    if let Some(option) = rest.iter().find(|arg| arg.starts_with('-')) {
+        return Err(CliError::UnknownOption(option.clone()).into());
+    }
+    if action == "lint" && rest.is_empty() {
+        return Err(CliError::MissingValue(String::from("rules lint")).into());
+    }
+    Ok(Command::Rules(match action.as_str() {
+        "list" => RulesCommand::List(rest),
+        _ => RulesCommand::Lint(rest),
+    }))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CliError {
+    UnknownOption(String),
+    MissingValue(String),
+    // 开关类的选项不接受 --name=value 的写法
+    UnexpectedValue(String),
+    InvalidValue {
+        option: String,
+        value: String,
+        reason: String,
+    },
+    // 互相冲突的选项组合
+    Conflict(&'static str),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CliError::UnknownOption(option) => {
+                write!(f, "unknown option '{option}' (see --help)")
+            }
+            CliError::MissingValue(option) => write!(f, "option '{option}' needs a value"),
+            CliError::UnexpectedValue(option) => {
+                write!(f, "option '{option}' doesn't take a value")
+            }
+            CliError::InvalidValue {
+                option,
+                value,
+                reason,
+            } => write!(f, "invalid value '{value}' for '{option}': {reason}"),
+            CliError::Conflict(reason) => write!(f, "{reason}"),
+        }
+    }
+}
+
+impl Error for CliError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Arg {
+    // 选项的名字，例如 -i、--context
+    Option(String),
+    Positional(String),
+}
+
+// 按顺序读取参数，遇到带值的选项时由调用方通过 value / parse 取出它的值
+pub struct Args<I: Iterator<Item = String>> {
+    args: I,
+    // 遇到 -- 之后，剩下的参数都是位置参数
+    only_positional: bool,
+    current: String,
+    // --name=value 写法中的值
+    inline: Option<String>,
+}
+
+impl<I: Iterator<Item = String>> Args<I> {
+    pub fn new(args: I) -> Args<I> {
+        Args {
+            args,
+            only_positional: false,
+            current: String::new(),
+            inline: None,
+        }
+    }
+
+    pub fn next_arg(&mut self) -> Result<Option<Arg>, CliError> {
+        // 上一个选项没有取走内联的值，说明它是一个开关
+        if self.inline.take().is_some() {
+            return Err(CliError::UnexpectedValue(self.current.clone()));
+        }
+        let Some(arg) = self.args.next() else {
+            return Ok(None);
+        };
+        if self.only_positional || arg == "-" || !arg.starts_with('-') {
+            return Ok(Some(Arg::Positional(arg)));
+        }
+        if arg == "--" {
+            self.only_positional = true;
+            return self.next_arg();
+        }
+        match arg.split_once('=') {
+            Some((name, value)) if arg.starts_with("--") => {
+                self.current = name.to_string();
+                self.inline = Some(value.to_string());
+            }
+            _ => self.current = arg,
+        }
+        Ok(Some(Arg::Option(self.current.clone())))
+    }
+
+    // 当前选项的值：--name=value 中的 value，或者紧跟在选项后面的参数
+    pub fn value(&mut self) -> Result<String, CliError> {
+        self.inline
+            .take()
+            .or_else(|| self.args.next())
+            .ok_or_else(|| CliError::MissingValue(self.current.clone()))
+    }
+
+    // 可选的值只能用 --name=value 的写法给出
+    pub fn optional_value(&mut self) -> Option<String> {
+        self.inline.take()
+    }
+
+    pub fn parse_with<T, E: fmt::Display>(
+        &mut self,
+        parse: impl FnOnce(&str) -> Result<T, E>,
+    ) -> Result<T, CliError> {
+        let value = self.value()?;
+        parse(&value).map_err(|reason| CliError::InvalidValue {
+            option: self.current.clone(),
+            reason: reason.to_string(),
+            value,
+        })
+    }
+
+    // 可选的值（--name=value）存在时才解析，没有给出时返回 None
+    pub fn parse_optional_with<T, E: fmt::Display>(
+        &mut self,
+        parse: impl FnOnce(&str) -> Result<T, E>,
+    ) -> Result<Option<T>, CliError> {
+        let Some(value) = self.optional_value() else {
+            return Ok(None);
+        };
+        parse(&value)
+            .map(Some)
+            .map_err(|reason| CliError::InvalidValue {
+                option: self.current.clone(),
+                reason: reason.to_string(),
+                value,
+            })
+    }
+
+    pub fn parse<T>(&mut self) -> Result<T, CliError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        self.parse_with(str::parse)
+    }
+}
+
+#[cfg(test)]
+mod cli_tests {
+    use super::*;
+    use crate::output::ColorChoice;
+
+    fn parse_args(list: &[&str]) -> Result<Command, AppError> {
+        parse_with(list.iter().map(|s| s.to_string()), Environment::default())
+    }
+
+    fn config(list: &[&str]) -> Config {
+        match parse_args(list) {
+            Ok(Command::Search(config)) => *config,
+            other => panic!("expected a search, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_help_and_version() {
+        assert!(matches!(
+            parse_args(&["minigrep", "--help"]),
+            Ok(Command::Help)
+        ));
+        // 其他参数有错误时也显示帮助
+        assert!(matches!(
+            parse_args(&["minigrep", "--bogus", "-h"]),
+            Ok(Command::Help)
+        ));
+        assert!(matches!(
+            parse_args(&["minigrep", "-V"]),
+            Ok(Command::Version)
+        ));
+        assert!(version().starts_with("minigrep "));
+        assert!(matches!(
+            parse_args(&["minigrep", "compare", "a", "b", "--query", "x"]),
+            Ok(Command::Compare(config)) if config.file_paths == ["a", "b"] && config.query == "x"
+        ));
+        // 没有 --query 时 compare 是要搜索的词
+        assert!(matches!(
+            parse_args(&["minigrep", "compare", "a"]),
+            Ok(Command::Search(config)) if config.query == "compare"
+        ));
+        // -- 之后的 --help 是普通的查询字符串
+        assert_eq!(config(&["minigrep", "--", "--help"]).query, "--help");
+    }
+
+    #[test]
+    fn test_rules_command() {
+        assert!(matches!(
+            parse_args(&["minigrep", "rules", "list"]),
+            Ok(Command::Rules(RulesCommand::List(packs))) if packs.is_empty()
+        ));
+        assert!(matches!(
+            parse_args(&["minigrep", "rules", "lint", "a.toml", "b.toml"]),
+            Ok(Command::Rules(RulesCommand::Lint(files))) if files == ["a.toml", "b.toml"]
+        ));
+        assert!(parse_args(&["minigrep", "rules", "lint"]).is_err());
+        assert!(parse_args(&["minigrep", "rules", "list", "-x"]).is_err());
+        let run = config(&["minigrep", "rules", "run", "secrets", "-c", "src"]);
+        assert_eq!(run.rules.unwrap().name, "secrets");
+        assert!(run.count);
+        assert_eq!(run.file_paths, ["src"]);
+        // 其他情况下 rules 仍然是要搜索的词
+        assert_eq!(config(&["minigrep", "rules", "notes.txt"]).query, "rules");
+        assert_eq!(
+            config(&["minigrep", "--query", "rules", "list"]).file_paths,
+            ["list"]
+        );
+    }
+
+    #[test]
+    fn test_query_and_files() {
+        let explicit = config(&["minigrep", "--query", "-x", "--file", "a.txt", "b.txt"]);
+        assert_eq!(explicit.query, "-x");
+        assert_eq!(explicit.file_paths, vec!["a.txt", "b.txt"]);
+        let files = config(&["minigrep", "-e", "x", "--file", "a", "--file=b"]);
+        assert_eq!(files.file_paths, vec!["a", "b"]);
+        let after_dashes = config(&["minigrep", "--color=always", "--", "-v", "-"]);
+        assert_eq!(after_dashes.color, ColorChoice::Always);
+        assert!(!after_dashes.invert);
+        assert_eq!(after_dashes.query, "-v");
+        assert_eq!(after_dashes.file_paths, vec!["-"]);
+        let history = config(&["minigrep", "--git-history", "x"]);
+        assert_eq!(history.git_history.as_deref(), Some("HEAD"));
+        assert_eq!(history.query, "x");
+        let history = config(&["minigrep", "--git-history=v1..", "x"]);
+        assert_eq!(history.git_history.as_deref(), Some("v1.."));
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = |list: &[&str]| parse_args(list).unwrap_err().to_string();
+        assert_eq!(error(&["minigrep"]), "Didn't get a query string");
+        assert_eq!(
+            error(&["minigrep", "--bogus", "x"]),
+            "unknown option '--bogus' (see --help)"
+        );
+        assert_eq!(error(&["minigrep", "x", "-A"]), "option '-A' needs a value");
+        assert_eq!(
+            error(&["minigrep", "-A", "many", "x"]),
+            "invalid value 'many' for '-A': Context options need a non-negative line count"
+        );
+        assert_eq!(
+            error(&["minigrep", "--anchor-start=10X", "x"]),
+            "invalid value '10X' for '--anchor-start': Anchor must be a line count or a byte size such as 512b"
+        );
+        assert_eq!(
+            error(&["minigrep", "--count=yes", "x"]),
+            "option '--count' doesn't take a value"
+        );
+        assert!(matches!(
+            parse_args(&["minigrep", "--in-place", "x"]),
+            Err(AppError::Usage(CliError::Conflict(_)))
+        ));
+        assert!(matches!(
+            parse_args(&["minigrep", "-i"]),
+            Err(AppError::MissingQuery)
+        ));
+    }
+}
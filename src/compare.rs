@@ -0,0 +1,220 @@
+// 比较两个目录中的匹配（minigrep compare DIR_A DIR_B --query Q）：分别搜索两棵目录树，
+// 按相对路径和行的内容配对，报告只在 A 中、只在 B 中和两边都有的匹配行。
+// 不比较行号，所以文件中插入或删除了别的行不影响结果；同一内容出现多次时按次数配对
+use crate::output::json_string;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Side {
+    OnlyA,
+    OnlyB,
+    Both,
+}
+
+impl Side {
+    // 与 diff 的习惯一致：< 表示左边（A），> 表示右边（B）
+    fn marker(&self) -> char {
+        match self {
+            Side::OnlyA => '<',
+            Side::OnlyB => '>',
+            Side::Both => '=',
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Side::OnlyA => "a",
+            Side::OnlyB => "b",
+            Side::Both => "both",
+        }
+    }
+}
+
+// 一个匹配行
+#[derive(Debug, Clone, PartialEq)]
+pub struct Found {
+    pub line_number: usize,
+    pub line: String,
+}
+
+// 比较结果中的一行，两边都有时行号取 A 中的
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompareLine {
+    pub side: Side,
+    pub path: String,
+    pub line_number: usize,
+    pub line: String,
+}
+
+// minigrep compare 的结果，input_errors 是读不了、没有参加比较的文件数
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Comparison {
+    pub lines: Vec<CompareLine>,
+    pub input_errors: usize,
+}
+
+// 相对路径到这个文件中所有匹配行（按行号排列）
+pub type Matches = BTreeMap<String, Vec<Found>>;
+
+// 结果按路径排序，同一个文件中先按 A 的顺序列出 A 的行，再按 B 的顺序列出只在 B 中的行
+pub fn compare(a: &Matches, b: &Matches) -> Vec<CompareLine> {
+    let paths: BTreeSet<&String> = a.keys().chain(b.keys()).collect();
+    let mut out = Vec::new();
+    for path in paths {
+        let (in_a, in_b) = (
+            a.get(path).map_or(&[][..], Vec::as_slice),
+            b.get(path).map_or(&[][..], Vec::as_slice),
+        );
+        // B 中每种内容还没有配对的次数
+        let mut unpaired: HashMap<&str, usize> = HashMap::new();
+        for found in in_b {
+            *unpaired.entry(&found.line).or_default() += 1;
+        }
+        // 配对成功的次数，之后列出 B 的行时跳过这么多个
+        let mut paired: HashMap<&str, usize> = HashMap::new();
+        let mut push = |side, found: &Found| {
+            out.push(CompareLine {
+                side,
+                path: path.clone(),
+                line_number: found.line_number,
+                line: found.line.clone(),
+            })
+        };
+        for found in in_a {
+            match unpaired.get_mut(found.line.as_str()).filter(|n| **n > 0) {
+                Some(n) => {
+                    *n -= 1;
+                    *paired.entry(&found.line).or_default() += 1;
+                    push(Side::Both, found);
+                }
+                None => push(Side::OnlyA, found),
+            }
+        }
+        for found in in_b {
+            match paired.get_mut(found.line.as_str()).filter(|n| **n > 0) {
+                Some(n) => *n -= 1,
+                None => push(Side::OnlyB, found),
+            }
+        }
+    }
+    out
+}
+
+impl CompareLine {
+    pub fn json(&self) -> String {
+        format!(
+            "{{\"side\":\"{}\",\"file\":{},\"line_number\":{},\"line\":{}}}",
+            self.side.name(),
+            json_string(&self.path),
+            self.line_number,
+            json_string(&self.line)
+        )
+    }
+}
+
+// < 路径:行号:内容
+impl fmt::Display for CompareLine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {}:{}:{}",
+            self.side.marker(),
+            self.path,
+            self.line_number,
+            self.line
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CompareSummary {
+    pub only_a: usize,
+    pub only_b: usize,
+    pub both: usize,
+}
+
+impl CompareSummary {
+    pub fn of(lines: &[CompareLine]) -> CompareSummary {
+        let mut summary = CompareSummary::default();
+        for line in lines {
+            match line.side {
+                Side::OnlyA => summary.only_a += 1,
+                Side::OnlyB => summary.only_b += 1,
+                Side::Both => summary.both += 1,
+            }
+        }
+        summary
+    }
+
+    pub fn differs(&self) -> bool {
+        self.only_a > 0 || self.only_b > 0
+    }
+}
+
+impl fmt::Display for CompareSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} only in A, {} only in B, {} in both",
+            self.only_a, self.only_b, self.both
+        )
+    }
+}
+
+#[cfg(test)]
+mod compare_tests {
+    use super::*;
+
+    fn matches(files: &[(&str, &[(usize, &str)])]) -> Matches {
+        files
+            .iter()
+            .map(|(path, lines)| {
+                let lines = lines
+                    .iter()
+                    .map(|&(line_number, line)| Found {
+                        line_number,
+                        line: line.to_string(),
+                    })
+                    .collect();
+                (path.to_string(), lines)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_compare() {
+        let a = matches(&[
+            (
+                "app.conf",
+                &[(1, "port = 80"), (4, "debug = true"), (9, "debug = true")],
+            ),
+            ("old.conf", &[(2, "port = 8080")]),
+        ]);
+        let b = matches(&[
+            ("app.conf", &[(3, "debug = true"), (5, "port = 443")]),
+            ("new.conf", &[(1, "port = 9090")]),
+        ]);
+        let lines: Vec<String> = compare(&a, &b).iter().map(|l| l.to_string()).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "< app.conf:1:port = 80",
+                "= app.conf:4:debug = true",
+                // 同样的内容 A 中有两行，B 中只有一行
+                "< app.conf:9:debug = true",
+                "> app.conf:5:port = 443",
+                "> new.conf:1:port = 9090",
+                "< old.conf:2:port = 8080",
+            ]
+        );
+        let summary = CompareSummary::of(&compare(&a, &b));
+        assert_eq!(summary.to_string(), "3 only in A, 2 only in B, 1 in both");
+        assert!(summary.differs());
+        assert!(!CompareSummary::of(&compare(&a, &a)).differs());
+        assert_eq!(
+            compare(&a, &b)[1].json(),
+            r#"{"side":"both","file":"app.conf","line_number":4,"line":"debug = true"}"#
+        );
+    }
+}
@@ -0,0 +1,229 @@
+// 配置文件：用户目录下的 ~/.minigreprc 和项目中的 .minigreprc（从当前目录往上找到的第一个），
+// 设置常用选项的默认值。INI/TOML 风格，每行一个 名字 = 值，# 之后是注释，字符串可以加引号：
+//
+//     ignore_case = true
+//     color = "always"
+//     context = 2
+//
+// 优先级从高到低：命令行、项目配置、用户配置。--no-config 时不读取配置文件
+use crate::context_value;
+use crate::error::AppError;
+use crate::output::{ColorChoice, OutputFormat};
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const FILE_NAME: &str = ".minigreprc";
+
+// 配置文件中的默认值，None 表示没有设置
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Defaults {
+    pub ignore_case: Option<bool>,
+    pub color: Option<ColorChoice>,
+    pub output: Option<OutputFormat>,
+    pub before_context: Option<usize>,
+    pub after_context: Option<usize>,
+    pub hidden: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigFileError {
+    // 从 1 开始的行号
+    pub line: usize,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for ConfigFileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.reason)
+    }
+}
+
+impl Error for ConfigFileError {}
+
+impl Defaults {
+    pub fn parse(text: &str) -> Result<Defaults, ConfigFileError> {
+        let mut defaults = Defaults::default();
+        for (index, line) in text.lines().enumerate() {
+            let error = |reason| ConfigFileError {
+                line: index + 1,
+                reason,
+            };
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            if trimmed.starts_with('[') {
+                return Err(error("sections are not supported"));
+            }
+            let (name, value) = trimmed
+                .split_once('=')
+                .ok_or_else(|| error("expected NAME = VALUE"))?;
+            let value = unquote(value.trim()).map_err(error)?;
+            // ignore-case 和 ignore_case 都可以
+            match name.trim().replace('-', "_").as_str() {
+                "ignore_case" => defaults.ignore_case = Some(parse_bool(value).map_err(error)?),
+                "color" => defaults.color = Some(value.parse().map_err(error)?),
+                "output" => defaults.output = Some(value.parse().map_err(error)?),
+                "context" => {
+                    let lines = context_value(value).map_err(error)?;
+                    defaults.before_context = Some(lines);
+                    defaults.after_context = Some(lines);
+                }
+                "before_context" => {
+                    defaults.before_context = Some(context_value(value).map_err(error)?)
+                }
+                "after_context" => {
+                    defaults.after_context = Some(context_value(value).map_err(error)?)
+                }
+                "hidden" => defaults.hidden = Some(parse_bool(value).map_err(error)?),
+                _ => return Err(error("unknown setting")),
+            }
+        }
+        Ok(defaults)
+    }
+
+    // other 中设置了的值覆盖这里的
+    pub fn merge(self, other: Defaults) -> Defaults {
+        Defaults {
+            ignore_case: other.ignore_case.or(self.ignore_case),
+            color: other.color.or(self.color),
+            output: other.output.or(self.output),
+            before_context: other.before_context.or(self.before_context),
+            after_context: other.after_context.or(self.after_context),
+            hidden: other.hidden.or(self.hidden),
+        }
+    }
+}
+
+// 带引号的值去掉引号，不带引号的值去掉行尾注释
+fn unquote(value: &str) -> Result<&str, &'static str> {
+    match value.chars().next() {
+        Some(quote @ ('"' | '\'')) => {
+            let rest = &value[1..];
+            let end = rest.find(quote).ok_or("missing closing quote")?;
+            match rest[end + 1..].trim_start() {
+                "" => Ok(&rest[..end]),
+                after if after.starts_with('#') => Ok(&rest[..end]),
+                _ => Err("unexpected text after the closing quote"),
+            }
+        }
+        _ => Ok(value.split('#').next().unwrap_or_default().trim_end()),
+    }
+}
+
+fn parse_bool(value: &str) -> Result<bool, &'static str> {
+    match value {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => Err("expected true or false"),
+    }
+}
+
+// 依次是用户配置和项目配置，交给 load_from 时后读的覆盖先读的
+pub fn paths() -> Vec<PathBuf> {
+    let project = env::current_dir().ok().and_then(|dir| find_project(&dir));
+    user_path().into_iter().chain(project).collect()
+}
+
+// 文件不存在时跳过
+
+pub fn load_from(paths: impl IntoIterator<Item = PathBuf>) -> Result<Defaults, AppError> {
+    let mut defaults = Defaults::default();
+    let mut loaded: Vec<PathBuf> = Vec::new();
+    for path in paths {
+        // 当前目录在用户目录下面又没有项目配置时，找到的就是用户配置，不需要读两次
+        if loaded.contains(&path) {
+            continue;
+        }
+        let name = path.display().to_string();
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(source) => return Err(AppError::io(name, source)),
+        };
+        let file =
+            Defaults::parse(&text).map_err(|source| AppError::ConfigFile { path: name, source })?;
+        defaults = defaults.merge(file);
+        loaded.push(path);
+    }
+    Ok(defaults)
+}
+
+fn user_path() -> Option<PathBuf> {
+    let home = env::var_os("HOME").or_else(|| env::var_os("USERPROFILE"))?;
+    Some(Path::new(&home).join(FILE_NAME))
+}
+
+// 从 dir 开始往上找第一个 .minigreprc
+fn find_project(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|dir| dir.join(FILE_NAME))
+        .find(|path| path.is_file())
+}
+
+#[cfg(test)]
+mod config_file_tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let text = "# defaults\nignore-case = true\ncolor = \"always\"  # for less -R\n\
+                    context = 2\nafter_context = '5'\nhidden = false\n";
+        assert_eq!(
+            Defaults::parse(text).unwrap(),
+            Defaults {
+                ignore_case: Some(true),
+                color: Some(ColorChoice::Always),
+                output: None,
+                before_context: Some(2),
+                after_context: Some(5),
+                hidden: Some(false),
+            }
+        );
+        let error = |text| Defaults::parse(text).unwrap_err().to_string();
+        assert_eq!(
+            error("color = always\nthreads = 4"),
+            "line 2: unknown setting"
+        );
+        assert_eq!(error("ignore_case"), "line 1: expected NAME = VALUE");
+        assert_eq!(error("ignore_case = yes"), "line 1: expected true or false");
+        assert_eq!(
+            error("color = sometimes"),
+            "line 1: Color must be auto, always or never"
+        );
+        assert_eq!(error("output = \"json"), "line 1: missing closing quote");
+        assert_eq!(error("[minigrep]"), "line 1: sections are not supported");
+    }
+
+    // 项目配置覆盖用户配置，没有设置的项保留用户配置的值
+    #[test]
+    fn test_load_from() {
+        let dir = env::temp_dir().join("minigrep-config-file");
+        let project = dir.join("project/src");
+        fs::create_dir_all(&project).unwrap();
+        let user = dir.join("user-minigreprc");
+        fs::write(&user, "ignore_case = true\ncontext = 3\n").unwrap();
+        fs::write(dir.join("project").join(FILE_NAME), "context = 1\n").unwrap();
+
+        let found = find_project(&project).unwrap();
+        assert_eq!(found, dir.join("project").join(FILE_NAME));
+        let defaults = load_from([user.clone(), dir.join("missing"), found]).unwrap();
+        assert_eq!(defaults.ignore_case, Some(true));
+        assert_eq!(defaults.before_context, Some(1));
+        assert_eq!(defaults.after_context, Some(1));
+
+        fs::write(&user, "context = -1\n").unwrap();
+        let err = load_from([user.clone()]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "{}: line 1: Context options need a non-negative line count",
+                user.display()
+            )
+        );
+    }
+}
@@ -0,0 +1,120 @@
+// 程序的错误类型：调用方可以通过匹配枚举值区分"参数错误""文件读不了""正则写错了"等情况，
+// 而不是只能拿到一段文字
+use crate::cli::CliError;
+use crate::config_file::ConfigFileError;
+use crate::regex::RegexError;
+use crate::rules::RulePackError;
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum AppError {
+    MissingQuery,
+    // 需要真实文件的操作（例如 --in-place）没有给出文件，或者给的是标准输入
+    MissingFilePath,
+    // 其他命令行参数错误
+    Usage(CliError),
+    InvalidPattern(RegexError),
+    // 配置文件的内容有错误
+    ConfigFile {
+        path: String,
+        source: ConfigFileError,
+    },
+    // 规则包的内容有错误
+    RulePack {
+        path: String,
+        source: RulePackError,
+    },
+    // 读取或改写某个输入时出错
+    Io {
+        path: String,
+        source: io::Error,
+    },
+    // 写标准输出时出错
+    Output(io::Error),
+    // 调用 git 出错
+    Git(io::Error),
+}
+
+impl AppError {
+    pub fn io(path: impl Into<String>, source: io::Error) -> AppError {
+        AppError::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    // 管道的读取端已经关闭（例如输出交给 head，它读够了行数就退出了），
+    // 这时不是真正的错误，调用方应该安静地结束
+    pub fn is_broken_pipe(&self) -> bool {
+        matches!(self, AppError::Output(source) if source.kind() == io::ErrorKind::BrokenPipe)
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AppError::MissingQuery => write!(f, "Didn't get a query string"),
+            AppError::MissingFilePath => write!(f, "Didn't get a file path to edit"),
+            AppError::Usage(err) => write!(f, "{err}"),
+            AppError::InvalidPattern(err) => write!(f, "{err}"),
+            AppError::ConfigFile { path, source } => write!(f, "{path}: {source}"),
+            AppError::RulePack { path, source } => write!(f, "{path}: {source}"),
+            AppError::Io { path, source } => write!(f, "{path}: {source}"),
+            AppError::Output(source) => write!(f, "cannot write output: {source}"),
+            AppError::Git(source) => write!(f, "{source}"),
+        }
+    }
+}
+
+impl Error for AppError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AppError::Usage(err) => Some(err),
+            AppError::InvalidPattern(err) => Some(err),
+            AppError::ConfigFile { source, .. } => Some(source),
+            AppError::RulePack { source, .. } => Some(source),
+            AppError::Io { source, .. } | AppError::Output(source) | AppError::Git(source) => {
+                Some(source)
+            }
+            AppError::MissingQuery | AppError::MissingFilePath => None,
+        }
+    }
+}
+
+// 实现 From 之后可以直接用 ? 把这些错误转换成 AppError
+impl From<CliError> for AppError {
+    fn from(err: CliError) -> AppError {
+        AppError::Usage(err)
+    }
+}
+
+impl From<RegexError> for AppError {
+    fn from(err: RegexError) -> AppError {
+        AppError::InvalidPattern(err)
+    }
+}
+
+#[cfg(test)]
+mod error_tests {
+    use super::*;
+
+    #[test]
+    fn test_display_and_source() {
+        let err = AppError::io("a.txt", io::Error::from(io::ErrorKind::NotFound));
+        assert!(err.to_string().starts_with("a.txt: "));
+        assert!(err.source().is_some());
+        assert!(matches!(err, AppError::Io { ref path, .. } if path == "a.txt"));
+
+        let err: AppError = CliError::UnknownOption("--x".into()).into();
+        assert_eq!(err.to_string(), "unknown option '--x' (see --help)");
+        assert!(AppError::MissingQuery.source().is_none());
+
+        let err = AppError::Output(io::Error::from(io::ErrorKind::BrokenPipe));
+        assert!(err.is_broken_pipe());
+        assert!(
+            !AppError::io("a.txt", io::Error::from(io::ErrorKind::BrokenPipe)).is_broken_pipe()
+        );
+    }
+}
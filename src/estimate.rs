@@ -0,0 +1,140 @@
+// 运行前的估算（--estimate）：统计要搜索的文件数和总大小，抽几个文件实际搜索一遍，
+// 用抽样得到的吞吐量和匹配密度推算整次搜索的耗时和结果数量，数据量很大时可以先看看值不值得跑
+use crate::units::{Bytes, Duration};
+use std::fmt;
+use std::time;
+
+// 最多抽样的文件数
+pub const SAMPLE_FILES: usize = 5;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Estimate {
+    pub files: usize,
+    pub total_bytes: u64,
+    pub sampled_files: usize,
+    pub sampled_bytes: u64,
+    pub sampled_matches: usize,
+    pub sample_elapsed: time::Duration,
+    pub threads: usize,
+    // 读不了、没有计入估算的文件数
+    pub input_errors: usize,
+}
+
+// 在 n 个文件中均匀地选出最多 SAMPLE_FILES 个，每次结果相同
+pub fn sample_indices(n: usize) -> Vec<usize> {
+    let k = n.min(SAMPLE_FILES);
+    (0..k).map(|i| i * n / k).collect()
+}
+
+impl Estimate {
+    // 按抽样的吞吐量推算，假设多个线程能同时满负荷工作
+    pub fn time(&self) -> Option<time::Duration> {
+        if self.sampled_bytes == 0 {
+            return None;
+        }
+        let secs_per_byte = self.sample_elapsed.as_secs_f64() / self.sampled_bytes as f64;
+        let threads = self.threads.clamp(1, self.files.max(1));
+        Some(time::Duration::from_secs_f64(
+            secs_per_byte * self.total_bytes as f64 / threads as f64,
+        ))
+    }
+
+    // 按抽样中每字节的匹配行数推算
+    pub fn matches(&self) -> Option<u64> {
+        if self.sampled_bytes == 0 {
+            return None;
+        }
+        let density = self.sampled_matches as f64 / self.sampled_bytes as f64;
+        Some((density * self.total_bytes as f64).round() as u64)
+    }
+
+    // 与 --stats 的 JSON 格式一致，用 type 字段区分，无法估算的值为 null
+    pub fn json(&self) -> String {
+        let or_null = |value: Option<String>| value.unwrap_or_else(|| String::from("null"));
+        format!(
+            "{{\"type\":\"estimate\",\"files\":{},\"total_bytes\":{},\"input_errors\":{},\"sampled_files\":{},\"sampled_bytes\":{},\"sampled_matches\":{},\"estimated_secs\":{},\"estimated_matches\":{}}}",
+            self.files,
+            self.total_bytes,
+            self.input_errors,
+            self.sampled_files,
+            self.sampled_bytes,
+            self.sampled_matches,
+            or_null(self.time().map(|t| format!("{:.3}", t.as_secs_f64()))),
+            or_null(self.matches().map(|m| m.to_string()))
+        )
+    }
+}
+
+impl fmt::Display for Estimate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} files, {}", self.files, Bytes(self.total_bytes))?;
+        match self.input_errors {
+            0 => writeln!(f)?,
+            1 => writeln!(f, " (1 file could not be read)")?,
+            n => writeln!(f, " ({n} files could not be read)")?,
+        }
+        writeln!(
+            f,
+            "sampled {} files ({}) in {}: {} matching lines",
+            self.sampled_files,
+            Bytes(self.sampled_bytes),
+            Duration(self.sample_elapsed),
+            self.sampled_matches
+        )?;
+        match (self.time(), self.matches()) {
+            (Some(time), Some(matches)) => write!(
+                f,
+                "estimated {} with {} threads, about {matches} matching lines",
+                Duration(time),
+                self.threads
+            ),
+            _ => write!(f, "nothing to sample, no estimate"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod estimate_tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_indices() {
+        assert_eq!(sample_indices(0), Vec::<usize>::new());
+        assert_eq!(sample_indices(3), vec![0, 1, 2]);
+        assert_eq!(sample_indices(100), vec![0, 20, 40, 60, 80]);
+    }
+
+    #[test]
+    fn test_estimate() {
+        let estimate = Estimate {
+            files: 100,
+            total_bytes: 10_000_000,
+            sampled_files: 5,
+            sampled_bytes: 500_000,
+            sampled_matches: 20,
+            sample_elapsed: time::Duration::from_millis(50),
+            threads: 4,
+            input_errors: 0,
+        };
+        // 每字节 0.1 微秒，10 MB 用 1 秒，4 个线程 250 毫秒
+        assert_eq!(estimate.time(), Some(time::Duration::from_millis(250)));
+        assert_eq!(estimate.matches(), Some(400));
+        assert!(estimate
+            .to_string()
+            .ends_with("estimated 250 ms with 4 threads, about 400 matching lines"));
+        assert!(estimate
+            .json()
+            .ends_with(r#""estimated_secs":0.250,"estimated_matches":400}"#));
+        let unreadable = Estimate {
+            input_errors: 2,
+            ..estimate
+        };
+        assert!(unreadable
+            .to_string()
+            .starts_with("100 files, 9.5 MiB (2 files could not be read)\n"));
+        assert!(unreadable.json().contains(r#""input_errors":2,"#));
+        let empty = Estimate::default();
+        assert_eq!(empty.matches(), None);
+        assert!(empty.json().ends_with(r#""estimated_matches":null}"#));
+    }
+}
@@ -0,0 +1,293 @@
+// 忽略大小写时的 Unicode 折叠和规范化：查询和行都先转换成同一种形式再比较。
+// 逐个字符 to_lowercase 处理不了一个字符对应多个字符的情况（ß 和 SS、ﬁ 和 fi），
+// 同一个带重音的字母也有两种写法：预组合的 é，或者 e 加上组合用的重音符号（U+0301）。
+// 这里做完整的大小写折叠（Unicode CaseFolding 中常见的多字符映射），再把常见的拉丁、希腊、
+// 西里尔预组合字母分解成基本字母加组合符号，两种写法就得到相同的结果。
+// 土耳其语的 İ 折叠成 i 加组合点（U+0307），和 ı（无点 i）一样不等于普通的 i，与默认的 Unicode 规则一致
+use std::ops::Range;
+
+// 折叠后的查询
+pub fn fold(s: &str) -> String {
+    let mut folded = String::with_capacity(s.len());
+    for c in s.chars() {
+        fold_char(c, |f| folded.push(f));
+    }
+    folded
+}
+
+// 折叠后的行，同时记录折叠结果中每个字节来自原始行中哪个字符，用来把匹配位置映射回原始行
+pub struct Folded {
+    text: String,
+    // 每个字节对应原始字符的开始位置，最后一项对应原始行的末尾
+    offsets: Vec<usize>,
+}
+
+impl Folded {
+    pub fn new(line: &str) -> Folded {
+        let mut text = String::with_capacity(line.len());
+        let mut offsets = Vec::with_capacity(line.len() + 1);
+        for (i, c) in line.char_indices() {
+            fold_char(c, |f| {
+                text.push(f);
+                offsets.extend(std::iter::repeat_n(i, f.len_utf8()));
+            });
+        }
+        offsets.push(line.len());
+        Folded { text, offsets }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    // 原始行中从 at 开始的第一个匹配。query 要已经折叠过
+    pub fn find_at(&self, query: &str, at: usize) -> Option<Range<usize>> {
+        let mut from = self.offsets.partition_point(|&o| o < at);
+        loop {
+            let start = from + self.text[from..].find(query)?;
+            if let Some(span) = self.span(start..start + query.len()) {
+                return Some(span);
+            }
+            from = start + self.text[start..].chars().next().map_or(1, char::len_utf8);
+        }
+    }
+
+    // 折叠结果中的范围对应的原始范围。两端落在一个字符的折叠结果中间（例如 ß 折叠成的两个 s 之间），
+    // 或者落在字母和它的组合符号之间（cafe 不能匹配 café 的前一半）时没有对应的范围
+    pub fn span(&self, range: Range<usize>) -> Option<Range<usize>> {
+        (self.boundary(range.start) && self.boundary(range.end))
+            .then(|| self.offsets[range.start]..self.offsets[range.end])
+    }
+
+    fn boundary(&self, i: usize) -> bool {
+        if i == self.text.len() {
+            return true;
+        }
+        let starts_char = i == 0 || self.offsets[i - 1] != self.offsets[i];
+        starts_char && !self.text[i..].chars().next().is_some_and(is_combining)
+    }
+}
+
+fn fold_char(c: char, mut push: impl FnMut(char)) {
+    let push_decomposed = |c: char| match DECOMPOSITIONS.binary_search_by_key(&c, |d| d.0) {
+        Ok(i) => {
+            push(DECOMPOSITIONS[i].1);
+            push(DECOMPOSITIONS[i].2);
+        }
+        Err(_) => push(c),
+    };
+    match special_fold(c) {
+        Some(folded) => folded.chars().for_each(push_decomposed),
+        None => c.to_lowercase().for_each(push_decomposed),
+    }
+}
+
+// to_lowercase 不会处理、但大小写折叠时要处理的字符
+fn special_fold(c: char) -> Option<&'static str> {
+    let folded = match c {
+        'ß' | 'ẞ' => "ss",
+        'ſ' => "s",
+        'ς' => "σ",
+        'µ' => "μ",
+        'ŉ' => "ʼn",
+        'ﬀ' => "ff",
+        'ﬁ' => "fi",
+        'ﬂ' => "fl",
+        'ﬃ' => "ffi",
+        'ﬄ' => "ffl",
+        'ﬅ' | 'ﬆ' => "st",
+        _ => return None,
+    };
+    Some(folded)
+}
+
+// 组合用的附加符号：重音、分音符、变音符等，它们属于前面的字母
+fn is_combining(c: char) -> bool {
+    matches!(c,
+        '\u{0300}'..='\u{036F}'
+        | '\u{1AB0}'..='\u{1AFF}'
+        | '\u{1DC0}'..='\u{1DFF}'
+        | '\u{20D0}'..='\u{20FF}'
+        | '\u{FE20}'..='\u{FE2F}')
+}
+
+// 预组合的小写字母 -> 基本字母和组合符号，按字符排序以便二分查找。
+// 大写字母先转成小写再分解，所以只需要小写字母
+const DECOMPOSITIONS: [(char, char, char); 101] = [
+    ('à', 'a', '\u{0300}'),
+    ('á', 'a', '\u{0301}'),
+    ('â', 'a', '\u{0302}'),
+    ('ã', 'a', '\u{0303}'),
+    ('ä', 'a', '\u{0308}'),
+    ('å', 'a', '\u{030A}'),
+    ('ç', 'c', '\u{0327}'),
+    ('è', 'e', '\u{0300}'),
+    ('é', 'e', '\u{0301}'),
+    ('ê', 'e', '\u{0302}'),
+    ('ë', 'e', '\u{0308}'),
+    ('ì', 'i', '\u{0300}'),
+    ('í', 'i', '\u{0301}'),
+    ('î', 'i', '\u{0302}'),
+    ('ï', 'i', '\u{0308}'),
+    ('ñ', 'n', '\u{0303}'),
+    ('ò', 'o', '\u{0300}'),
+    ('ó', 'o', '\u{0301}'),
+    ('ô', 'o', '\u{0302}'),
+    ('õ', 'o', '\u{0303}'),
+    ('ö', 'o', '\u{0308}'),
+    ('ù', 'u', '\u{0300}'),
+    ('ú', 'u', '\u{0301}'),
+    ('û', 'u', '\u{0302}'),
+    ('ü', 'u', '\u{0308}'),
+    ('ý', 'y', '\u{0301}'),
+    ('ÿ', 'y', '\u{0308}'),
+    ('ā', 'a', '\u{0304}'),
+    ('ă', 'a', '\u{0306}'),
+    ('ą', 'a', '\u{0328}'),
+    ('ć', 'c', '\u{0301}'),
+    ('ĉ', 'c', '\u{0302}'),
+    ('ċ', 'c', '\u{0307}'),
+    ('č', 'c', '\u{030C}'),
+    ('ď', 'd', '\u{030C}'),
+    ('ē', 'e', '\u{0304}'),
+    ('ĕ', 'e', '\u{0306}'),
+    ('ė', 'e', '\u{0307}'),
+    ('ę', 'e', '\u{0328}'),
+    ('ě', 'e', '\u{030C}'),
+    ('ĝ', 'g', '\u{0302}'),
+    ('ğ', 'g', '\u{0306}'),
+    ('ġ', 'g', '\u{0307}'),
+    ('ģ', 'g', '\u{0327}'),
+    ('ĥ', 'h', '\u{0302}'),
+    ('ĩ', 'i', '\u{0303}'),
+    ('ī', 'i', '\u{0304}'),
+    ('ĭ', 'i', '\u{0306}'),
+    ('į', 'i', '\u{0328}'),
+    ('ĵ', 'j', '\u{0302}'),
+    ('ķ', 'k', '\u{0327}'),
+    ('ĺ', 'l', '\u{0301}'),
+    ('ļ', 'l', '\u{0327}'),
+    ('ľ', 'l', '\u{030C}'),
+    ('ń', 'n', '\u{0301}'),
+    ('ņ', 'n', '\u{0327}'),
+    ('ň', 'n', '\u{030C}'),
+    ('ō', 'o', '\u{0304}'),
+    ('ŏ', 'o', '\u{0306}'),
+    ('ő', 'o', '\u{030B}'),
+    ('ŕ', 'r', '\u{0301}'),
+    ('ŗ', 'r', '\u{0327}'),
+    ('ř', 'r', '\u{030C}'),
+    ('ś', 's', '\u{0301}'),
+    ('ŝ', 's', '\u{0302}'),
+    ('ş', 's', '\u{0327}'),
+    ('š', 's', '\u{030C}'),
+    ('ţ', 't', '\u{0327}'),
+    ('ť', 't', '\u{030C}'),
+    ('ũ', 'u', '\u{0303}'),
+    ('ū', 'u', '\u{0304}'),
+    ('ŭ', 'u', '\u{0306}'),
+    ('ů', 'u', '\u{030A}'),
+    ('ű', 'u', '\u{030B}'),
+    ('ų', 'u', '\u{0328}'),
+    ('ŵ', 'w', '\u{0302}'),
+    ('ŷ', 'y', '\u{0302}'),
+    ('ź', 'z', '\u{0301}'),
+    ('ż', 'z', '\u{0307}'),
+    ('ž', 'z', '\u{030C}'),
+    ('ǎ', 'a', '\u{030C}'),
+    ('ǐ', 'i', '\u{030C}'),
+    ('ǒ', 'o', '\u{030C}'),
+    ('ǔ', 'u', '\u{030C}'),
+    ('ș', 's', '\u{0326}'),
+    ('ț', 't', '\u{0326}'),
+    ('ά', 'α', '\u{0301}'),
+    ('έ', 'ε', '\u{0301}'),
+    ('ή', 'η', '\u{0301}'),
+    ('ί', 'ι', '\u{0301}'),
+    ('ό', 'ο', '\u{0301}'),
+    ('ύ', 'υ', '\u{0301}'),
+    ('ώ', 'ω', '\u{0301}'),
+    ('й', 'и', '\u{0306}'),
+    ('ё', 'е', '\u{0308}'),
+    ('ḍ', 'd', '\u{0323}'),
+    ('ḥ', 'h', '\u{0323}'),
+    ('ṃ', 'm', '\u{0323}'),
+    ('ṇ', 'n', '\u{0323}'),
+    ('ṣ', 's', '\u{0323}'),
+    ('ṭ', 't', '\u{0323}'),
+];
+
+#[cfg(test)]
+mod fold_tests {
+    use super::*;
+
+    fn find_all<'a>(query: &str, line: &'a str) -> Vec<&'a str> {
+        let query = fold(query);
+        let folded = Folded::new(line);
+        let mut found = Vec::new();
+        let mut at = 0;
+        while let Some(span) = folded.find_at(&query, at) {
+            at = span.end;
+            found.push(&line[span]);
+        }
+        found
+    }
+
+    #[test]
+    fn test_decompositions_sorted() {
+        assert!(DECOMPOSITIONS.windows(2).all(|w| w[0].0 < w[1].0));
+        // 每一项都是小写字母，并且确实是基本字母加组合符号
+        for (c, base, mark) in DECOMPOSITIONS {
+            assert!(
+                c.is_lowercase() && base.is_lowercase() && is_combining(mark),
+                "{c}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_full_case_folding() {
+        assert_eq!(fold("STRASSE"), fold("straße"));
+        assert_eq!(fold("ẞ"), "ss");
+        assert_eq!(fold("ΣΊΣΥΦΟΣ"), fold("σίσυφος"));
+        assert_eq!(
+            find_all("strasse", "Straße und STRASSE"),
+            ["Straße", "STRASSE"]
+        );
+        assert_eq!(find_all("office", "the oﬃce"), ["oﬃce"]);
+        // 匹配不能停在一个字符的折叠结果中间
+        assert!(find_all("stras", "Straße").is_empty());
+    }
+
+    #[test]
+    fn test_composed_and_decomposed() {
+        let decomposed = "cafe\u{0301} CAFE\u{0301}";
+        assert_eq!(
+            find_all("café", decomposed),
+            ["cafe\u{0301}", "CAFE\u{0301}"]
+        );
+        assert_eq!(find_all("cafe\u{0301}", "Café"), ["Café"]);
+        assert_eq!(find_all("ÉCOLE", "école"), ["école"]);
+        // 没有重音的查询不匹配带重音的字母
+        assert!(find_all("cafe", "café").is_empty());
+        assert!(find_all("cafe", decomposed).is_empty());
+        assert!(find_all("e", "é").is_empty());
+    }
+
+    #[test]
+    fn test_turkish_i() {
+        // İ 折叠成 i 加组合点，两种写法可以互相匹配
+        assert_eq!(fold("İ"), "i\u{0307}");
+        assert_eq!(
+            find_all("İSTANBUL", "i\u{0307}stanbul"),
+            ["i\u{0307}stanbul"]
+        );
+        assert_eq!(find_all("i\u{0307}stanbul", "İstanbul"), ["İstanbul"]);
+        // 带点和不带点的 i 都不等于普通的 i，位置仍然对应原始行
+        let line = "İstanbul ıstanbul istanbul";
+        assert_eq!(find_all("istanbul", line), ["istanbul"]);
+        assert_eq!(find_all("ISTANBUL", line), ["istanbul"]);
+        assert_eq!(find_all("IST", "ıst İst ist"), ["ist"]);
+        assert_eq!(find_all("ı", "I ı i"), ["ı"]);
+    }
+}
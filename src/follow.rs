@@ -0,0 +1,117 @@
+// --follow：像 tail -f 一样跟踪一个不断变长的文件（通常是日志）。读到文件结尾时不返回结束，
+// 而是每隔一段时间看看文件有没有变长，有新内容就接着读，搜索逻辑完全不用改动。
+// 文件被截断（例如日志轮转时的 copytruncate）时从头开始读。stop 返回 true 之后返回文件结束，
+// 扫描照常收尾；还没有换行的最后一行这时也会被搜索
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::thread;
+use std::time::Duration;
+
+// 检查文件长度的间隔，也是按下 Ctrl-C 之后最多等待的时间
+pub const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+pub struct Follow<F> {
+    file: File,
+    name: String,
+    // 已经读到的位置
+    pos: u64,
+    interval: Duration,
+    stop: F,
+}
+
+impl<F: FnMut() -> bool> Follow<F> {
+    pub fn new(file: File, name: &str, interval: Duration, stop: F) -> Follow<F> {
+        Follow {
+            file,
+            name: name.to_string(),
+            pos: 0,
+            interval,
+            stop,
+        }
+    }
+}
+
+impl<F: FnMut() -> bool> Read for Follow<F> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let read = self.file.read(buf)?;
+            if read > 0 {
+                self.pos += read as u64;
+                return Ok(read);
+            }
+            if (self.stop)() {
+                return Ok(0);
+            }
+            if self.file.metadata()?.len() < self.pos {
+                eprintln!(
+                    "warning: {}: file truncated, reading from the start",
+                    self.name
+                );
+                self.pos = self.file.seek(SeekFrom::Start(0))?;
+                continue;
+            }
+            thread::sleep(self.interval);
+        }
+    }
+}
+
+#[cfg(test)]
+mod follow_tests {
+    use super::*;
+    use std::env;
+    use std::fs::OpenOptions;
+    use std::io::{BufRead, BufReader, Write};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    // 另一个线程分几次追加内容（最后一行先写一半），读取一直等到追加完成并且 stop 之后才结束
+    #[test]
+    fn test_follow_growing_file() {
+        let path = env::temp_dir().join("minigrep-follow-growing.log");
+        std::fs::write(&path, "one\n").unwrap();
+        let done = Arc::new(AtomicBool::new(false));
+        let writer = {
+            let (path, done) = (path.clone(), Arc::clone(&done));
+            thread::spawn(move || {
+                let mut file = OpenOptions::new().append(true).open(path).unwrap();
+                for chunk in ["two\n", "thr", "ee\nfo", "ur"] {
+                    thread::sleep(Duration::from_millis(20));
+                    file.write_all(chunk.as_bytes()).unwrap();
+                }
+                done.store(true, Ordering::SeqCst);
+            })
+        };
+        let follow = Follow::new(
+            File::open(&path).unwrap(),
+            "growing.log",
+            Duration::from_millis(5),
+            || done.load(Ordering::SeqCst),
+        );
+        let lines: Vec<String> = BufReader::new(follow).lines().map(Result::unwrap).collect();
+        writer.join().unwrap();
+        assert_eq!(lines, ["one", "two", "three", "four"]);
+    }
+
+    #[test]
+    fn test_follow_truncated_file() {
+        let path = env::temp_dir().join("minigrep-follow-truncated.log");
+        std::fs::write(&path, "old line\n").unwrap();
+        let mut polls = 0;
+        let mut follow = Follow::new(
+            File::open(&path).unwrap(),
+            "truncated.log",
+            Duration::from_millis(1),
+            || {
+                polls += 1;
+                // 第一次到达结尾时把文件换成更短的内容
+                if polls == 1 {
+                    std::fs::write(&path, "new\n").unwrap();
+                }
+                polls > 2
+            },
+        );
+        let mut contents = String::new();
+        follow.read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "old line\nnew\n");
+    }
+}
@@ -0,0 +1,144 @@
+// --fuzzy：按编辑距离近似匹配，拼错一两个字母也能找到，例如 cofig 能找到 config。
+// 在行中查找和查询的编辑距离（插入、删除、替换一个字符各算 1）不超过上限的片段，
+// 用的是 Sellers 算法：和计算两个字符串编辑距离的动态规划相同，只是匹配可以从行中任意位置开始。
+// 允许的距离随查询的长度增加，每 4 个字符允许错一个，短查询必须完全一致，否则几乎每行都能匹配
+use std::ops::Range;
+
+#[derive(Debug, Clone)]
+pub struct Fuzzy {
+    // ignore_case 时已经转成了小写
+    query: Vec<char>,
+    ignore_case: bool,
+    max_distance: usize,
+}
+
+impl Fuzzy {
+    pub fn new(query: &str, ignore_case: bool) -> Fuzzy {
+        let query: Vec<char> = if ignore_case {
+            query.chars().flat_map(char::to_lowercase).collect()
+        } else {
+            query.chars().collect()
+        };
+        Fuzzy {
+            max_distance: query.len() / 4,
+            query,
+            ignore_case,
+        }
+    }
+
+    pub fn max_distance(&self) -> usize {
+        self.max_distance
+    }
+
+    fn same(&self, query: char, c: char) -> bool {
+        if self.ignore_case {
+            c.to_lowercase().eq(std::iter::once(query))
+        } else {
+            query == c
+        }
+    }
+
+    // 从 at 开始第一个距离不超过上限的片段。找到之后继续向后看，距离没有变大时把结尾延长，
+    // 这样 config 中查找 confg 得到整个 config，而不是在同样差一个字符的 conf 处就停下
+    pub fn find_at(&self, line: &str, at: usize) -> Option<Range<usize>> {
+        if self.query.is_empty() {
+            return Some(at..at);
+        }
+        // column[i] 是查询的前 i 个字符匹配到当前位置为止的（最小距离，片段开头）
+        let mut column: Vec<(usize, usize)> = (0..=self.query.len()).map(|i| (i, at)).collect();
+        let mut best: Option<(usize, Range<usize>)> = None;
+        for (offset, c) in line[at..].char_indices() {
+            let next = at + offset + c.len_utf8();
+            let mut diagonal = column[0];
+            column[0] = (0, next);
+            for (i, &q) in self.query.iter().enumerate() {
+                let substitute = (diagonal.0 + usize::from(!self.same(q, c)), diagonal.1);
+                let skip_text = (column[i + 1].0 + 1, column[i + 1].1);
+                let skip_query = (column[i].0 + 1, column[i].1);
+                diagonal = column[i + 1];
+                column[i + 1] = [skip_text, skip_query]
+                    .into_iter()
+                    .fold(substitute, |a, b| if b.0 < a.0 { b } else { a });
+            }
+            let (distance, start) = column[self.query.len()];
+            match &best {
+                Some((best_distance, _)) if distance > *best_distance => break,
+                _ if distance <= self.max_distance => best = Some((distance, start..next)),
+                _ => {}
+            }
+        }
+        best.map(|(_, span)| span)
+    }
+
+    // 0 到 1 之间的相似度：1 表示完全一致，每差一个字符减去 1/查询长度
+    pub fn score(&self, matched: &str) -> f64 {
+        if self.query.is_empty() {
+            return 1.0;
+        }
+        let distance = self.distance(matched);
+        1.0 - distance.min(self.query.len()) as f64 / self.query.len() as f64
+    }
+
+    // 查询和 text 之间的编辑距离
+    fn distance(&self, text: &str) -> usize {
+        let mut row: Vec<usize> = (0..=self.query.len()).collect();
+        for (j, c) in text.chars().enumerate() {
+            let mut diagonal = row[0];
+            row[0] = j + 1;
+            for (i, &q) in self.query.iter().enumerate() {
+                let substitute = diagonal + usize::from(!self.same(q, c));
+                diagonal = row[i + 1];
+                row[i + 1] = substitute.min(row[i + 1] + 1).min(row[i] + 1);
+            }
+        }
+        row[self.query.len()]
+    }
+}
+
+#[cfg(test)]
+mod fuzzy_tests {
+    use super::*;
+
+    fn find_all<'a>(fuzzy: &Fuzzy, line: &'a str) -> Vec<&'a str> {
+        let mut found = Vec::new();
+        let mut at = 0;
+        while at <= line.len() {
+            let Some(span) = fuzzy.find_at(line, at) else {
+                break;
+            };
+            at = span.end.max(at + 1);
+            found.push(&line[span]);
+        }
+        found
+    }
+
+    #[test]
+    fn test_find() {
+        let fuzzy = Fuzzy::new("cofig", false);
+        assert_eq!(fuzzy.max_distance(), 1);
+        assert_eq!(
+            find_all(&fuzzy, "let config = load_coffig();"),
+            ["config", "coffig"]
+        );
+        assert!(find_all(&fuzzy, "let conflict = 1;").is_empty());
+        // 短查询不允许出错
+        let short = Fuzzy::new("fn", false);
+        assert_eq!(short.max_distance(), 0);
+        assert_eq!(find_all(&short, "fn main, fin"), ["fn"]);
+        // 找到之后继续延长，取距离最小的结尾
+        assert_eq!(find_all(&Fuzzy::new("confg", false), "config"), ["config"]);
+        let ignore_case = Fuzzy::new("Cofig", true);
+        assert_eq!(find_all(&ignore_case, "CONFIG é"), ["CONFIG"]);
+        assert_eq!(find_all(&Fuzzy::new("", false), "ab").len(), 3);
+    }
+
+    #[test]
+    fn test_score() {
+        let fuzzy = Fuzzy::new("config", false);
+        assert_eq!(fuzzy.score("config"), 1.0);
+        assert_eq!(fuzzy.score("confg"), 1.0 - 1.0 / 6.0);
+        assert_eq!(fuzzy.score("cnofig"), 1.0 - 2.0 / 6.0);
+        assert_eq!(fuzzy.score("xyz"), 0.0);
+        assert_eq!(Fuzzy::new("", false).score("x"), 1.0);
+    }
+}
@@ -0,0 +1,307 @@
+// 调用 git 命令行读取历史版本中的文件内容，用于在整个提交历史中搜索
+// 只依赖 rev-list、ls-tree 和 cat-file 这几个底层命令，输出格式稳定，不受用户配置影响
+use std::collections::{HashMap, HashSet};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+pub struct Git {
+    dir: PathBuf,
+}
+
+// 某个提交中的一个文件
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeEntry {
+    pub commit: String,
+    pub path: String,
+    pub blob: String,
+}
+
+impl Git {
+    pub fn new(dir: impl AsRef<Path>) -> Git {
+        Git {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+
+    fn command(&self) -> Command {
+        let mut command = Command::new("git");
+        command.current_dir(&self.dir);
+        command
+    }
+
+    // 执行一条 git 命令并返回标准输出，命令失败时把 git 的错误信息放进 io::Error
+    fn run(&self, args: &[&str]) -> io::Result<String> {
+        let output = self.command().args(args).output()?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(io::Error::other(format!(
+                "git {} failed: {}",
+                args.join(" "),
+                stderr.trim()
+            )));
+        }
+        String::from_utf8(output.stdout).map_err(io::Error::other)
+    }
+
+    // 范围内的提交，按从旧到新的顺序
+    pub fn rev_list(&self, range: &str) -> io::Result<Vec<String>> {
+        let out = self.run(&["rev-list", "--reverse", range, "--"])?;
+        Ok(out.lines().map(String::from).collect())
+    }
+
+    // 提交中的所有文件，pathspecs 为空时不做过滤
+    pub fn ls_tree(&self, commit: &str, pathspecs: &[String]) -> io::Result<Vec<TreeEntry>> {
+        let mut args = vec!["ls-tree", "-r", "-z", "--full-tree", commit, "--"];
+        args.extend(pathspecs.iter().map(String::as_str));
+        let out = self.run(&args)?;
+        // 每一项的格式为 "<mode> <type> <object>\t<path>"，以 \0 分隔
+        Ok(out
+            .split('\0')
+            .filter_map(|entry| {
+                let (info, path) = entry.split_once('\t')?;
+                let mut fields = info.split(' ');
+                let (_mode, kind, blob) = (fields.next()?, fields.next()?, fields.next()?);
+                (kind == "blob").then(|| TreeEntry {
+                    commit: commit.to_string(),
+                    path: path.to_string(),
+                    blob: blob.to_string(),
+                })
+            })
+            .collect())
+    }
+
+    // 范围内每个文件出现过的每个版本，只保留第一次出现该版本的提交，
+    // 这样没有改动的文件不会在每个提交中被重复搜索
+    pub fn history(&self, range: &str, pathspecs: &[String]) -> io::Result<Vec<TreeEntry>> {
+        let mut seen = HashSet::new();
+        let mut entries = Vec::new();
+        for commit in self.rev_list(range)? {
+            for entry in self.ls_tree(&commit, pathspecs)? {
+                if seen.insert((entry.path.clone(), entry.blob.clone())) {
+                    entries.push(entry);
+                }
+            }
+        }
+        Ok(entries)
+    }
+
+    // 文件中每一行最后一次被修改的提交，结果按行号排列
+    pub fn blame(&self, path: &str) -> io::Result<Blame> {
+        let out = self.run(&["blame", "--porcelain", "--", path])?;
+        Ok(parse_blame(&out))
+    }
+
+    pub fn cat_file(&self) -> io::Result<CatFile> {
+        let mut child = self
+            .command()
+            .args(["cat-file", "--batch"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        Ok(CatFile {
+            child,
+            stdin,
+            stdout,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlameLine {
+    pub commit: String,
+    pub author: String,
+    // 作者提交的时间（Unix 时间戳，秒）
+    pub time: i64,
+}
+
+impl BlameLine {
+    pub fn short_commit(&self) -> &str {
+        &self.commit[..self.commit.len().min(7)]
+    }
+
+    // 按 UTC 显示的日期，例如 2024-05-01
+    pub fn date(&self) -> String {
+        let (year, month, day) = civil_from_days(self.time.div_euclid(86400));
+        format!("{year:04}-{month:02}-{day:02}")
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Blame {
+    lines: Vec<BlameLine>,
+}
+
+impl Blame {
+    // 行号从 1 开始
+    pub fn line(&self, line_number: usize) -> Option<&BlameLine> {
+        self.lines.get(line_number.checked_sub(1)?)
+    }
+}
+
+// --porcelain 的输出由若干段组成，每段以 "<commit> <原行号> <当前行号> [行数]" 开头，
+// 同一个提交第一次出现时后面跟着 author、author-time 等信息，最后是以 \t 开头的行内容
+fn parse_blame(out: &str) -> Blame {
+    let mut authors: HashMap<&str, (&str, i64)> = HashMap::new();
+    let mut entries: Vec<(usize, &str)> = Vec::new();
+    let mut current = ("", 0);
+    for line in out.lines() {
+        if line.starts_with('\t') {
+            entries.push((current.1, current.0));
+            continue;
+        }
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+        match key {
+            "author" => authors.entry(current.0).or_default().0 = value,
+            "author-time" => authors.entry(current.0).or_default().1 = value.parse().unwrap_or(0),
+            commit if commit.len() == 40 && commit.chars().all(|c| c.is_ascii_hexdigit()) => {
+                let final_line = value.split(' ').nth(1).and_then(|n| n.parse().ok());
+                current = (commit, final_line.unwrap_or(0));
+            }
+            _ => {}
+        }
+    }
+    entries.sort_by_key(|(line_number, _)| *line_number);
+    let lines = entries
+        .into_iter()
+        .map(|(_, commit)| {
+            let (author, time) = authors.get(commit).copied().unwrap_or_default();
+            BlameLine {
+                commit: commit.to_string(),
+                author: author.to_string(),
+                time,
+            }
+        })
+        .collect();
+    Blame { lines }
+}
+
+// 把从 1970-01-01 开始的天数换算成公历日期（Howard Hinnant 的 civil_from_days 算法）
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+// 常驻的 git cat-file --batch 进程，每读取一个对象只需要一次管道往返，不用为每个文件启动一个进程
+pub struct CatFile {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl CatFile {
+    pub fn read(&mut self, object: &str) -> io::Result<Vec<u8>> {
+        writeln!(self.stdin, "{object}")?;
+        self.stdin.flush()?;
+        // 响应头的格式为 "<object> <type> <size>"，对象不存在时为 "<object> missing"
+        let mut header = String::new();
+        self.stdout.read_line(&mut header)?;
+        let size = header
+            .split_whitespace()
+            .nth(2)
+            .and_then(|size| size.parse::<usize>().ok())
+            .ok_or_else(|| io::Error::other(format!("git cat-file: {}", header.trim())))?;
+        // 内容后面还跟着一个换行符
+        let mut content = vec![0; size + 1];
+        self.stdout.read_exact(&mut content)?;
+        content.pop();
+        Ok(content)
+    }
+}
+
+impl Drop for CatFile {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(test)]
+mod git_tests {
+    use super::*;
+    use std::fs;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .current_dir(dir)
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .stdout(Stdio::null())
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    #[test]
+    fn test_history() {
+        let dir = std::env::temp_dir().join("minigrep-git-test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("src")).unwrap();
+        git(&dir, &["init", "-q"]);
+        fs::write(dir.join("a.txt"), "old secret\n").unwrap();
+        fs::write(dir.join("src/b.txt"), "unchanged\n").unwrap();
+        git(&dir, &["add", "."]);
+        git(&dir, &["commit", "-q", "-m", "first"]);
+        fs::write(dir.join("a.txt"), "new text\n").unwrap();
+        git(&dir, &["commit", "-q", "-am", "second"]);
+
+        let repo = Git::new(&dir);
+        let commits = repo.rev_list("HEAD").unwrap();
+        assert_eq!(commits.len(), 2);
+        // b.txt 在第二个提交中没有变化，只出现一次
+        let history = repo.history("HEAD", &[]).unwrap();
+        let paths: Vec<_> = history.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, vec!["a.txt", "src/b.txt", "a.txt"]);
+        assert_eq!(history[2].commit, commits[1]);
+        assert_eq!(repo.history("HEAD", &["src".into()]).unwrap().len(), 1);
+
+        let mut cat = repo.cat_file().unwrap();
+        assert_eq!(cat.read(&history[0].blob).unwrap(), b"old secret\n");
+        assert_eq!(cat.read(&history[2].blob).unwrap(), b"new text\n");
+        assert!(cat
+            .read("0000000000000000000000000000000000000000")
+            .is_err());
+        assert!(repo.rev_list("no-such-rev").is_err());
+
+        let blame = repo.blame("a.txt").unwrap();
+        let line = blame.line(1).unwrap();
+        assert_eq!(line.commit, commits[1]);
+        assert_eq!(line.author, "test");
+        assert_eq!(line.short_commit(), &commits[1][..7]);
+        assert_eq!(blame.line(2), None);
+        assert!(repo.blame("missing.txt").is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_parse_blame() {
+        let a = "a".repeat(40);
+        let b = "b".repeat(40);
+        let out = format!(
+            "{a} 1 1 2\nauthor Alice\nauthor-time 0\nsummary x\n\tfirst\n\
+             {a} 2 2\n\tsecond\n\
+             {b} 5 3 1\nauthor Bob\nauthor-time 1714521600\n\tauthor fake header\n"
+        );
+        let blame = parse_blame(&out);
+        assert_eq!(blame.line(2).unwrap().author, "Alice");
+        assert_eq!(blame.line(1).unwrap().date(), "1970-01-01");
+        let third = blame.line(3).unwrap();
+        assert_eq!(
+            (third.author.as_str(), third.date()),
+            ("Bob", "2024-05-01".into())
+        );
+        assert_eq!(blame.line(0), None);
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        assert_eq!(civil_from_days(11016), (2000, 2, 29));
+    }
+}
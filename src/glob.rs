@@ -0,0 +1,250 @@
+// 通配符匹配，用于 --include / --exclude 过滤搜索的文件。支持的写法：
+//   *       任意个字符，不跨越目录（不匹配 /）
+//   **      任意个字符，可以跨越目录；**/ 可以匹配零个或多个目录
+//   ?       任意一个字符（不匹配 /）
+//   [abc]   [a-z] [!a-z]  字符集合及其取反
+//   {a,b}   几个候选之一（不支持嵌套）
+// 不含 / 的模式只和文件名比较，例如 *.rs 能匹配 src/lib.rs；含 / 的模式和完整的相对路径比较
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Char(char),
+    Any,
+    Star,
+    // ** 后面紧跟着 / 时为 true，表示零个或多个完整的目录
+    DoubleStar {
+        dirs: bool,
+    },
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Glob {
+    pattern: String,
+    // 展开 {a,b} 之后的每个候选
+    alternatives: Vec<Vec<Token>>,
+    match_path: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlobError {
+    pub pattern: String,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for GlobError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid glob '{}': {}", self.pattern, self.reason)
+    }
+}
+
+impl Error for GlobError {}
+
+impl Glob {
+    pub fn new(pattern: &str) -> Result<Glob, GlobError> {
+        let error = |reason| GlobError {
+            pattern: pattern.to_string(),
+            reason,
+        };
+        let alternatives = expand_braces(pattern)
+            .map_err(error)?
+            .iter()
+            .map(|alternative| parse(alternative))
+            .collect::<Result<_, _>>()
+            .map_err(error)?;
+        Ok(Glob {
+            pattern: pattern.to_string(),
+            alternatives,
+            match_path: pattern.contains('/'),
+        })
+    }
+
+    // 即使模式中没有 /，也和完整的相对路径比较，例如 .gitignore 中的 /target 只匹配最上层的 target
+    pub fn anchored(mut self) -> Glob {
+        self.match_path = true;
+        self
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    // path 使用 / 分隔目录
+    pub fn is_match(&self, path: &str) -> bool {
+        let text = if self.match_path {
+            path.trim_start_matches("./")
+        } else {
+            path.rsplit('/').next().unwrap_or(path)
+        };
+        let text: Vec<char> = text.chars().collect();
+        self.alternatives
+            .iter()
+            .any(|tokens| matches(tokens, &text))
+    }
+}
+
+impl FromStr for Glob {
+    type Err = GlobError;
+
+    fn from_str(s: &str) -> Result<Glob, GlobError> {
+        Glob::new(s)
+    }
+}
+
+// 把 a{b,c}d 展开成 abd 和 acd
+fn expand_braces(pattern: &str) -> Result<Vec<String>, &'static str> {
+    let Some(open) = pattern.find('{') else {
+        return Ok(vec![pattern.to_string()]);
+    };
+    let close = pattern[open..]
+        .find('}')
+        .map(|i| open + i)
+        .ok_or("unclosed '{'")?;
+    let (prefix, suffix) = (&pattern[..open], &pattern[close + 1..]);
+    let mut out = Vec::new();
+    for choice in pattern[open + 1..close].split(',') {
+        if choice.contains('{') {
+            return Err("nested '{' is not supported");
+        }
+        for rest in expand_braces(suffix)? {
+            out.push(format!("{prefix}{choice}{rest}"));
+        }
+    }
+    Ok(out)
+}
+
+fn parse(pattern: &str) -> Result<Vec<Token>, &'static str> {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '*' if chars.get(i + 1) == Some(&'*') => {
+                let dirs = chars.get(i + 2) == Some(&'/');
+                tokens.push(Token::DoubleStar { dirs });
+                i += if dirs { 3 } else { 2 };
+                continue;
+            }
+            '*' => tokens.push(Token::Star),
+            '?' => tokens.push(Token::Any),
+            '[' => {
+                let negated = matches!(chars.get(i + 1), Some('!' | '^'));
+                let mut j = i + 1 + usize::from(negated);
+                let mut ranges = Vec::new();
+                // 紧跟在 [ 后面的 ] 是普通字符
+                let mut first = true;
+                loop {
+                    let c = *chars.get(j).ok_or("unclosed '['")?;
+                    if c == ']' && !first {
+                        break;
+                    }
+                    first = false;
+                    if chars.get(j + 1) == Some(&'-') && chars.get(j + 2).is_some_and(|&e| e != ']')
+                    {
+                        ranges.push((c, chars[j + 2]));
+                        j += 3;
+                    } else {
+                        ranges.push((c, c));
+                        j += 1;
+                    }
+                }
+                tokens.push(Token::Class { negated, ranges });
+                i = j;
+            }
+            '\\' if i + 1 < chars.len() => {
+                tokens.push(Token::Char(chars[i + 1]));
+                i += 1;
+            }
+            c => tokens.push(Token::Char(c)),
+        }
+        i += 1;
+    }
+    Ok(tokens)
+}
+
+fn matches(tokens: &[Token], text: &[char]) -> bool {
+    let Some((token, rest)) = tokens.split_first() else {
+        return text.is_empty();
+    };
+    match token {
+        Token::Star => {
+            // * 不能越过 /
+            let limit = text.iter().position(|&c| c == '/').unwrap_or(text.len());
+            (0..=limit).any(|i| matches(rest, &text[i..]))
+        }
+        Token::DoubleStar { dirs: false } => (0..=text.len()).any(|i| matches(rest, &text[i..])),
+        // **/ 只能停在目录的边界上：开头，或者某个 / 之后
+        Token::DoubleStar { dirs: true } => {
+            matches(rest, text)
+                || (0..text.len())
+                    .filter(|&i| text[i] == '/')
+                    .any(|i| matches(rest, &text[i + 1..]))
+        }
+        token => match text.split_first() {
+            Some((&c, text)) if c != '/' || *token == Token::Char('/') => {
+                let ok = match token {
+                    Token::Char(expected) => c == *expected,
+                    Token::Any => true,
+                    Token::Class { negated, ranges } => {
+                        ranges.iter().any(|&(lo, hi)| lo <= c && c <= hi) != *negated
+                    }
+                    _ => unreachable!(),
+                };
+                ok && matches(rest, text)
+            }
+            _ => false,
+        },
+    }
+}
+
+#[cfg(test)]
+mod glob_tests {
+    use super::*;
+
+    fn is_match(pattern: &str, path: &str) -> bool {
+        Glob::new(pattern).unwrap().is_match(path)
+    }
+
+    #[test]
+    fn test_file_name_patterns() {
+        assert!(is_match("*.rs", "src/lib.rs"));
+        assert!(is_match("*.rs", "main.rs"));
+        assert!(!is_match("*.rs", "src/lib.rs.bak"));
+        assert!(is_match("?ib.rs", "src/lib.rs"));
+        assert!(is_match("[lm]*.rs", "src/main.rs"));
+        assert!(!is_match("[!lm]*.rs", "src/main.rs"));
+        assert!(is_match("file[0-9].txt", "file7.txt"));
+        assert!(is_match("*.{rs,toml}", "Cargo.toml"));
+        assert!(!is_match("*.{rs,toml}", "Cargo.lock"));
+        assert!(is_match(r"\*.txt", "*.txt"));
+    }
+
+    #[test]
+    fn test_path_patterns() {
+        assert!(is_match("src/*.rs", "src/lib.rs"));
+        assert!(is_match("src/*.rs", "./src/lib.rs"));
+        // * 不跨越目录
+        assert!(!is_match("src/*.rs", "src/a/lib.rs"));
+        assert!(is_match("src/**/*.rs", "src/a/b/lib.rs"));
+        assert!(is_match("src/**/*.rs", "src/lib.rs"));
+        assert!(is_match("**/target/**", "a/target/debug/x"));
+        assert!(is_match("**/target/**", "target/x"));
+        assert!(!is_match("src/**/*.rs", "tests/lib.rs"));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            Glob::new("[abc").unwrap_err().to_string(),
+            "invalid glob '[abc': unclosed '['"
+        );
+        assert!(Glob::new("{a,b").is_err());
+        assert!("*.rs".parse::<Glob>().is_ok());
+    }
+}
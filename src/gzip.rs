@@ -0,0 +1,534 @@
+// 透明地搜索 gzip 压缩的文件（.gz，例如轮转之后的日志）：打开文件时按扩展名套上解压层，
+// 搜索逻辑看到的是解压之后的内容。没有依赖，这里实现了 DEFLATE（RFC 1951）解码和
+// gzip 格式（RFC 1952）的解析，支持多个成员连接在一起的文件（cat a.gz b.gz > c.gz），
+// 每个成员结束时校验 CRC32 和长度
+use crate::io_backend::Reader;
+use std::io::{self, BufRead, BufReader, Read};
+use std::mem;
+use std::path::Path;
+
+// 回溯引用最远 32 KiB
+const WINDOW: usize = 1 << 15;
+// 每次 fill 最多解压出这么多字节，之后交给调用方
+const CHUNK: usize = 1 << 15;
+
+// gzip 头部的标志位
+const FHCRC: u8 = 0x02;
+const FEXTRA: u8 = 0x04;
+const FNAME: u8 = 0x08;
+const FCOMMENT: u8 = 0x10;
+
+// 长度码 257..=285 和距离码 0..=29 的基础值和额外的位数
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+// 动态块中码长的码长按这个顺序给出
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                0xedb8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+pub fn is_gzip_path(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("gz"))
+}
+
+// 路径以 .gz 结尾时返回边读边解压的读取器，其他文件原样返回
+pub fn decode(path: &str, reader: Reader) -> Reader {
+    if is_gzip_path(path) {
+        Box::new(GzDecoder::new(reader))
+    } else {
+        reader
+    }
+}
+
+fn invalid(reason: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("corrupt gzip file: {reason}"),
+    )
+}
+
+// 按位读取：DEFLATE 从每个字节的最低位开始存放
+struct Bits<R> {
+    inner: R,
+    bits: u64,
+    count: u32,
+}
+
+impl<R: BufRead> Bits<R> {
+    fn next_byte(&mut self) -> io::Result<u8> {
+        let byte = match self.inner.fill_buf()? {
+            [] => {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "truncated gzip file",
+                ))
+            }
+            buf => buf[0],
+        };
+        self.inner.consume(1);
+        Ok(byte)
+    }
+
+    fn bits(&mut self, n: u32) -> io::Result<u32> {
+        while self.count < n {
+            self.bits |= u64::from(self.next_byte()?) << self.count;
+            self.count += 8;
+        }
+        let value = (self.bits & ((1 << n) - 1)) as u32;
+        self.bits >>= n;
+        self.count -= n;
+        Ok(value)
+    }
+
+    // 丢掉当前字节剩下的位，之后按整字节读取
+    fn align(&mut self) {
+        let rest = self.count % 8;
+        self.bits >>= rest;
+        self.count -= rest;
+    }
+
+    fn byte(&mut self) -> io::Result<u8> {
+        Ok(self.bits(8)? as u8)
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        Ok(self.bits(16)? as u16)
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from(self.u16()?) | u32::from(self.u16()?) << 16)
+    }
+
+    fn at_end(&mut self) -> io::Result<bool> {
+        Ok(self.count == 0 && self.inner.fill_buf()?.is_empty())
+    }
+}
+
+// 规范哈夫曼编码：每个码长有几个符号，以及按码排好序的符号
+struct Huffman {
+    count: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    // lengths[符号] 是这个符号的码长，0 表示不出现
+    fn new(lengths: &[u8]) -> io::Result<Huffman> {
+        let mut count = [0u16; 16];
+        for &len in lengths {
+            count[len as usize] += 1;
+        }
+        // 某个码长的符号比剩下可用的码还多，编码无效
+        let mut left = 1i32;
+        for &n in &count[1..] {
+            left = (left << 1) - i32::from(n);
+            if left < 0 {
+                return Err(invalid("over-subscribed Huffman code"));
+            }
+        }
+        let mut offsets = [0u16; 16];
+        for len in 1..15 {
+            offsets[len + 1] = offsets[len] + count[len];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len != 0 {
+                symbols[offsets[len as usize] as usize] = symbol as u16;
+                offsets[len as usize] += 1;
+            }
+        }
+        Ok(Huffman { count, symbols })
+    }
+
+    // 逐位读取，直到读到的码落在某个码长的范围内
+    fn decode<R: BufRead>(&self, input: &mut Bits<R>) -> io::Result<u16> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for &count in &self.count[1..] {
+            code |= input.bits(1)? as i32;
+            let count = i32::from(count);
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(invalid("invalid Huffman code"))
+    }
+}
+
+struct Tables {
+    literal: Huffman,
+    distance: Huffman,
+}
+
+enum State {
+    // 下一个成员的头部，或者文件结尾
+    Header,
+    // 下一个块的头部，last 时改为读成员的结尾
+    Block,
+    // 不压缩的块还剩多少字节
+    Stored(usize),
+    Codes(Box<Tables>),
+    Trailer,
+    Done,
+}
+
+pub struct GzDecoder<R> {
+    input: Bits<BufReader<R>>,
+    state: State,
+    // 当前块是否是这个成员的最后一块
+    last: bool,
+    members: usize,
+    // 最近解压出的 32 KiB，pos 是这个成员已经解压出的字节数
+    window: Box<[u8]>,
+    pos: usize,
+    crc: u32,
+    // 解压出来还没有交给调用方的内容
+    out: Vec<u8>,
+    out_pos: usize,
+}
+
+impl<R: Read> GzDecoder<R> {
+    pub fn new(reader: R) -> GzDecoder<R> {
+        GzDecoder {
+            input: Bits {
+                inner: BufReader::new(reader),
+                bits: 0,
+                count: 0,
+            },
+            state: State::Header,
+            last: false,
+            members: 0,
+            window: vec![0; WINDOW].into_boxed_slice(),
+            pos: 0,
+            crc: !0,
+            out: Vec::with_capacity(CHUNK + 258),
+            out_pos: 0,
+        }
+    }
+
+    fn emit(&mut self, byte: u8) {
+        self.window[self.pos % WINDOW] = byte;
+        self.pos += 1;
+        self.crc = CRC_TABLE[((self.crc ^ u32::from(byte)) & 0xff) as usize] ^ (self.crc >> 8);
+        self.out.push(byte);
+    }
+
+    fn fill(&mut self) -> io::Result<()> {
+        self.out.clear();
+        self.out_pos = 0;
+        while self.out.len() < CHUNK {
+            self.state = match mem::replace(&mut self.state, State::Done) {
+                State::Done => break,
+                State::Header if self.header()? => State::Block,
+                State::Header => State::Done,
+                State::Block if self.last => State::Trailer,
+                State::Block => self.block_header()?,
+                State::Stored(0) => State::Block,
+                State::Stored(left) => {
+                    let n = left.min(CHUNK - self.out.len());
+                    for _ in 0..n {
+                        let byte = self.input.byte()?;
+                        self.emit(byte);
+                    }
+                    State::Stored(left - n)
+                }
+                State::Codes(tables) if self.codes(&tables)? => State::Block,
+                State::Codes(tables) => State::Codes(tables),
+                State::Trailer => {
+                    self.trailer()?;
+                    State::Header
+                }
+            };
+        }
+        Ok(())
+    }
+
+    // 读取一个成员的头部，返回 false 表示没有更多成员。
+    // 和 gzip 一样，最后一个成员之后不是 gzip 数据的内容（例如磁带的补齐）忽略掉
+    fn header(&mut self) -> io::Result<bool> {
+        let first = self.members == 0;
+        if self.input.at_end()? {
+            return if first {
+                Err(invalid("empty file"))
+            } else {
+                Ok(false)
+            };
+        }
+        if [self.input.byte()?, self.input.byte()?] != [0x1f, 0x8b] {
+            return if first {
+                Err(invalid("not in gzip format"))
+            } else {
+                Ok(false)
+            };
+        }
+        if self.input.byte()? != 8 {
+            return Err(invalid("unknown compression method"));
+        }
+        let flags = self.input.byte()?;
+        // 修改时间、压缩级别和操作系统
+        for _ in 0..6 {
+            self.input.byte()?;
+        }
+        if flags & FEXTRA != 0 {
+            for _ in 0..self.input.u16()? {
+                self.input.byte()?;
+            }
+        }
+        // 原来的文件名和注释，以 0 结尾
+        for flag in [FNAME, FCOMMENT] {
+            if flags & flag != 0 {
+                while self.input.byte()? != 0 {}
+            }
+        }
+        if flags & FHCRC != 0 {
+            self.input.u16()?;
+        }
+        self.members += 1;
+        self.last = false;
+        self.pos = 0;
+        self.crc = !0;
+        Ok(true)
+    }
+
+    fn block_header(&mut self) -> io::Result<State> {
+        self.last = self.input.bits(1)? == 1;
+        Ok(match self.input.bits(2)? {
+            0 => {
+                self.input.align();
+                let len = self.input.u16()?;
+                if self.input.u16()? != !len {
+                    return Err(invalid("bad stored block length"));
+                }
+                State::Stored(len as usize)
+            }
+            1 => State::Codes(Box::new(fixed_tables()?)),
+            2 => State::Codes(Box::new(self.dynamic_tables()?)),
+            _ => return Err(invalid("invalid block type")),
+        })
+    }
+
+    fn dynamic_tables(&mut self) -> io::Result<Tables> {
+        let literals = self.input.bits(5)? as usize + 257;
+        let distances = self.input.bits(5)? as usize + 1;
+        let code_lengths = self.input.bits(4)? as usize + 4;
+        if literals > 286 || distances > 30 {
+            return Err(invalid("too many length or distance codes"));
+        }
+        let mut lengths = [0u8; 19];
+        for &symbol in &CODE_LENGTH_ORDER[..code_lengths] {
+            lengths[symbol] = self.input.bits(3)? as u8;
+        }
+        let code = Huffman::new(&lengths)?;
+        // 字面量/长度码和距离码的码长连在一起编码，重复的码长可以跨过两者的边界
+        let mut lengths = vec![0u8; literals + distances];
+        let mut i = 0;
+        while i < lengths.len() {
+            let (value, repeat) = match code.decode(&mut self.input)? {
+                len @ 0..=15 => (len as u8, 1),
+                16 if i == 0 => return Err(invalid("repeated length with no first length")),
+                16 => (lengths[i - 1], 3 + self.input.bits(2)?),
+                17 => (0, 3 + self.input.bits(3)?),
+                _ => (0, 11 + self.input.bits(7)?),
+            };
+            let end = i + repeat as usize;
+            if end > lengths.len() {
+                return Err(invalid("too many code lengths"));
+            }
+            lengths[i..end].fill(value);
+            i = end;
+        }
+        if lengths[256] == 0 {
+            return Err(invalid("missing end-of-block code"));
+        }
+        Ok(Tables {
+            literal: Huffman::new(&lengths[..literals])?,
+            distance: Huffman::new(&lengths[literals..])?,
+        })
+    }
+
+    // 解码压缩块，返回 true 表示块结束；out 满了时先返回 false，下次接着解码
+    fn codes(&mut self, tables: &Tables) -> io::Result<bool> {
+        while self.out.len() < CHUNK {
+            let symbol = tables.literal.decode(&mut self.input)? as usize;
+            if symbol < 256 {
+                self.emit(symbol as u8);
+                continue;
+            }
+            if symbol == 256 {
+                return Ok(true);
+            }
+            let index = symbol - 257;
+            if index >= LENGTH_BASE.len() {
+                return Err(invalid("invalid length code"));
+            }
+            let len = usize::from(LENGTH_BASE[index])
+                + self.input.bits(u32::from(LENGTH_EXTRA[index]))? as usize;
+            let index = tables.distance.decode(&mut self.input)? as usize;
+            if index >= DIST_BASE.len() {
+                return Err(invalid("invalid distance code"));
+            }
+            let dist = usize::from(DIST_BASE[index])
+                + self.input.bits(u32::from(DIST_EXTRA[index]))? as usize;
+            if dist > self.pos {
+                return Err(invalid("distance too far back"));
+            }
+            // 距离比长度短时复制的是刚刚写出的内容，只能逐字节复制
+            for _ in 0..len {
+                let byte = self.window[(self.pos - dist) % WINDOW];
+                self.emit(byte);
+            }
+        }
+        Ok(false)
+    }
+
+    fn trailer(&mut self) -> io::Result<()> {
+        self.input.align();
+        if self.input.u32()? != !self.crc {
+            return Err(invalid("CRC mismatch"));
+        }
+        // 长度只记录了低 32 位
+        if self.input.u32()? != self.pos as u32 {
+            return Err(invalid("length mismatch"));
+        }
+        Ok(())
+    }
+}
+
+// 固定编码的块使用 RFC 1951 规定的码长
+fn fixed_tables() -> io::Result<Tables> {
+    let mut lengths = [8u8; 288];
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    Ok(Tables {
+        literal: Huffman::new(&lengths)?,
+        distance: Huffman::new(&[5; 30])?,
+    })
+}
+
+impl<R: Read> Read for GzDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.out_pos == self.out.len() {
+            self.fill()?;
+        }
+        let n = buf.len().min(self.out.len() - self.out_pos);
+        buf[..n].copy_from_slice(&self.out[self.out_pos..self.out_pos + n]);
+        self.out_pos += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod gzip_tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn gunzip(data: &[u8]) -> io::Result<Vec<u8>> {
+        let mut out = Vec::new();
+        GzDecoder::new(data).read_to_end(&mut out)?;
+        Ok(out)
+    }
+
+    // 用 Python 的 gzip 模块生成的数据
+    #[test]
+    fn test_decode() {
+        // 固定编码，距离比长度短的回溯引用
+        let run = hex("1f8b08000000000002034b4c4a2412701595169770010020cdcc7330000000");
+        let expected = format!("ab{}\nrust\n", "a".repeat(40));
+        assert_eq!(gunzip(&run).unwrap(), expected.as_bytes());
+        // 两个成员：固定编码和不压缩的块
+        let multi = hex(
+            "1f8b08000000000002034bcb2c2a2e51282a2d2ee10200e72ed73f0b000000\
+             1f8b0800000000000403010c00f3ff7365636f6e6420727573740abae17e2f0c000000",
+        );
+        assert_eq!(gunzip(&multi).unwrap(), b"first rust\nsecond rust\n");
+        // 头部带有 FEXTRA、FNAME、FCOMMENT 和 FHCRC
+        let flags = hex(
+            "1f8b081e000000000003030078797a6c6f672e74787400636f6d6d656e74005829\
+             cb4bcc4d4d51282a2d2ee1020094a6b0380b000000",
+        );
+        assert_eq!(gunzip(&flags).unwrap(), b"named rust\n");
+        // 动态编码，内容由结尾的 CRC32 和长度校验
+        let dynamic = hex(
+            "1f8b0800000000000203554ed10ac020087cdf57f86bd11cc558322df6fb5b172d0439bcf33c\
+             8d212626e5bbb155bab2d94f723984e4ecb54b614ab97a23546d1fed23d8a1039ea085a24b1f\
+             8c55455dd850b0e0f2871fce7904cd7c68c1bad8617b0159de8781d5000000",
+        );
+        let text = String::from_utf8(gunzip(&dynamic).unwrap()).unwrap();
+        assert_eq!(text.len(), 213);
+        assert_eq!(text.matches("rust").count(), 3);
+    }
+
+    #[test]
+    fn test_corrupt() {
+        let data = hex("1f8b08000000000002034b4c4a2412701595169770010020cdcc7330000000");
+        let error = |data: &[u8]| gunzip(data).unwrap_err();
+        let mut bad_crc = data.clone();
+        bad_crc[data.len() - 8] ^= 1;
+        assert_eq!(
+            error(&bad_crc).to_string(),
+            "corrupt gzip file: CRC mismatch"
+        );
+        assert_eq!(error(&data[..20]).kind(), io::ErrorKind::UnexpectedEof);
+        assert_eq!(
+            error(b"plain text").to_string(),
+            "corrupt gzip file: not in gzip format"
+        );
+        assert!(gunzip(b"").is_err());
+        // 最后一个成员之后的补齐忽略掉
+        let mut padded = data.clone();
+        padded.extend_from_slice(&[0; 16]);
+        assert_eq!(gunzip(&padded).unwrap(), gunzip(&data).unwrap());
+    }
+
+    #[test]
+    fn test_is_gzip_path() {
+        assert!(is_gzip_path("logs/app.log.gz"));
+        assert!(is_gzip_path("ARCHIVE.GZ"));
+        assert!(!is_gzip_path("app.log"));
+        assert!(!is_gzip_path("gz"));
+        assert!(!is_gzip_path("notes.tgz"));
+    }
+}
@@ -0,0 +1,137 @@
+// 按标识符搜索：先把一行拆成标识符，再拿查询和整个标识符比较，所以 id 不会匹配 identifier。
+// 单词模式下还会按 camelCase 和 snake_case 把标识符拆成单词，查询可以是其中连续的几个单词，
+// 例如 user_id 能匹配 userId、USER_ID 和 getUserIdFromToken 中的 UserId
+use crate::fold::fold;
+use crate::regex::is_word_char;
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IdentifierMode {
+    // 查询必须等于整个标识符
+    Whole,
+    // 查询等于标识符中连续的几个单词即可，不区分大小写
+    Subword,
+}
+
+// 行中的标识符：由字母、数字和下划线组成的最长片段
+pub fn identifiers(line: &str) -> impl Iterator<Item = Range<usize>> + '_ {
+    let mut chars = line.char_indices().peekable();
+    std::iter::from_fn(move || {
+        let (start, _) = chars.find(|&(_, c)| is_word_char(c))?;
+        let mut end = line.len();
+        while let Some(&(i, c)) = chars.peek() {
+            if !is_word_char(c) {
+                end = i;
+                break;
+            }
+            chars.next();
+        }
+        Some(start..end)
+    })
+}
+
+// 把一个标识符拆成单词，返回的位置相对于标识符的开头。下划线是分隔符；
+// 小写字母或数字后面跟着大写字母时断开，连续的大写字母后面跟着小写字母时，最后一个大写字母属于下一个单词，
+// 所以 parseHTTPResponse 拆成 parse、HTTP、Response
+pub fn subwords(ident: &str) -> Vec<Range<usize>> {
+    let chars: Vec<(usize, char)> = ident.char_indices().collect();
+    let mut words = Vec::new();
+    let mut start = None;
+    for (k, &(i, c)) in chars.iter().enumerate() {
+        if c == '_' {
+            if let Some(s) = start.take() {
+                words.push(s..i);
+            }
+            continue;
+        }
+        let prev = k.checked_sub(1).map(|p| chars[p].1);
+        let next = chars.get(k + 1).map(|&(_, c)| c);
+        let boundary = c.is_uppercase()
+            && prev.is_some_and(|p| {
+                p.is_lowercase()
+                    || p.is_numeric()
+                    || (p.is_uppercase() && next.is_some_and(char::is_lowercase))
+            });
+        match start {
+            Some(s) if boundary => {
+                words.push(s..i);
+                start = Some(i);
+            }
+            Some(_) => {}
+            None => start = Some(i),
+        }
+    }
+    if let Some(s) = start {
+        words.push(s..ident.len());
+    }
+    words
+}
+
+// 查询在各个标识符中出现的位置，查询本身也按同样的规则拆成单词后再逐个比较
+pub fn subword_spans(line: &str, query: &str) -> Vec<Range<usize>> {
+    let wanted: Vec<String> = subwords(query)
+        .into_iter()
+        .map(|word| fold(&query[word]))
+        .collect();
+    if wanted.is_empty() {
+        return Vec::new();
+    }
+    let mut spans = Vec::new();
+    for ident in identifiers(line) {
+        let words = subwords(&line[ident.clone()]);
+        let at = |k: usize| ident.start + words[k].start..ident.start + words[k].end;
+        let mut k = 0;
+        while k + wanted.len() <= words.len() {
+            let found = wanted
+                .iter()
+                .enumerate()
+                .all(|(j, word)| fold(&line[at(k + j)]) == *word);
+            if found {
+                spans.push(at(k).start..at(k + wanted.len() - 1).end);
+                k += wanted.len();
+            } else {
+                k += 1;
+            }
+        }
+    }
+    spans
+}
+
+#[cfg(test)]
+mod identifier_tests {
+    use super::*;
+
+    fn words(ident: &str) -> Vec<&str> {
+        subwords(ident).into_iter().map(|r| &ident[r]).collect()
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let line = "let user_id = getUserId(x2);";
+        let tokens: Vec<_> = identifiers(line).map(|r| &line[r]).collect();
+        assert_eq!(tokens, vec!["let", "user_id", "getUserId", "x2"]);
+        assert_eq!(
+            words("parseHTTPResponse"),
+            vec!["parse", "HTTP", "Response"]
+        );
+        assert_eq!(words("__init__"), vec!["init"]);
+        assert_eq!(words("utf8Decode"), vec!["utf8", "Decode"]);
+        assert_eq!(words("MAX_SIZE"), vec!["MAX", "SIZE"]);
+    }
+
+    #[test]
+    fn test_subword_spans() {
+        let line = "userId identifier USER_ID getUserIdFromToken";
+        let found: Vec<_> = subword_spans(line, "user_id")
+            .into_iter()
+            .map(|r| &line[r])
+            .collect();
+        assert_eq!(found, vec!["userId", "USER_ID", "UserId"]);
+        let found: Vec<_> = subword_spans(line, "id")
+            .into_iter()
+            .map(|r| &line[r])
+            .collect();
+        assert_eq!(found, vec!["Id", "ID", "Id"]);
+        assert!(subword_spans(line, "_").is_empty());
+    }
+}
@@ -0,0 +1,92 @@
+// .gitignore 规则：递归搜索时跳过被忽略的文件和目录。支持的写法与 git 相同：
+//   # 开头是注释，\# 表示以 # 开头的模式
+//   ! 开头表示重新包含之前被忽略的路径
+//   / 结尾只匹配目录
+//   开头或中间有 / 的模式相对于 .gitignore 所在的目录，否则匹配任意层级中的名字
+// 同一个文件中后面的规则优先；深层目录中的 .gitignore 优先于上层的
+use crate::glob::Glob;
+
+#[derive(Debug, Clone)]
+struct Rule {
+    glob: Glob,
+    negated: bool,
+    dir_only: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreRules {
+    rules: Vec<Rule>,
+}
+
+impl IgnoreRules {
+    // 写错的模式直接忽略，和 git 一样不报错
+    pub fn parse(text: &str) -> IgnoreRules {
+        let rules = text.lines().filter_map(parse_rule).collect();
+        IgnoreRules { rules }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    // relative 是相对于 .gitignore 所在目录的路径。返回 Some(true) 表示忽略，
+    // Some(false) 表示被 ! 规则重新包含，None 表示没有规则匹配
+    pub fn matched(&self, relative: &str, is_dir: bool) -> Option<bool> {
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| (is_dir || !rule.dir_only) && rule.glob.is_match(relative))
+            .map(|rule| !rule.negated)
+    }
+}
+
+fn parse_rule(line: &str) -> Option<Rule> {
+    // 行尾的空格不算在模式中，除非用 \ 转义
+    let mut line = line.trim_end_matches('\r');
+    if !line.ends_with("\\ ") {
+        line = line.trim_end();
+    }
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let (negated, line) = match line.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, line.strip_prefix('\\').unwrap_or(line)),
+    };
+    let (dir_only, line) = match line.strip_suffix('/') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    let anchored = line.contains('/');
+    let glob = Glob::new(line.strip_prefix('/').unwrap_or(line)).ok()?;
+    Some(Rule {
+        glob: if anchored { glob.anchored() } else { glob },
+        negated,
+        dir_only,
+    })
+}
+
+#[cfg(test)]
+mod ignore_tests {
+    use super::*;
+
+    #[test]
+    fn test_rules() {
+        let rules = IgnoreRules::parse(
+            "# build output\n/target\n*.log\n!keep.log\nbuild/\ndocs/*.html\n\\#notes\n\n",
+        );
+        assert_eq!(rules.matched("target", true), Some(true));
+        // /target 只匹配最上层
+        assert_eq!(rules.matched("src/target", true), None);
+        assert_eq!(rules.matched("a/b/debug.log", false), Some(true));
+        assert_eq!(rules.matched("keep.log", false), Some(false));
+        // build/ 只匹配目录
+        assert_eq!(rules.matched("src/build", true), Some(true));
+        assert_eq!(rules.matched("src/build", false), None);
+        assert_eq!(rules.matched("docs/index.html", false), Some(true));
+        assert_eq!(rules.matched("src/docs/index.html", false), None);
+        assert_eq!(rules.matched("#notes", false), Some(true));
+        assert_eq!(rules.matched("main.rs", false), None);
+        assert!(IgnoreRules::parse("# only a comment\n").is_empty());
+    }
+}
@@ -0,0 +1,50 @@
+// Ctrl-C（SIGINT）：--follow 会一直运行下去，第一次按下 Ctrl-C 时不直接终止进程，
+// 只记下来让搜索正常收尾（输出统计信息、按是否找到匹配决定退出码）；再按一次照常终止。
+// 标准库没有提供信号处理，和 priority 模块一样直接调用 C 库的 signal(2)
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(unix)]
+mod sys {
+    use std::os::raw::c_int;
+
+    pub const SIGINT: c_int = 2;
+    // SIG_DFL 和 SIG_ERR
+    pub const DEFAULT: usize = 0;
+    pub const ERROR: usize = usize::MAX;
+
+    extern "C" {
+        pub fn signal(signum: c_int, handler: usize) -> usize;
+    }
+}
+
+// 信号处理函数里只能做异步信号安全的事：写原子变量和调用 signal 都可以
+#[cfg(unix)]
+extern "C" fn on_interrupt(_signum: std::os::raw::c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+    // SAFETY: 恢复默认的处理方式，第二次 Ctrl-C 直接终止进程
+    unsafe { sys::signal(sys::SIGINT, sys::DEFAULT) };
+}
+
+// 之后的第一次 Ctrl-C 只设置 interrupted() 的结果
+#[cfg(unix)]
+pub fn catch_interrupt() -> io::Result<()> {
+    let handler: extern "C" fn(std::os::raw::c_int) = on_interrupt;
+    // SAFETY: on_interrupt 只做异步信号安全的操作
+    match unsafe { sys::signal(sys::SIGINT, handler as usize) } {
+        sys::ERROR => Err(io::Error::last_os_error()),
+        _ => Ok(()),
+    }
+}
+
+// 其他平台上 Ctrl-C 照常终止进程
+#[cfg(not(unix))]
+pub fn catch_interrupt() -> io::Result<()> {
+    Ok(())
+}
+
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
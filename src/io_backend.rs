@@ -0,0 +1,546 @@
+// 读取文件的方式（--io-backend）。默认逐个打开文件，搜索时才边读边处理；
+// Linux 上可以选择 io_uring：一次系统调用提交一批小文件的读取，
+// 递归搜索大量小文件时减少系统调用和等待的次数。
+// 两种后端都可以跳过稀疏文件中的空洞（见 sparse 模块），只在不把二进制文件当作文本时使用
+use crate::sparse;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use std::str::FromStr;
+
+pub type Reader = Box<dyn Read + Send>;
+
+pub trait IoBackend: Send + Sync {
+    // 每次交给 open_batch 多少个文件
+    fn batch_size(&self) -> usize {
+        1
+    }
+
+    // 打开一批文件，返回的读取器和 paths 一一对应，打不开的文件在对应的位置上是错误
+    fn open_batch(&self, paths: &[&Path]) -> Vec<io::Result<Reader>>;
+}
+
+// 可移植的默认实现：只打开文件，内容在读取时才从磁盘读出
+pub struct StdBackend {
+    skip_holes: bool,
+}
+
+impl StdBackend {
+    pub fn new(skip_holes: bool) -> StdBackend {
+        StdBackend { skip_holes }
+    }
+}
+
+impl IoBackend for StdBackend {
+    fn open_batch(&self, paths: &[&Path]) -> Vec<io::Result<Reader>> {
+        paths
+            .iter()
+            .map(|path| reader(File::open(path)?, self.skip_holes))
+            .collect()
+    }
+}
+
+// 边读边搜索的文件，skip_holes 时跳过其中的空洞
+fn reader(file: File, skip_holes: bool) -> io::Result<Reader> {
+    if skip_holes {
+        sparse::skip_holes(file)
+    } else {
+        Ok(Box::new(file))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum IoBackendKind {
+    #[default]
+    Std,
+    IoUring,
+}
+
+impl FromStr for IoBackendKind {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<IoBackendKind, &'static str> {
+        match s {
+            "std" => Ok(IoBackendKind::Std),
+            "io-uring" => Ok(IoBackendKind::IoUring),
+            _ => Err("I/O backend must be std or io-uring"),
+        }
+    }
+}
+
+impl IoBackendKind {
+    // 创建选定的后端，当前系统不支持 io_uring 时返回错误
+    pub fn create(self, skip_holes: bool) -> io::Result<Box<dyn IoBackend>> {
+        match self {
+            IoBackendKind::Std => Ok(Box::new(StdBackend::new(skip_holes))),
+            IoBackendKind::IoUring => Ok(Box::new(uring::IoUringBackend::new(skip_holes)?)),
+        }
+    }
+}
+
+// 系统调用的编号只在这两种架构上核对过（新加的系统调用在大多数架构上编号相同，但也有例外）
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+mod uring {
+    use super::{reader, IoBackend, Reader};
+    use std::cell::RefCell;
+    use std::fs::File;
+    use std::io::{self, Cursor, Read, Seek, SeekFrom};
+    use std::mem;
+    use std::os::fd::AsRawFd;
+    use std::os::raw::{c_int, c_long, c_void};
+    use std::path::Path;
+    use std::ptr;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    // 一批最多提交的读取数，也就是提交队列的大小
+    const ENTRIES: u32 = 32;
+    // 更大的文件不一次读进内存，仍然边读边搜索
+    const MAX_FILE_SIZE: u64 = 1 << 20;
+
+    const SYS_IO_URING_SETUP: c_long = 425;
+    const SYS_IO_URING_ENTER: c_long = 426;
+    const IORING_OFF_SQ_RING: i64 = 0;
+    const IORING_OFF_CQ_RING: i64 = 0x800_0000;
+    const IORING_OFF_SQES: i64 = 0x1000_0000;
+    const IORING_OP_READ: u8 = 22;
+    const IORING_ENTER_GETEVENTS: u32 = 1;
+    // 和 IORING_OP_READ 同时在 5.6 加入，用它判断内核是否足够新
+    const IORING_FEAT_RW_CUR_POS: u32 = 1 << 3;
+    const PROT_READ_WRITE: c_int = 0x1 | 0x2;
+    const MAP_SHARED_POPULATE: c_int = 0x01 | 0x8000;
+    const EINTR: i32 = 4;
+
+    extern "C" {
+        fn syscall(number: c_long, ...) -> c_long;
+        fn mmap(
+            addr: *mut c_void,
+            len: usize,
+            prot: c_int,
+            flags: c_int,
+            fd: c_int,
+            offset: i64,
+        ) -> *mut c_void;
+        fn munmap(addr: *mut c_void, len: usize) -> c_int;
+        fn close(fd: c_int) -> c_int;
+    }
+
+    // 下面几个结构体和内核头文件 linux/io_uring.h 中的定义一一对应
+    #[repr(C)]
+    #[derive(Default)]
+    struct SqOffsets {
+        head: u32,
+        tail: u32,
+        ring_mask: u32,
+        ring_entries: u32,
+        flags: u32,
+        dropped: u32,
+        array: u32,
+        resv1: u32,
+        user_addr: u64,
+    }
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct CqOffsets {
+        head: u32,
+        tail: u32,
+        ring_mask: u32,
+        ring_entries: u32,
+        overflow: u32,
+        cqes: u32,
+        flags: u32,
+        resv1: u32,
+        user_addr: u64,
+    }
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct Params {
+        sq_entries: u32,
+        cq_entries: u32,
+        flags: u32,
+        sq_thread_cpu: u32,
+        sq_thread_idle: u32,
+        features: u32,
+        wq_fd: u32,
+        resv: [u32; 3],
+        sq_off: SqOffsets,
+        cq_off: CqOffsets,
+    }
+
+    // 提交队列中的一项请求
+    #[repr(C)]
+    #[derive(Default)]
+    struct Sqe {
+        opcode: u8,
+        flags: u8,
+        ioprio: u16,
+        fd: i32,
+        off: u64,
+        addr: u64,
+        len: u32,
+        rw_flags: u32,
+        user_data: u64,
+        buf_index: u16,
+        personality: u16,
+        splice_fd_in: i32,
+        addr3: u64,
+        pad: u64,
+    }
+
+    // 完成队列中的一项结果，res 是读到的字节数或者负的错误码
+    #[repr(C)]
+    struct Cqe {
+        user_data: u64,
+        res: i32,
+        flags: u32,
+    }
+
+    struct Fd(c_int);
+
+    impl Drop for Fd {
+        fn drop(&mut self) {
+            // SAFETY: 文件描述符由 io_uring_setup 返回，只在这里关闭一次
+            unsafe { close(self.0) };
+        }
+    }
+
+    // 和内核共享的一块内存
+    struct Mapping {
+        ptr: *mut u8,
+        len: usize,
+    }
+
+    impl Mapping {
+        fn new(fd: &Fd, len: usize, offset: i64) -> io::Result<Mapping> {
+            // SAFETY: 映射 io_uring 的文件描述符，长度和偏移量都按内核返回的参数计算
+            let ptr = unsafe {
+                mmap(
+                    ptr::null_mut(),
+                    len,
+                    PROT_READ_WRITE,
+                    MAP_SHARED_POPULATE,
+                    fd.0,
+                    offset,
+                )
+            };
+            if ptr as isize == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(Mapping {
+                ptr: ptr.cast(),
+                len,
+            })
+        }
+
+        // 偏移量为 offset 的字段。调用方保证 offset 来自内核返回的参数，类型与字段一致
+        unsafe fn at<T>(&self, offset: u32) -> *mut T {
+            self.ptr.add(offset as usize).cast()
+        }
+    }
+
+    impl Drop for Mapping {
+        fn drop(&mut self) {
+            // SAFETY: 与 new 中映射的地址和长度相同
+            unsafe { munmap(self.ptr.cast(), self.len) };
+        }
+    }
+
+    struct Ring {
+        params: Params,
+        sq: Mapping,
+        cq: Mapping,
+        sqes: Mapping,
+        // 最后关闭，前面的映射都已经解除
+        fd: Fd,
+    }
+
+    impl Ring {
+        fn new() -> io::Result<Ring> {
+            let mut params = Params::default();
+            // SAFETY: io_uring_setup 只写入 params，返回新的文件描述符或者 -1
+            let fd = unsafe { syscall(SYS_IO_URING_SETUP, ENTRIES, &mut params as *mut Params) };
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let fd = Fd(fd as c_int);
+            if params.features & IORING_FEAT_RW_CUR_POS == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "io_uring needs Linux 5.6 or later",
+                ));
+            }
+            let sq_len = params.sq_off.array as usize + params.sq_entries as usize * 4;
+            let cq_len =
+                params.cq_off.cqes as usize + params.cq_entries as usize * mem::size_of::<Cqe>();
+            let sqes_len = params.sq_entries as usize * mem::size_of::<Sqe>();
+            Ok(Ring {
+                sq: Mapping::new(&fd, sq_len, IORING_OFF_SQ_RING)?,
+                cq: Mapping::new(&fd, cq_len, IORING_OFF_CQ_RING)?,
+                sqes: Mapping::new(&fd, sqes_len, IORING_OFF_SQES)?,
+                params,
+                fd,
+            })
+        }
+
+        // 从文件开头读取，把每个文件读进对应的缓冲区（缓冲区的长度就是要读的字节数），
+        // 等全部完成后返回每个缓冲区和实际读到的字节数。
+        // 等待出错时内核可能还在写这些缓冲区，只能放弃它们，不再释放
+        #[allow(clippy::type_complexity)]
+        fn read_all(
+            &self,
+            reads: Vec<(c_int, Vec<u8>)>,
+        ) -> io::Result<Vec<(Vec<u8>, io::Result<usize>)>> {
+            let count = reads.len();
+            assert!(count <= self.params.sq_entries as usize);
+            let (sq_off, cq_off) = (&self.params.sq_off, &self.params.cq_off);
+            // SAFETY: 各个字段的位置来自内核返回的参数；提交队列只有这个线程写入，
+            // 尾部用 Release 写入保证内核看到完整的请求
+            unsafe {
+                let mask = *self.sq.at::<u32>(sq_off.ring_mask);
+                let tail = AtomicU32::from_ptr(self.sq.at(sq_off.tail));
+                let array = self.sq.at::<u32>(sq_off.array);
+                let sqes = self.sqes.ptr.cast::<Sqe>();
+                let mut next = tail.load(Ordering::Relaxed);
+                for (i, (fd, buffer)) in reads.iter().enumerate() {
+                    let index = next & mask;
+                    sqes.add(index as usize).write(Sqe {
+                        opcode: IORING_OP_READ,
+                        fd: *fd,
+                        addr: buffer.as_ptr() as u64,
+                        len: buffer.len() as u32,
+                        user_data: i as u64,
+                        ..Sqe::default()
+                    });
+                    array.add(index as usize).write(index);
+                    next = next.wrapping_add(1);
+                }
+                tail.store(next, Ordering::Release);
+            }
+
+            let mut results: Vec<Option<io::Result<usize>>> = (0..count).map(|_| None).collect();
+            let (mut to_submit, mut done) = (count, 0);
+            while done < count {
+                // SAFETY: 提交 to_submit 个已经写好的请求，并等待剩下的请求全部完成
+                let submitted = unsafe {
+                    syscall(
+                        SYS_IO_URING_ENTER,
+                        self.fd.0,
+                        to_submit as u32,
+                        (count - done) as u32,
+                        IORING_ENTER_GETEVENTS,
+                        ptr::null::<c_void>(),
+                        0usize,
+                    )
+                };
+                if submitted < 0 {
+                    let err = io::Error::last_os_error();
+                    if err.raw_os_error() == Some(EINTR) {
+                        continue;
+                    }
+                    mem::forget(reads);
+                    return Err(err);
+                }
+                to_submit -= submitted as usize;
+                // SAFETY: 完成队列的尾部用 Acquire 读取，之前的结果都已经由内核写好；
+                // 读完后用 Release 写入头部，把这些位置还给内核
+                unsafe {
+                    let mask = *self.cq.at::<u32>(cq_off.ring_mask);
+                    let head = AtomicU32::from_ptr(self.cq.at(cq_off.head));
+                    let tail = AtomicU32::from_ptr(self.cq.at(cq_off.tail));
+                    let cqes = self.cq.at::<Cqe>(cq_off.cqes);
+                    let (mut next, end) =
+                        (head.load(Ordering::Relaxed), tail.load(Ordering::Acquire));
+                    while next != end {
+                        let cqe = cqes.add((next & mask) as usize).read();
+                        results[cqe.user_data as usize] = Some(if cqe.res < 0 {
+                            Err(io::Error::from_raw_os_error(-cqe.res))
+                        } else {
+                            Ok(cqe.res as usize)
+                        });
+                        next = next.wrapping_add(1);
+                        done += 1;
+                    }
+                    head.store(next, Ordering::Release);
+                }
+            }
+            Ok(reads
+                .into_iter()
+                .zip(results)
+                .map(|((_, buffer), result)| (buffer, result.unwrap()))
+                .collect())
+        }
+    }
+
+    thread_local! {
+        // 每个线程一个环，第一次用到时创建，之后这个线程读取的所有批次都复用它，
+        // 省掉每批都要做的创建、映射和关闭。多个线程因此可以同时使用同一个后端
+        static RING: RefCell<Option<Ring>> = const { RefCell::new(None) };
+    }
+
+    // 用当前线程的环执行 f。出错时环中可能还有没完成的请求，丢掉它，下次用到时重新创建
+    fn with_ring<T>(f: impl FnOnce(&Ring) -> io::Result<T>) -> io::Result<T> {
+        RING.with(|slot| {
+            let mut slot = slot.borrow_mut();
+            if slot.is_none() {
+                *slot = Some(Ring::new()?);
+            }
+            let result = f(slot.as_ref().unwrap());
+            if result.is_err() {
+                *slot = None;
+            }
+            result
+        })
+    }
+
+    pub struct IoUringBackend {
+        skip_holes: bool,
+    }
+
+    impl IoUringBackend {
+        // 先创建一个环，确认内核支持（也没有被容器之类的环境禁用），顺序搜索时接着使用它
+        pub fn new(skip_holes: bool) -> io::Result<IoUringBackend> {
+            with_ring(|_| Ok(()))?;
+            Ok(IoUringBackend { skip_holes })
+        }
+    }
+
+    impl IoBackend for IoUringBackend {
+        fn batch_size(&self) -> usize {
+            ENTRIES as usize
+        }
+
+        fn open_batch(&self, paths: &[&Path]) -> Vec<io::Result<Reader>> {
+            let mut files: Vec<io::Result<(File, u64)>> = paths
+                .iter()
+                .map(|path| {
+                    let file = File::open(path)?;
+                    let metadata = file.metadata()?;
+                    // 目录、设备等不是普通文件的输入，以及大文件和空文件，都照常读取
+                    let size = if metadata.is_file() && metadata.len() <= MAX_FILE_SIZE {
+                        metadata.len()
+                    } else {
+                        0
+                    };
+                    Ok((file, size))
+                })
+                .collect();
+            let small: Vec<usize> = (0..files.len())
+                .filter(|&i| matches!(files[i], Ok((_, size)) if size > 0))
+                .collect();
+            let reads = small
+                .iter()
+                .map(|&i| {
+                    let (file, size) = files[i].as_ref().unwrap();
+                    (file.as_raw_fd(), vec![0; *size as usize])
+                })
+                .collect();
+            // 创建环或者等待出错时，这一批文件都改为照常读取
+            let read = with_ring(|ring| ring.read_all(reads));
+            let mut contents: Vec<Option<(Vec<u8>, io::Result<usize>)>> =
+                files.iter().map(|_| None).collect();
+            if let Ok(read) = read {
+                for (i, result) in small.into_iter().zip(read) {
+                    contents[i] = Some(result);
+                }
+            }
+            files
+                .drain(..)
+                .zip(contents)
+                .map(|(file, content)| {
+                    let (mut file, _) = file?;
+                    let Some((mut buffer, read)) = content else {
+                        return reader(file, self.skip_holes);
+                    };
+                    // 文件在读取前变长时，后面的内容接着从文件中读
+                    let read = read?;
+                    buffer.truncate(read);
+                    file.seek(SeekFrom::Start(read as u64))?;
+                    Ok(Box::new(Cursor::new(buffer).chain(file)) as Reader)
+                })
+                .collect()
+        }
+    }
+}
+
+#[cfg(not(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+)))]
+mod uring {
+    use std::io;
+
+    pub struct IoUringBackend;
+
+    impl IoUringBackend {
+        pub fn new(_skip_holes: bool) -> io::Result<IoUringBackend> {
+            Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "io_uring is only available on Linux",
+            ))
+        }
+    }
+
+    impl super::IoBackend for IoUringBackend {
+        fn open_batch(&self, _paths: &[&std::path::Path]) -> Vec<io::Result<super::Reader>> {
+            unreachable!()
+        }
+    }
+}
+
+#[cfg(test)]
+mod io_backend_tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn test_backend_kind() {
+        assert_eq!("std".parse(), Ok(IoBackendKind::Std));
+        assert_eq!("io-uring".parse(), Ok(IoBackendKind::IoUring));
+        assert!("uring".parse::<IoBackendKind>().is_err());
+    }
+
+    // 两种后端读出的内容相同，包括空文件、大文件、目录和不存在的文件
+    #[test]
+    fn test_open_batch() {
+        let dir = env::temp_dir().join("minigrep-io-backend");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        let mut paths = Vec::new();
+        for (i, len) in [0, 1, 100, 5000, 2 << 20].into_iter().enumerate() {
+            let path = dir.join(format!("f{i}"));
+            let contents: Vec<u8> = (0..len).map(|n| (n % 251) as u8).collect();
+            fs::write(&path, contents).unwrap();
+            paths.push(path);
+        }
+        paths.push(dir.join("missing"));
+        paths.push(dir.join("sub"));
+        let paths: Vec<&Path> = paths.iter().map(|p| p.as_path()).collect();
+
+        let read_all = |backend: &dyn IoBackend| -> Vec<Result<Vec<u8>, io::ErrorKind>> {
+            let mut results = Vec::new();
+            for chunk in paths.chunks(backend.batch_size()) {
+                for reader in backend.open_batch(chunk) {
+                    let mut contents = Vec::new();
+                    let result = reader.and_then(|mut r| r.read_to_end(&mut contents));
+                    results.push(result.map(|_| contents).map_err(|err| err.kind()));
+                }
+            }
+            results
+        };
+        let expected = read_all(&StdBackend::new(true));
+        assert_eq!(expected.len(), paths.len());
+        assert_eq!(expected[3].as_ref().unwrap().len(), 5000);
+        assert!(expected[5].is_err());
+        // 环境不支持 io_uring 时没有什么可比较的
+        // 同一个线程的第二次读取复用第一次创建的环
+        if let Ok(backend) = IoBackendKind::IoUring.create(true) {
+            assert_eq!(read_all(backend.as_ref()), expected);
+            assert_eq!(read_all(backend.as_ref()), expected);
+        }
+    }
+}
@@ -0,0 +1,415 @@
+// 按语言区分代码、注释和字符串的简易词法分析，用于 --skip-comments 和 --only-strings。
+// 只识别注释和字符串的边界，不做完整的词法分析；状态（块注释、跨行字符串）会在行与行之间保留，
+// 因此必须按顺序把每一行都交给同一个 Lexer
+use std::ops::Range;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Language {
+    Rust,
+    // C、C++、Java、JavaScript、Go 等使用 // 和 /* */ 注释的语言
+    CLike,
+    Python,
+}
+
+impl Language {
+    // 根据扩展名判断语言，不认识的文件返回 None
+    pub fn from_path(path: &str) -> Option<Language> {
+        let extension = Path::new(path).extension()?.to_str()?;
+        match extension {
+            "rs" => Some(Language::Rust),
+            "c" | "h" | "cc" | "cpp" | "cxx" | "hpp" | "java" | "js" | "jsx" | "ts" | "tsx"
+            | "go" | "cs" | "kt" | "swift" | "scala" => Some(Language::CLike),
+            "py" | "pyi" => Some(Language::Python),
+            _ => None,
+        }
+    }
+
+    fn line_comment(&self) -> &'static str {
+        match self {
+            Language::Rust | Language::CLike => "//",
+            Language::Python => "#",
+        }
+    }
+
+    fn has_block_comments(&self) -> bool {
+        *self != Language::Python
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Region {
+    Code,
+    Comment,
+    // 字符串字面量，包括两侧的引号
+    String,
+}
+
+// 只保留在允许的区域中的匹配
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CodeFilter {
+    // 丢弃与注释有重叠的匹配
+    SkipComments,
+    // 只保留完全在某个字符串字面量之内的匹配
+    OnlyStrings,
+}
+
+impl CodeFilter {
+    pub fn filter_spans(
+        &self,
+        spans: Vec<Range<usize>>,
+        segments: &[(Range<usize>, Region)],
+    ) -> Vec<Range<usize>> {
+        spans
+            .into_iter()
+            .filter(|span| self.keeps(span, segments))
+            .collect()
+    }
+
+    pub fn keeps(&self, span: &Range<usize>, segments: &[(Range<usize>, Region)]) -> bool {
+        match self {
+            CodeFilter::SkipComments => !segments.iter().any(|(range, region)| {
+                *region == Region::Comment
+                    && range.start < span.end.max(span.start + 1)
+                    && span.start < range.end
+            }),
+            CodeFilter::OnlyStrings => segments.iter().any(|(range, region)| {
+                *region == Region::String && range.start <= span.start && span.end <= range.end
+            }),
+        }
+    }
+}
+
+// 字符串的结束方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Delimiter {
+    quote: u8,
+    // Python 的三引号字符串为 3，其他为 1
+    count: usize,
+    // Rust 原始字符串 r#"..."# 中 # 的个数
+    hashes: usize,
+    escapes: bool,
+    // 是否可以不用反斜杠续行就跨越多行
+    multiline: bool,
+}
+
+impl Delimiter {
+    fn plain(quote: u8, multiline: bool) -> Delimiter {
+        Delimiter {
+            quote,
+            count: 1,
+            hashes: 0,
+            escapes: true,
+            multiline,
+        }
+    }
+
+    // 在 at 处结束时返回结束符的长度
+    fn closes_at(&self, bytes: &[u8], at: usize) -> Option<usize> {
+        let len = self.count + self.hashes;
+        let end = bytes.get(at..at + len)?;
+        let (quotes, hashes) = end.split_at(self.count);
+        (quotes.iter().all(|&b| b == self.quote) && hashes.iter().all(|&b| b == b'#'))
+            .then_some(len)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    Code,
+    // 块注释的嵌套层数，只有 Rust 允许嵌套
+    BlockComment(usize),
+    Str(Delimiter),
+}
+
+pub struct Lexer {
+    language: Language,
+    state: State,
+}
+
+impl Lexer {
+    pub fn new(language: Language) -> Lexer {
+        Lexer {
+            language,
+            state: State::Code,
+        }
+    }
+
+    // 把一行（不含换行符）切分成首尾相连的若干段。
+    // 所有的分隔符都是 ASCII 字符，所以按字节扫描得到的位置一定落在字符边界上
+    pub fn line(&mut self, line: &str) -> Vec<(Range<usize>, Region)> {
+        let bytes = line.as_bytes();
+        let mut segments = Vec::new();
+        let mut push = |range: Range<usize>, region: Region| {
+            if !range.is_empty() {
+                segments.push((range, region));
+            }
+        };
+        let mut start = 0;
+        let mut i = 0;
+        while i < bytes.len() {
+            match self.state {
+                State::Code => {
+                    if bytes[i..].starts_with(self.language.line_comment().as_bytes()) {
+                        push(start..i, Region::Code);
+                        start = i;
+                        i = bytes.len();
+                        self.state = State::BlockComment(0);
+                    } else if self.language.has_block_comments() && bytes[i..].starts_with(b"/*") {
+                        push(start..i, Region::Code);
+                        start = i;
+                        i += 2;
+                        self.state = State::BlockComment(1);
+                    } else if let Some((delimiter, len)) = self.string_start(bytes, i) {
+                        push(start..i, Region::Code);
+                        start = i;
+                        i += len;
+                        self.state = State::Str(delimiter);
+                    } else {
+                        i += self.char_literal(bytes, i).unwrap_or(1);
+                    }
+                }
+                State::BlockComment(depth) => {
+                    if bytes[i..].starts_with(b"*/") {
+                        i += 2;
+                        if depth == 1 {
+                            push(start..i, Region::Comment);
+                            start = i;
+                            self.state = State::Code;
+                        } else {
+                            self.state = State::BlockComment(depth - 1);
+                        }
+                    } else if self.language == Language::Rust && bytes[i..].starts_with(b"/*") {
+                        i += 2;
+                        self.state = State::BlockComment(depth + 1);
+                    } else {
+                        i += 1;
+                    }
+                }
+                State::Str(delimiter) => {
+                    if delimiter.escapes && bytes[i] == b'\\' {
+                        i += 2;
+                    } else if let Some(len) = delimiter.closes_at(bytes, i) {
+                        i += len;
+                        push(start..i, Region::String);
+                        start = i;
+                        self.state = State::Code;
+                    } else {
+                        i += 1;
+                    }
+                }
+            }
+        }
+        // i 超过行尾说明最后一个字符是转义用的反斜杠，字符串在下一行继续
+        let continued = i > bytes.len();
+        match self.state {
+            State::Code => push(start..bytes.len(), Region::Code),
+            // 行注释用深度 0 的块注释表示，到行尾结束
+            State::BlockComment(0) => {
+                push(start..bytes.len(), Region::Comment);
+                self.state = State::Code;
+            }
+            State::BlockComment(_) => push(start..bytes.len(), Region::Comment),
+            State::Str(delimiter) => {
+                push(start..bytes.len(), Region::String);
+                if !delimiter.multiline && !continued {
+                    self.state = State::Code;
+                }
+            }
+        }
+        segments
+    }
+
+    // 字符串的开头，返回结束方式和开头部分（前缀和引号）的长度
+    fn string_start(&self, bytes: &[u8], at: usize) -> Option<(Delimiter, usize)> {
+        let rest = &bytes[at..];
+        match self.language {
+            Language::Rust => {
+                // 前缀只能出现在标识符的开头，避免把 bar"" 中的 r 当成原始字符串
+                let after_ident = at > 0 && is_ident_byte(bytes[at - 1]);
+                let prefix = match rest {
+                    [b'b', b'r', ..] if !after_ident => 2,
+                    [b'r' | b'b', ..] if !after_ident => 1,
+                    _ => 0,
+                };
+                let raw = rest[..prefix].contains(&b'r');
+                let hashes = if raw {
+                    rest[prefix..].iter().take_while(|&&b| b == b'#').count()
+                } else {
+                    0
+                };
+                if rest.get(prefix + hashes) != Some(&b'"') {
+                    return None;
+                }
+                let delimiter = Delimiter {
+                    hashes,
+                    escapes: !raw,
+                    ..Delimiter::plain(b'"', true)
+                };
+                Some((delimiter, prefix + hashes + 1))
+            }
+            Language::CLike => match rest.first()? {
+                quote @ (b'"' | b'\'') => Some((Delimiter::plain(*quote, false), 1)),
+                // JavaScript 的模板字符串可以跨行
+                b'`' => Some((Delimiter::plain(b'`', true), 1)),
+                _ => None,
+            },
+            Language::Python => {
+                let quote = *rest.first().filter(|&&b| b == b'"' || b == b'\'')?;
+                if rest.starts_with(&[quote; 3]) {
+                    let delimiter = Delimiter {
+                        count: 3,
+                        ..Delimiter::plain(quote, true)
+                    };
+                    Some((delimiter, 3))
+                } else {
+                    Some((Delimiter::plain(quote, false), 1))
+                }
+            }
+        }
+    }
+
+    // Rust 的字符字面量（'a'、'\n'、'"'）当作代码跳过，返回它的长度；
+    // 'a 这样的生命周期不是字符字面量，返回 None
+    fn char_literal(&self, bytes: &[u8], at: usize) -> Option<usize> {
+        if self.language != Language::Rust || bytes[at] != b'\'' {
+            return None;
+        }
+        let rest = &bytes[at + 1..];
+        if rest.first() == Some(&b'\\') {
+            // 转义序列最长是 \u{10FFFF}
+            let end = rest.iter().skip(2).take(9).position(|&b| b == b'\'')?;
+            return Some(end + 4);
+        }
+        // 跳过一个完整的 UTF-8 字符
+        let char_len = std::str::from_utf8(&rest[..rest.len().min(4)])
+            .or_else(|e| std::str::from_utf8(&rest[..e.valid_up_to()]))
+            .ok()?
+            .chars()
+            .next()?
+            .len_utf8();
+        (rest.get(char_len) == Some(&b'\'')).then_some(char_len + 2)
+    }
+}
+
+fn is_ident_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
+
+#[cfg(test)]
+mod lexer_tests {
+    use super::*;
+
+    // 每一段的文字和类型，方便断言
+    fn regions<'a>(lexer: &mut Lexer, line: &'a str) -> Vec<(&'a str, Region)> {
+        lexer
+            .line(line)
+            .into_iter()
+            .map(|(range, region)| (&line[range], region))
+            .collect()
+    }
+
+    #[test]
+    fn test_rust() {
+        let mut lexer = Lexer::new(Language::Rust);
+        assert_eq!(
+            regions(&mut lexer, r#"let s = "a // b"; // note"#),
+            vec![
+                ("let s = ", Region::Code),
+                ("\"a // b\"", Region::String),
+                ("; ", Region::Code),
+                ("// note", Region::Comment),
+            ]
+        );
+        // 嵌套的块注释跨越多行
+        assert_eq!(
+            regions(&mut lexer, "x /* a /* b */"),
+            vec![("x ", Region::Code), ("/* a /* b */", Region::Comment)]
+        );
+        assert_eq!(
+            regions(&mut lexer, "c */ y"),
+            vec![("c */", Region::Comment), (" y", Region::Code)]
+        );
+        // 原始字符串、字符字面量和生命周期
+        assert_eq!(
+            regions(&mut lexer, r###"f(r#"say "hi""#, '"', 'a: x)"###),
+            vec![
+                ("f(", Region::Code),
+                (r##"r#"say "hi""#"##, Region::String),
+                (r#", '"', 'a: x)"#, Region::Code),
+            ]
+        );
+        // 普通字符串可以跨行，转义的引号不会结束字符串
+        assert_eq!(
+            regions(&mut lexer, r#"let s = "a\"b"#),
+            vec![("let s = ", Region::Code), (r#""a\"b"#, Region::String)]
+        );
+        assert_eq!(
+            regions(&mut lexer, "c\"; bar\"\""),
+            vec![
+                ("c\"", Region::String),
+                ("; bar", Region::Code),
+                ("\"\"", Region::String),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_c_like_and_python() {
+        let mut c = Lexer::new(Language::CLike);
+        assert_eq!(
+            regions(&mut c, "puts('/*'); /* x"),
+            vec![
+                ("puts(", Region::Code),
+                ("'/*'", Region::String),
+                ("); ", Region::Code),
+                ("/* x", Region::Comment),
+            ]
+        );
+        assert_eq!(regions(&mut c, "*/"), vec![("*/", Region::Comment)]);
+        // 没有结束的普通字符串不会延续到下一行
+        c.line("char *s = \"oops");
+        assert_eq!(regions(&mut c, "x"), vec![("x", Region::Code)]);
+
+        let mut py = Lexer::new(Language::Python);
+        assert_eq!(
+            regions(&mut py, "s = '#' # comment"),
+            vec![
+                ("s = ", Region::Code),
+                ("'#'", Region::String),
+                (" ", Region::Code),
+                ("# comment", Region::Comment),
+            ]
+        );
+        py.line("doc = \"\"\"first");
+        assert_eq!(
+            regions(&mut py, "# still doc\"\"\" + x"),
+            vec![
+                ("# still doc\"\"\"", Region::String),
+                (" + x", Region::Code)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_filter_spans() {
+        let line = r#"call("todo") // todo"#;
+        let segments = Lexer::new(Language::Rust).line(line);
+        let spans = vec![6..10, 16..20];
+        assert_eq!(
+            CodeFilter::SkipComments.filter_spans(spans.clone(), &segments),
+            vec![6..10]
+        );
+        assert_eq!(
+            CodeFilter::OnlyStrings.filter_spans(spans, &segments),
+            vec![6..10]
+        );
+        assert_eq!(
+            CodeFilter::OnlyStrings.filter_spans(vec![0..4, 4..7], &segments),
+            Vec::<Range<usize>>::new()
+        );
+        assert_eq!(Language::from_path("src/main.rs"), Some(Language::Rust));
+        assert_eq!(Language::from_path("a/b.py"), Some(Language::Python));
+        assert_eq!(Language::from_path("poem.txt"), None);
+    }
+}
@@ -0,0 +1,218 @@
+// 普通字符串的快速查找。从查询中挑出两个"最少见"的字节（按一般文本和代码中的字节频率估计），
+// 一次检查 8 个可能的开始位置：这两个字节都对上的位置才是候选，只在候选上比较整个查询。
+// 比较用 SWAR（把 8 个字节装进一个 u64 同时比较），大部分文本不用逐字节看。
+// 还支持 ASCII 范围内忽略大小写的查找，不需要先把整行转成小写再查找。
+// 只回答"有没有"时标准库的 str::contains 在 x86_64 上用了 SIMD，比这里更快，Pattern 仍然用它；
+// 需要具体位置（高亮、--replace、-w 等）时用这里的 find 代替 str::find
+const LO7: u64 = 0x7f7f_7f7f_7f7f_7f7f;
+
+#[derive(Debug, Clone)]
+pub struct Finder {
+    needle: Vec<u8>,
+    // 用来挑候选位置的两个字节在查询中的下标，first <= second，查询只有一个字节时两者相同
+    first: usize,
+    second: usize,
+    ignore_ascii_case: bool,
+}
+
+impl Finder {
+    pub fn new(needle: &str) -> Finder {
+        Finder::build(needle, false)
+    }
+
+    // 只对 ASCII 字母忽略大小写，其他字节必须完全相同
+    pub fn new_ignore_ascii_case(needle: &str) -> Finder {
+        Finder::build(needle, true)
+    }
+
+    fn build(needle: &str, ignore_ascii_case: bool) -> Finder {
+        let needle = needle.as_bytes().to_vec();
+        let key = |i: &usize| frequency(needle[*i].to_ascii_lowercase());
+        let rarest = (0..needle.len()).min_by_key(key).unwrap_or(0);
+        // 第二个字节尽量选和第一个不同的值，否则两个条件几乎总是同时满足
+        let second = (0..needle.len())
+            .filter(|&i| i != rarest)
+            .min_by_key(|i| (needle[*i] == needle[rarest], key(i)))
+            .unwrap_or(rarest);
+        Finder {
+            first: rarest.min(second),
+            second: rarest.max(second),
+            needle,
+            ignore_ascii_case,
+        }
+    }
+
+    // 第一次出现的字节位置，空查询在位置 0 匹配
+    pub fn find(&self, haystack: &[u8]) -> Option<usize> {
+        let n = self.needle.len();
+        if n == 0 {
+            return Some(0);
+        }
+        // 可能的开始位置的数量
+        let starts = (haystack.len() + 1).checked_sub(n)?;
+        if starts < 8 {
+            let (first, second) = (self.needle[self.first], self.needle[self.second]);
+            return (0..starts).find(|&at| {
+                self.same_byte(haystack[at + self.first], first)
+                    && self.same_byte(haystack[at + self.second], second)
+                    && self.matches(&haystack[at..at + n])
+            });
+        }
+        let first = self.byte_matcher(self.needle[self.first]);
+        let second = self.byte_matcher(self.needle[self.second]);
+        // 检查开始位置 start..start + 8，其中前 skip 个已经检查过
+        let block = |start: usize, skip: usize| {
+            let mut candidates = first(load(haystack, start + self.first))
+                & second(load(haystack, start + self.second));
+            candidates &= u64::MAX << (skip * 8);
+            while candidates != 0 {
+                let at = start + candidates.trailing_zeros() as usize / 8;
+                if self.matches(&haystack[at..at + n]) {
+                    return Some(at);
+                }
+                candidates &= candidates - 1;
+            }
+            None
+        };
+        // 开始位置都在范围内时，两次读取的 8 个字节也都在文本范围内
+        let mut start = 0;
+        while start + 8 <= starts {
+            if let Some(at) = block(start, 0) {
+                return Some(at);
+            }
+            start += 8;
+        }
+        // 剩下不足 8 个开始位置时，把最后一块往前挪，和已经检查过的部分重叠
+        if start < starts {
+            let tail = starts - 8;
+            return block(tail, start - tail);
+        }
+        None
+    }
+
+    pub fn is_match(&self, haystack: &[u8]) -> bool {
+        self.find(haystack).is_some()
+    }
+
+    // 返回一个函数：8 个字节中等于 byte 的（忽略大小写时包括另一种大小写）最高位为 1
+    fn byte_matcher(&self, byte: u8) -> impl Fn(u64) -> u64 {
+        let (a, b) = if self.ignore_ascii_case {
+            (byte.to_ascii_lowercase(), byte.to_ascii_uppercase())
+        } else {
+            (byte, byte)
+        };
+        let (a, b) = (repeat(a), repeat(b));
+        move |word| zero_bytes(word ^ a) | zero_bytes(word ^ b)
+    }
+
+    fn same_byte(&self, a: u8, b: u8) -> bool {
+        a == b || (self.ignore_ascii_case && a.eq_ignore_ascii_case(&b))
+    }
+
+    fn matches(&self, candidate: &[u8]) -> bool {
+        if self.ignore_ascii_case {
+            candidate.eq_ignore_ascii_case(&self.needle)
+        } else {
+            candidate == self.needle.as_slice()
+        }
+    }
+}
+
+fn repeat(byte: u8) -> u64 {
+    u64::from_le_bytes([byte; 8])
+}
+
+fn load(haystack: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(haystack[at..at + 8].try_into().unwrap())
+}
+
+// 为 0 的字节最高位为 1，其他位都是 0。低 7 位加上 0x7f 时只有非 0 才会进位到最高位，
+// 再或上原来的最高位，就得到"不为 0"的标志；各字节之间不会进位，所以没有误报
+fn zero_bytes(word: u64) -> u64 {
+    !(((word & LO7) + LO7) | word | LO7)
+}
+
+// 字节在一般文本和代码中出现的频繁程度（大致的排名，越大越常见），用来挑选候选字节
+fn frequency(byte: u8) -> u8 {
+    match byte {
+        b' ' => 255,
+        b'e' | b't' | b'a' | b'o' | b'i' | b'n' | b's' | b'r' => 220,
+        b'h' | b'l' | b'd' | b'c' | b'u' | b'm' | b'\n' | b'\t' => 180,
+        b'f' | b'p' | b'g' | b'w' | b'y' | b'b' | b'.' | b',' => 150,
+        b'_' | b'(' | b')' | b';' | b':' | b'=' | b'"' | b'/' | b'-' => 130,
+        b'v' | b'k' | b'0'..=b'9' => 110,
+        b'x' | b'j' | b'q' | b'z' => 70,
+        b'{' | b'}' | b'[' | b']' | b'<' | b'>' | b'\'' | b'*' | b'&' | b'!' => 60,
+        0x80.. => 40,
+        _ => 30,
+    }
+}
+
+#[cfg(test)]
+mod literal_tests {
+    use super::*;
+
+    #[test]
+    fn test_zero_bytes() {
+        let word = u64::from_le_bytes([0x00, 0x01, 0x80, 0xff, 0x00, 0x7f, 0x00, 0x10]);
+        assert_eq!(
+            zero_bytes(word).to_le_bytes(),
+            [0x80, 0, 0, 0, 0x80, 0, 0x80, 0]
+        );
+        assert_eq!(zero_bytes(repeat(0x01)), 0);
+    }
+
+    // 和 str::find 的结果相同，包括查询在文本开头、结尾、重叠、跨过 8 字节边界以及多字节字符的情况
+    #[test]
+    fn test_find_matches_std() {
+        let haystacks = [
+            "",
+            "a",
+            "aaab",
+            "abababc",
+            "hello world, hello rust",
+            "fn main() { println!(\"x\"); }",
+            "naïve café über",
+            "xxxxxxxxxxxxxxxxxxxxxxxxxxxxxz",
+            "0123456789abcdefghijklmnopqrstuvwxyz",
+        ];
+        let needles = [
+            "",
+            "a",
+            "ab",
+            "abc",
+            "world",
+            "rust",
+            "rustacean",
+            "(\"x\")",
+            "é ü",
+            "xz",
+            "zx",
+            "78",
+            "6789abcdefgh",
+            "xyz",
+        ];
+        for haystack in haystacks {
+            for needle in needles {
+                let finder = Finder::new(needle);
+                assert_eq!(
+                    finder.find(haystack.as_bytes()),
+                    haystack.find(needle),
+                    "{needle:?} in {haystack:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_find_ignore_ascii_case() {
+        let finder = Finder::new_ignore_ascii_case("todo:");
+        assert_eq!(finder.find(b"// ToDo: later"), Some(3));
+        assert_eq!(finder.find(b"// TODO later, todo: now"), Some(15));
+        assert!(!finder.is_match(b"to do:"));
+        // 非 ASCII 字节必须完全相同
+        let finder = Finder::new_ignore_ascii_case("café");
+        assert!(finder.is_match("CAFé".as_bytes()));
+        assert!(!finder.is_match("CAFÉ".as_bytes()));
+    }
+}
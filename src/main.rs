@@ -1,21 +1,246 @@
+use rust_study::cli::{self, Command, RulesCommand};
+use rust_study::compare::CompareSummary;
+use rust_study::error::AppError;
+use rust_study::output::{self, OutputFormat};
+use rust_study::preset::Preset;
+use rust_study::priority;
+use rust_study::rules;
+use rust_study::Config;
 use std::env;
+use std::fmt::Display;
+use std::io::{self, Write};
 use std::process;
-use rust_study::Config;
+
+// 和 grep 一样的退出码：0 表示找到了匹配，1 表示没有匹配，2 表示出错（包括有输入读不了）。
+// 给了 --fail-on 时 1 表示找到了达到这个严重程度的匹配
+const EXIT_NO_MATCH: i32 = 1;
+const EXIT_ERROR: i32 = 2;
+// 输出管道被关闭时的退出码，和被 SIGPIPE 终止的进程在 shell 中看到的一样（128 + 13）
+const EXIT_BROKEN_PIPE: i32 = 141;
+
+// 所有标准输出都经过这里。println! 写入失败时会 panic，例如输出交给 head 而 head 已经退出，
+// 这里改为安静地退出（管道关闭）或者报告错误
+fn emit(text: impl Display) {
+    if let Err(err) = writeln!(io::stdout(), "{text}") {
+        output_failed(err);
+    }
+}
+
+fn output_failed(err: io::Error) -> ! {
+    if err.kind() == io::ErrorKind::BrokenPipe {
+        process::exit(EXIT_BROKEN_PIPE);
+    }
+    eprintln!("Application error: cannot write output: {err}");
+    process::exit(EXIT_ERROR);
+}
+
+fn fail(err: AppError) -> ! {
+    if err.is_broken_pipe() {
+        process::exit(EXIT_BROKEN_PIPE);
+    }
+    emit(format_args!("Application error: {err}"));
+    process::exit(EXIT_ERROR);
+}
 
 fn main() {
-    // unwrap_or_else中如果 Result 是 OK 则返回 Ok 的值，否则返回 Err 的值
-    let config = Config::build(env::args()).unwrap_or_else(|err| {
-        println!("Problem parsing arguments: {err}");
-        // 终结进程
-        process::exit(1);
-    });
+    // --help 和 --version 直接输出后正常退出，参数有误时打印具体的错误并终结进程
+    let config = match cli::parse(env::args()) {
+        Ok(Command::Search(config)) => *config,
+        Ok(Command::Compare(config)) => compare(*config),
+        Ok(Command::Rules(RulesCommand::List(packs))) => list_rules(&packs),
+        Ok(Command::Rules(RulesCommand::Lint(files))) => lint_rules(&files),
+        Ok(Command::Help) => {
+            if let Err(err) = io::stdout().write_all(cli::HELP.as_bytes()) {
+                output_failed(err);
+            }
+            return;
+        }
+        Ok(Command::Version) => {
+            emit(cli::version());
+            return;
+        }
+        Err(err) => {
+            emit(format_args!("Problem parsing arguments: {err}"));
+            // 终结进程
+            process::exit(EXIT_ERROR);
+        }
+    };
+
+    if config.estimate {
+        let json = config.output == OutputFormat::Json;
+        match rust_study::estimate(config) {
+            Ok(estimate) => {
+                if json {
+                    emit(estimate.json());
+                } else {
+                    emit(estimate);
+                }
+                // 和搜索一样，有文件读不了时退出码为 2
+                if estimate.input_errors > 0 {
+                    process::exit(EXIT_ERROR);
+                }
+            }
+            Err(e) => fail(e),
+        }
+        return;
+    }
 
-    println!("Searching for {}", config.query);
-    println!("In file {}", config.file_path);
+    // JSON 输出、替换模式和文件列表的输出要能被直接使用，不能夹杂提示信息；-q 时什么都不输出
+    if config.output == OutputFormat::Text
+        && config.replace.is_none()
+        && config.list_files.is_none()
+        && !config.quiet
+    {
+        // 提示信息里的查询和文件名同样按 --ascii-output 转义
+        let text = |s: &str| {
+            if config.ascii_output {
+                output::ascii_escape(s).into_owned()
+            } else {
+                s.to_string()
+            }
+        };
+        match (&config.batch, &config.rules) {
+            (Some(batch), _) => emit(format_args!("Searching for {} batch queries", batch.len())),
+            (None, Some(pack)) => emit(format_args!("Searching for rules of {pack}")),
+            (None, None) => emit(format_args!("Searching for {}", text(&config.query))),
+        }
+        emit(format_args!(
+            "In file {}",
+            text(&config.file_paths.join(", "))
+        ));
+    }
+
+    // config 会被 run 拿走，先记下输出统计信息需要的选项
+    let show_stats = config.stats;
+    let max_results = config.max_results.unwrap_or(0);
+    let json = config.output == OutputFormat::Json;
+    let quiet = config.quiet;
+    let fail_on = config.fail_on;
+    // 在创建工作线程之前调整，工作线程会继承。做不到时只提醒一下，搜索照常进行
+    if let Some(increment) = config.nice {
+        if let Err(err) = priority::lower_priority(increment) {
+            eprintln!("warning: cannot lower the priority (--nice): {err}");
+        }
+    }
+    match rust_study::run(config) {
+        // -q 时找到第一个匹配就停止，结果总是"截断"的，只需要退出码
+        // 和 grep -q 一样，已经找到匹配时读不了的输入不影响退出码
+        Ok(result) if quiet => {
+            if result.input_errors > 0 && !result.matches_found {
+                process::exit(EXIT_ERROR);
+            }
+            if result.failed(fail_on) {
+                process::exit(EXIT_NO_MATCH);
+            }
+        }
+        Ok(result) => {
+            // 和统计信息一样，JSON 模式下用 type 字段区分的一行
+            if result.truncated && json {
+                emit(format_args!(
+                    "{{\"type\":\"truncated\",\"max_results\":{max_results}}}"
+                ));
+            } else if result.truncated {
+                emit(format_args!(
+                    "\nResults truncated: stopped after {max_results} matching lines (--max-results)"
+                ));
+            }
+            if show_stats && json {
+                emit(result.stats.json());
+            } else if show_stats {
+                emit(format_args!("\n{}", result.stats));
+            }
+            // 因为 --fail-on 失败时说明原因，否则只看退出码分不清是找到了问题还是出了错
+            if let (Some(threshold), Some(severity)) = (fail_on, result.max_severity) {
+                if severity >= threshold && json {
+                    emit(format_args!(
+                        "{{\"type\":\"fail_on\",\"severity\":\"{severity}\",\"threshold\":\"{threshold}\"}}"
+                    ));
+                } else if severity >= threshold {
+                    emit(format_args!(
+                        "\nFailed: found {severity} severity matches (--fail-on {threshold})"
+                    ));
+                }
+            }
+            if result.input_errors > 0 {
+                process::exit(EXIT_ERROR);
+            }
+            if result.failed(fail_on) {
+                process::exit(EXIT_NO_MATCH);
+            }
+        }
+        Err(_) if quiet => process::exit(EXIT_ERROR),
+        Err(e) => fail(e),
+    }
+}
+
+// compare 子命令：和 diff 一样，两边相同时退出码为 0，不同时为 1
+fn compare(config: Config) -> ! {
+    let json = config.output == OutputFormat::Json;
+    let comparison = match rust_study::compare(config) {
+        Ok(comparison) => comparison,
+        Err(e) => fail(e),
+    };
+    let lines = comparison.lines;
+    for line in &lines {
+        if json {
+            emit(line.json());
+        } else {
+            emit(line);
+        }
+    }
+    let summary = CompareSummary::of(&lines);
+    if !json {
+        emit(format_args!("\n{summary}"));
+    }
+    // 和 diff 一样，出了问题时退出码为 2，即使读得了的文件都相同
+    if comparison.input_errors > 0 {
+        process::exit(EXIT_ERROR);
+    }
+    process::exit(if summary.differs() { EXIT_NO_MATCH } else { 0 });
+}
 
-    // 我们并不关注 run 返回的 Ok 值，因此只需要用 if let 去匹配是否存在错误即可
-    if let Err(e) = rust_study::run(config) {
-        println!("Application error: {e}");
-        process::exit(1);
+// rules list：每个规则包一行概要，下面每条规则一行，没有给出规则包时列出所有内置预设
+fn list_rules(specs: &[String]) -> ! {
+    let packs: Vec<_> = match specs.is_empty() {
+        true => Preset::ALL.iter().map(|preset| preset.pack()).collect(),
+        false => specs
+            .iter()
+            .map(|spec| rust_study::load_rule_pack(spec).unwrap_or_else(|e| fail(e)))
+            .collect(),
+    };
+    for (index, pack) in packs.iter().enumerate() {
+        if index > 0 {
+            emit("");
+        }
+        emit(format_args!("{pack}: {}", pack.description));
+        for rule in &pack.rules {
+            emit(format_args!(
+                "  {:<24} {:<8} {}",
+                rule.id, rule.severity, rule.description
+            ));
+        }
+    }
+    process::exit(0);
+}
+
+// rules lint：列出每个文件的所有问题，有问题时退出码为 1，和 compare 一样只有读不了文件才算出错
+fn lint_rules(files: &[String]) -> ! {
+    let mut problems = 0;
+    for file in files {
+        let text = std::fs::read_to_string(file).unwrap_or_else(|e| fail(AppError::io(file, e)));
+        let found = rules::lint(&text);
+        if found.is_empty() {
+            emit(format_args!("{file}: ok"));
+        }
+        for problem in &found {
+            emit(format_args!("{file}: {problem}"));
+        }
+        problems += found.len();
+    }
+    if problems > 0 {
+        let s = if problems == 1 { "" } else { "s" };
+        emit(format_args!("\n{problems} problem{s} found"));
+        process::exit(EXIT_NO_MATCH);
     }
+    process::exit(0);
 }
@@ -1,11 +1,280 @@
-// math 模块，该模块下又有 arithmetic 和 geometry 两个子模块，模块内部有定义好公开的函数
+// math 模块，该模块下又有 arithmetic、matrix 和 gen 三个子模块，模块内部有定义好公开的函数
 pub mod arithmetic {
     pub fn add(a: i32, b: i32) -> i32 {
         a + b
     }
 
     // 私有的外部无法调用
+    #[allow(dead_code)]
     fn subtract(a: i32, b: i32) -> i32 {
         a - b
     }
-}
\ No newline at end of file
+}
+
+// 基于常量泛型（const generics）的定长向量与矩阵
+// 维度是类型的一部分，Matrix<2, 3> 与 Matrix<3, 2> 是不同的类型，因此维度不匹配的运算在编译期就会报错，而不是运行时 panic
+pub mod matrix {
+    use std::ops::{Add, Index, IndexMut, Mul};
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct VecN<const N: usize>(pub [f64; N]);
+
+    impl<const N: usize> VecN<N> {
+        pub fn new(values: [f64; N]) -> Self {
+            VecN(values)
+        }
+
+        pub fn zeros() -> Self {
+            VecN([0.0; N])
+        }
+
+        // 点积只对相同长度的向量有定义，这里由 const N 保证
+        pub fn dot(&self, other: &VecN<N>) -> f64 {
+            self.0.iter().zip(other.0.iter()).map(|(a, b)| a * b).sum()
+        }
+    }
+
+    impl<const N: usize> Index<usize> for VecN<N> {
+        type Output = f64;
+
+        fn index(&self, i: usize) -> &f64 {
+            &self.0[i]
+        }
+    }
+
+    impl<const N: usize> Add for VecN<N> {
+        type Output = VecN<N>;
+
+        fn add(self, rhs: VecN<N>) -> VecN<N> {
+            VecN(std::array::from_fn(|i| self.0[i] + rhs.0[i]))
+        }
+    }
+
+    // R 行 C 列的矩阵，按行存储
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct Matrix<const R: usize, const C: usize>(pub [[f64; C]; R]);
+
+    impl<const R: usize, const C: usize> Matrix<R, C> {
+        pub fn new(rows: [[f64; C]; R]) -> Self {
+            Matrix(rows)
+        }
+
+        pub fn zeros() -> Self {
+            Matrix([[0.0; C]; R])
+        }
+
+        // 转置后行列互换，返回类型也随之变成 Matrix<C, R>
+        pub fn transpose(&self) -> Matrix<C, R> {
+            Matrix(std::array::from_fn(|c| {
+                std::array::from_fn(|r| self.0[r][c])
+            }))
+        }
+    }
+
+    // 单位矩阵只对方阵有意义，因此只为 Matrix<N, N> 实现
+    impl<const N: usize> Matrix<N, N> {
+        pub fn identity() -> Self {
+            Matrix(std::array::from_fn(|r| {
+                std::array::from_fn(|c| if r == c { 1.0 } else { 0.0 })
+            }))
+        }
+    }
+
+    impl<const R: usize, const C: usize> Index<(usize, usize)> for Matrix<R, C> {
+        type Output = f64;
+
+        fn index(&self, (r, c): (usize, usize)) -> &f64 {
+            &self.0[r][c]
+        }
+    }
+
+    impl<const R: usize, const C: usize> IndexMut<(usize, usize)> for Matrix<R, C> {
+        fn index_mut(&mut self, (r, c): (usize, usize)) -> &mut f64 {
+            &mut self.0[r][c]
+        }
+    }
+
+    // 只有形状完全相同的矩阵才能相加
+    impl<const R: usize, const C: usize> Add for Matrix<R, C> {
+        type Output = Matrix<R, C>;
+
+        fn add(self, rhs: Matrix<R, C>) -> Matrix<R, C> {
+            Matrix(std::array::from_fn(|r| {
+                std::array::from_fn(|c| self.0[r][c] + rhs.0[r][c])
+            }))
+        }
+    }
+
+    // (R x C) * (C x K) = (R x K)，左矩阵的列数必须等于右矩阵的行数，否则找不到对应的 Mul 实现
+    impl<const R: usize, const C: usize, const K: usize> Mul<Matrix<C, K>> for Matrix<R, C> {
+        type Output = Matrix<R, K>;
+
+        fn mul(self, rhs: Matrix<C, K>) -> Matrix<R, K> {
+            Matrix(std::array::from_fn(|r| {
+                std::array::from_fn(|k| (0..C).map(|c| self.0[r][c] * rhs.0[c][k]).sum())
+            }))
+        }
+    }
+
+    // (R x C) * 长度为 C 的向量 = 长度为 R 的向量
+    impl<const R: usize, const C: usize> Mul<VecN<C>> for Matrix<R, C> {
+        type Output = VecN<R>;
+
+        fn mul(self, rhs: VecN<C>) -> VecN<R> {
+            VecN(std::array::from_fn(|r| {
+                (0..C).map(|c| self.0[r][c] * rhs.0[c]).sum()
+            }))
+        }
+    }
+}
+
+// 随机测试数据生成器，基于一个可设定种子的伪随机数生成器（SplitMix64）
+// 同一个种子总是生成同一串数据，随机测试失败时可以用相同的种子稳定复现
+pub mod gen {
+    use super::matrix::{Matrix, VecN};
+    use std::ops::Range;
+
+    pub struct Rng {
+        state: u64,
+    }
+
+    impl Rng {
+        pub fn seed(seed: u64) -> Rng {
+            Rng { state: seed }
+        }
+
+        pub fn next_u64(&mut self) -> u64 {
+            self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = self.state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            z ^ (z >> 31)
+        }
+
+        // 左闭右开区间 [start, end) 内的整数，区间为空时 panic
+        pub fn int(&mut self, range: Range<i64>) -> i64 {
+            assert!(range.start < range.end, "empty range");
+            let span = range.end.wrapping_sub(range.start) as u64;
+            range.start.wrapping_add((self.next_u64() % span) as i64)
+        }
+
+        // [0, 1) 内的浮点数，取高 53 位刚好填满 f64 的尾数
+        pub fn unit(&mut self) -> f64 {
+            (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+        }
+
+        pub fn float(&mut self, range: Range<f64>) -> f64 {
+            range.start + self.unit() * (range.end - range.start)
+        }
+
+        // 用闭包生成每个元素，长度由调用方决定
+        pub fn vec<T>(&mut self, len: usize, mut f: impl FnMut(&mut Rng) -> T) -> Vec<T> {
+            (0..len).map(|_| f(self)).collect()
+        }
+
+        pub fn vec_n<const N: usize>(&mut self, range: Range<f64>) -> VecN<N> {
+            VecN(std::array::from_fn(|_| self.float(range.clone())))
+        }
+
+        pub fn matrix<const R: usize, const C: usize>(
+            &mut self,
+            range: Range<f64>,
+        ) -> Matrix<R, C> {
+            Matrix(std::array::from_fn(|_| {
+                std::array::from_fn(|_| self.float(range.clone()))
+            }))
+        }
+    }
+}
+
+#[cfg(test)]
+mod matrix_tests {
+    use super::matrix::*;
+
+    #[test]
+    fn test_vec_ops() {
+        let a = VecN::new([1.0, 2.0, 3.0]);
+        let b = VecN::new([4.0, 5.0, 6.0]);
+        assert_eq!(a + b, VecN::new([5.0, 7.0, 9.0]));
+        assert_eq!(a.dot(&b), 32.0);
+        assert_eq!(a[2], 3.0);
+        // 下面这行无法通过编译：长度为 3 和 2 的向量之间没有 Add 实现
+        // let _ = a + VecN::new([1.0, 2.0]);
+    }
+
+    #[test]
+    fn test_matrix_mul_shapes() {
+        let a = Matrix::new([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]); // 2 x 3
+        let b = Matrix::new([[7.0, 8.0], [9.0, 10.0], [11.0, 12.0]]); // 3 x 2
+        let c: Matrix<2, 2> = a * b;
+        assert_eq!(c, Matrix::new([[58.0, 64.0], [139.0, 154.0]]));
+        // a * a 无法通过编译：Matrix<2, 3> 没有实现 Mul<Matrix<2, 3>>
+    }
+
+    #[test]
+    fn test_matrix_helpers() {
+        let mut m = Matrix::new([[1.0, 2.0], [3.0, 4.0]]);
+        assert_eq!(m * Matrix::identity(), m);
+        assert_eq!(m + Matrix::zeros(), m);
+        assert_eq!(m.transpose(), Matrix::new([[1.0, 3.0], [2.0, 4.0]]));
+        assert_eq!(m * VecN::new([1.0, 1.0]), VecN::new([3.0, 7.0]));
+        m[(0, 1)] = 5.0;
+        assert_eq!(m[(0, 1)], 5.0);
+    }
+}
+
+#[cfg(test)]
+mod gen_tests {
+    use super::arithmetic::add;
+    use super::gen::Rng;
+    use super::matrix::Matrix;
+
+    const CASES: usize = 200;
+
+    fn close<const R: usize, const C: usize>(a: &Matrix<R, C>, b: &Matrix<R, C>) -> bool {
+        (0..R).all(|r| (0..C).all(|c| (a[(r, c)] - b[(r, c)]).abs() < 1e-9))
+    }
+
+    #[test]
+    fn test_rng_is_deterministic() {
+        let mut a = Rng::seed(42);
+        let mut b = Rng::seed(42);
+        assert_eq!(a.vec(16, |r| r.next_u64()), b.vec(16, |r| r.next_u64()));
+
+        for _ in 0..CASES {
+            let i = a.int(-5..5);
+            assert!((-5..5).contains(&i));
+            let f = a.float(1.5..2.5);
+            assert!((1.5..2.5).contains(&f));
+        }
+    }
+
+    #[test]
+    fn test_add_properties() {
+        let mut rng = Rng::seed(7);
+        for _ in 0..CASES {
+            let a = rng.int(-1_000_000..1_000_000) as i32;
+            let b = rng.int(-1_000_000..1_000_000) as i32;
+            assert_eq!(add(a, b), add(b, a));
+            assert_eq!(add(a, 0), a);
+        }
+    }
+
+    #[test]
+    fn test_matrix_properties() {
+        let mut rng = Rng::seed(2024);
+        for _ in 0..CASES {
+            let a: Matrix<2, 3> = rng.matrix(-10.0..10.0);
+            let b: Matrix<3, 4> = rng.matrix(-10.0..10.0);
+            let c: Matrix<2, 3> = rng.matrix(-10.0..10.0);
+            // (AB)^T = B^T A^T
+            assert!(close(
+                &(a * b).transpose(),
+                &(b.transpose() * a.transpose())
+            ));
+            // (A + C)B = AB + CB
+            assert!(close(&((a + c) * b), &(a * b + c * b)));
+            assert_eq!(a * Matrix::<3, 3>::identity(), a);
+        }
+    }
+}
@@ -0,0 +1,148 @@
+// 同时查找多个普通字符串：--batch 一次读取输入，对每一行找出所有出现的查询，
+// 不需要为每个查询把数据重新读一遍。查找用 Aho-Corasick 自动机，每行只扫描一遍，
+// 模式很多时也不会变慢；逐个模式查找的版本保留为 find_all_naive，用于测试和基准比较
+use crate::aho_corasick::AhoCorasick;
+use crate::fold::{fold, Folded};
+use std::ops::Range;
+
+pub struct MultiPattern {
+    // ignore_case 时已经折叠过
+    patterns: Vec<String>,
+    ignore_case: bool,
+    automaton: AhoCorasick,
+    // 每个模式第一次出现的下标；自动机对相同的模式只报告第一个
+    first: Vec<usize>,
+}
+
+impl MultiPattern {
+    // 空字符串没有意义（会在每个位置匹配），由调用方保证不传入
+    pub fn new(patterns: &[impl AsRef<str>], ignore_case: bool) -> MultiPattern {
+        let patterns: Vec<String> = patterns
+            .iter()
+            .map(|p| {
+                let p = p.as_ref();
+                if ignore_case {
+                    fold(p)
+                } else {
+                    p.to_string()
+                }
+            })
+            .collect();
+        let first = patterns
+            .iter()
+            .map(|p| patterns.iter().position(|q| q == p).unwrap())
+            .collect();
+        MultiPattern {
+            automaton: AhoCorasick::new(&patterns),
+            patterns,
+            ignore_case,
+            first,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.patterns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    // 这一行中出现的每个模式（按模式的顺序）和它互不重叠的所有位置
+    pub fn find_all(&self, line: &str) -> Vec<(usize, Vec<Range<usize>>)> {
+        self.search(line, |haystack| {
+            // 自动机给出所有位置（包括重叠的），按结束位置排列；同一个模式长度固定，
+            // 所以开始位置也是递增的，从左到右跳过与上一个重叠的，结果和 match_indices 相同
+            let mut spans: Vec<Vec<Range<usize>>> = vec![Vec::new(); self.len()];
+            for m in self.automaton.find_overlapping(haystack.as_bytes()) {
+                let found = &mut spans[m.pattern];
+                if found.last().is_none_or(|last| last.end <= m.span.start) {
+                    found.push(m.span);
+                }
+            }
+            self.first.iter().map(|&i| spans[i].clone()).collect()
+        })
+    }
+
+    // 逐个模式用 match_indices 查找，耗时与模式的数量成正比
+    pub fn find_all_naive(&self, line: &str) -> Vec<(usize, Vec<Range<usize>>)> {
+        self.search(line, |haystack| {
+            self.patterns
+                .iter()
+                .map(|pattern| {
+                    haystack
+                        .match_indices(pattern.as_str())
+                        .map(|(start, m)| start..start + m.len())
+                        .collect()
+                })
+                .collect()
+        })
+    }
+
+    // find 对（可能折叠过的）行返回每个模式的位置，这里把位置映射回原始行并去掉没有出现的模式
+    fn search(
+        &self,
+        line: &str,
+        find: impl FnOnce(&str) -> Vec<Vec<Range<usize>>>,
+    ) -> Vec<(usize, Vec<Range<usize>>)> {
+        // 折叠后的位置需要映射回原始行，两端落在一个字符中间的位置不算匹配
+        let folded = self.ignore_case.then(|| Folded::new(line));
+        let haystack = folded.as_ref().map_or(line, Folded::as_str);
+        let at = |s: Range<usize>| match &folded {
+            Some(folded) => folded.span(s),
+            None => Some(s),
+        };
+        find(haystack)
+            .into_iter()
+            .enumerate()
+            .map(|(index, spans)| (index, spans.into_iter().filter_map(at).collect::<Vec<_>>()))
+            .filter(|(_, spans)| !spans.is_empty())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::single_range_in_vec_init)] // 断言中经常出现只有一个匹配范围的 Vec
+mod multi_tests {
+    use super::*;
+
+    #[test]
+    fn test_find_all() {
+        let multi = MultiPattern::new(&["TODO", "fixme", "do"], false);
+        assert_eq!(multi.len(), 3);
+        assert_eq!(
+            multi.find_all("TODO: do it, TODO later"),
+            vec![(0, vec![0..4, 13..17]), (2, vec![6..8])]
+        );
+        assert!(multi.find_all("nothing here").is_empty());
+
+        let multi = MultiPattern::new(&["straße", "todo"], true);
+        // İ 折叠后变长，位置仍然指向原始行；ß 和 SS 折叠后相同
+        assert_eq!(
+            multi.find_all("İ STRASSE Straße ToDo"),
+            vec![(0, vec![3..10, 11..18]), (1, vec![19..23])]
+        );
+    }
+
+    // 自动机和逐个模式查找的结果必须相同：互相包含的模式、自身重叠的模式、重复的模式
+    #[test]
+    fn test_matches_naive() {
+        let patterns = ["aa", "a", "aba", "b", "aa", "bab", "Ab"];
+        let lines = ["", "aaaa", "ababab", "abaabaab", "xAbAB", "babbab a"];
+        for ignore_case in [false, true] {
+            let multi = MultiPattern::new(&patterns, ignore_case);
+            for line in lines {
+                assert_eq!(multi.find_all(line), multi.find_all_naive(line), "{line}");
+            }
+        }
+        let multi = MultiPattern::new(&patterns, false);
+        assert_eq!(
+            multi.find_all("aaa"),
+            vec![
+                (0, vec![0..2]),
+                (1, vec![0..1, 1..2, 2..3]),
+                (4, vec![0..2])
+            ]
+        );
+    }
+}
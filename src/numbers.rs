@@ -0,0 +1,259 @@
+// 从匹配行中提取数字：整数、浮点数以及 0x 开头的十六进制数
+// 例如 "status=500 latency=12.5ms size=0xff" 会得到 Int(500)、Float(12.5)、Hex(255)
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Number {
+    Int(i128),
+    Float(f64),
+    Hex(u128),
+}
+
+impl Number {
+    // 统一转换成 f64，方便做比较和统计
+    pub fn as_f64(&self) -> f64 {
+        match *self {
+            Number::Int(n) => n as f64,
+            Number::Float(f) => f,
+            Number::Hex(n) => n as f64,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NumberMatch {
+    pub value: Number,
+    // 数字在原文中的字节范围
+    pub span: Range<usize>,
+}
+
+// 数字格式：千位分隔符是可选的，例如英文习惯 1,234.5，德语习惯 1.234,5
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NumberFormat {
+    pub thousands: Option<char>,
+    pub decimal: char,
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        NumberFormat {
+            thousands: None,
+            decimal: '.',
+        }
+    }
+}
+
+pub fn extract_numbers(text: &str) -> Vec<NumberMatch> {
+    extract_numbers_with(text, &NumberFormat::default())
+}
+
+pub fn extract_numbers_with(text: &str, format: &NumberFormat) -> Vec<NumberMatch> {
+    let bytes = text.as_bytes();
+    let mut found = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let start_ok = i == 0 || !is_word_byte(bytes[i - 1]) && bytes[i - 1] != b'.';
+        if start_ok {
+            if let Some(m) = parse_at(text, i, format) {
+                i = m.span.end;
+                found.push(m);
+                continue;
+            }
+        }
+        i += 1;
+    }
+    found
+}
+
+fn is_word_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
+
+fn parse_at(text: &str, start: usize, format: &NumberFormat) -> Option<NumberMatch> {
+    let bytes = text.as_bytes();
+    let mut i = start;
+    let negative = match bytes[i] {
+        b'-' | b'+' => {
+            i += 1;
+            bytes[start] == b'-'
+        }
+        _ => false,
+    };
+    if i >= bytes.len() || !bytes[i].is_ascii_digit() {
+        return None;
+    }
+
+    // 十六进制：0x 后面至少跟一个十六进制数字。带负号时是负整数，-0x10 得到 Int(-16)
+    if bytes[i] == b'0' && matches!(bytes.get(i + 1), Some(b'x' | b'X')) {
+        let digits_start = i + 2;
+        let end = digits_start
+            + bytes[digits_start..]
+                .iter()
+                .take_while(|b| b.is_ascii_hexdigit())
+                .count();
+        if end > digits_start {
+            let value = u128::from_str_radix(&text[digits_start..end], 16).ok()?;
+            let value = match (negative, i128::try_from(value)) {
+                (false, _) => Number::Hex(value),
+                (true, Ok(n)) => Number::Int(-n),
+                (true, Err(_)) => Number::Float(-(value as f64)),
+            };
+            return Some(NumberMatch {
+                value,
+                span: start..end,
+            });
+        }
+    }
+
+    // 整数部分，遇到千位分隔符时要求后面恰好跟三位数字，否则分隔符不属于这个数字
+    let mut digits = String::new();
+    if negative {
+        digits.push('-');
+    }
+    let mut group_len = 0;
+    let mut grouped = false;
+    while i < bytes.len() {
+        let c = bytes[i] as char;
+        if c.is_ascii_digit() {
+            digits.push(c);
+            group_len += 1;
+            i += 1;
+        } else if Some(c) == format.thousands
+            && (grouped || group_len <= 3)
+            && (!grouped || group_len == 3)
+            && bytes[i + 1..]
+                .iter()
+                .take_while(|b| b.is_ascii_digit())
+                .count()
+                == 3
+        {
+            grouped = true;
+            group_len = 0;
+            i += 1;
+        } else {
+            break;
+        }
+    }
+
+    // 小数部分与指数部分
+    let mut is_float = false;
+    if i + 1 < bytes.len() && bytes[i] as char == format.decimal && bytes[i + 1].is_ascii_digit() {
+        is_float = true;
+        digits.push('.');
+        i += 1;
+        while i < bytes.len() && bytes[i].is_ascii_digit() {
+            digits.push(bytes[i] as char);
+            i += 1;
+        }
+    }
+    if i < bytes.len() && matches!(bytes[i], b'e' | b'E') {
+        let mut j = i + 1;
+        if j < bytes.len() && matches!(bytes[j], b'-' | b'+') {
+            j += 1;
+        }
+        let exp_digits = bytes[j..].iter().take_while(|b| b.is_ascii_digit()).count();
+        if exp_digits > 0 {
+            is_float = true;
+            digits.push_str(&text[i..j + exp_digits]);
+            i = j + exp_digits;
+        }
+    }
+
+    // 超出 i128 范围的整数退化为浮点数
+    let value = match (is_float, digits.parse::<i128>()) {
+        (false, Ok(n)) => Number::Int(n),
+        _ => Number::Float(digits.parse().ok()?),
+    };
+    Some(NumberMatch {
+        value,
+        span: start..i,
+    })
+}
+
+#[cfg(test)]
+mod numbers_tests {
+    use super::*;
+
+    fn values(text: &str) -> Vec<Number> {
+        extract_numbers(text).into_iter().map(|m| m.value).collect()
+    }
+
+    #[test]
+    fn test_extract_kinds() {
+        let text = "status=500 latency=12.5ms size=0xff delta=-3 ratio=1e-3";
+        assert_eq!(
+            values(text),
+            vec![
+                Number::Int(500),
+                Number::Float(12.5),
+                Number::Hex(255),
+                Number::Int(-3),
+                Number::Float(0.001),
+            ]
+        );
+        let spans: Vec<_> = extract_numbers(text).into_iter().map(|m| m.span).collect();
+        assert_eq!(&text[spans[1].clone()], "12.5");
+    }
+
+    #[test]
+    fn test_skip_identifiers_and_versions() {
+        // 标识符中的数字不算，版本号只取第一段 1.2
+        assert_eq!(values("user42 id_7 v1.2.3"), vec![]);
+        assert_eq!(values("version 1.2.3"), vec![Number::Float(1.2)]);
+        assert_eq!(values("a-b 5-"), vec![Number::Int(5)]);
+    }
+
+    #[test]
+    fn test_signed_hex() {
+        let found = extract_numbers("delta=-0x10 gain=+0x10");
+        assert_eq!(found[0].value, Number::Int(-16));
+        assert_eq!(found[0].span, 6..11);
+        assert_eq!(found[1].value, Number::Hex(16));
+        assert_eq!(found[1].span, 17..22);
+        assert_eq!(
+            values("-0xFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF"),
+            vec![Number::Float(-(u128::MAX as f64))]
+        );
+    }
+
+    #[test]
+    fn test_thousands_separators() {
+        let en = NumberFormat {
+            thousands: Some(','),
+            decimal: '.',
+        };
+        let de = NumberFormat {
+            thousands: Some('.'),
+            decimal: ',',
+        };
+        let values_with = |text: &str, f: &NumberFormat| -> Vec<Number> {
+            extract_numbers_with(text, f)
+                .into_iter()
+                .map(|m| m.value)
+                .collect()
+        };
+        assert_eq!(
+            values_with("total 1,234,567.5 bytes", &en),
+            vec![Number::Float(1234567.5)]
+        );
+        assert_eq!(values_with("1.234,5", &de), vec![Number::Float(1234.5)]);
+        // 分隔符后面不是三位数字时，视为两个独立的数字
+        assert_eq!(
+            values_with("1,23 4", &en),
+            vec![Number::Int(1), Number::Int(23), Number::Int(4)]
+        );
+        // 不开启千位分隔符时逗号就是普通字符
+        assert_eq!(values("1,234"), vec![Number::Int(1), Number::Int(234)]);
+    }
+
+    #[test]
+    fn test_big_values() {
+        let big = "170141183460469231731687303715884105728"; // i128::MAX + 1
+        assert_eq!(values(big), vec![Number::Float(1.7014118346046923e38)]);
+        assert_eq!(
+            values("0xFFFFFFFFFFFFFFFFFFFF")[0].as_f64(),
+            2f64.powi(80) - 1.0
+        );
+    }
+}
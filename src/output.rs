@@ -0,0 +1,758 @@
+// 输出相关的工具：是否使用颜色、用 ANSI 转义序列高亮匹配的部分，以及不同的输出格式
+use crate::git::BlameLine;
+use crate::rules::Severity;
+use crate::{format_hunk_line, format_line, ByteOffsets, HunkLine, SearchMatch};
+use std::borrow::Cow;
+use std::fmt::Write;
+use std::io::{self, IsTerminal};
+use std::ops::Range;
+use std::str::FromStr;
+
+// 匹配部分使用粗体红色，与 grep 的默认配色一致
+const MATCH_START: &str = "\x1b[1;31m";
+const RESET: &str = "\x1b[0m";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ColorChoice {
+    // 只有标准输出是终端时才使用颜色，重定向到文件或管道时不输出转义序列
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl FromStr for ColorChoice {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<ColorChoice, &'static str> {
+        match s {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            _ => Err("Color must be auto, always or never"),
+        }
+    }
+}
+
+impl ColorChoice {
+    pub fn enabled(&self) -> bool {
+        match self {
+            ColorChoice::Auto => stdout_is_tty(),
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+}
+
+pub fn stdout_is_tty() -> bool {
+    io::stdout().is_terminal()
+}
+
+// spans 需要按顺序排列且互不重叠，Pattern::find_spans 的结果满足这个要求
+pub fn highlight(line: &str, spans: &[Range<usize>]) -> String {
+    let mut out = String::with_capacity(line.len() + spans.len() * 11);
+    let mut last = 0;
+    for span in spans.iter().filter(|span| !span.is_empty()) {
+        out.push_str(&line[last..span.start]);
+        out.push_str(MATCH_START);
+        out.push_str(&line[span.clone()]);
+        out.push_str(RESET);
+        last = span.end;
+    }
+    out.push_str(&line[last..]);
+    out
+}
+
+// --ascii-output：非 ASCII 字符和控制字符写成 \xNN（ASCII 范围内）或 \u{...}，反斜杠写成 \\，
+// 输出可以原样贴到不支持 Unicode 的工单系统和终端里，也能看出原来是哪个字符
+pub fn ascii_escape(s: &str) -> Cow<'_, str> {
+    if !s.chars().any(|c| needs_escape(c, true)) {
+        return Cow::Borrowed(s);
+    }
+    let mut out = String::with_capacity(s.len() + 8);
+    escape_into(&mut out, s, false, true);
+    Cow::Owned(out)
+}
+
+// 转义匹配行，匹配的位置随着转义移动，高亮的仍然是原来匹配的字符
+fn ascii_match(m: &SearchMatch) -> SearchMatch<'static> {
+    let mut line = String::with_capacity(m.line.len() + 8);
+    let mut last = 0;
+    let mut spans = Vec::with_capacity(m.spans.len());
+    for span in &m.spans {
+        escape_into(&mut line, &m.line[last..span.start], false, true);
+        let start = line.len();
+        escape_into(&mut line, &m.line[span.clone()], false, true);
+        spans.push(start..line.len());
+        last = span.end;
+    }
+    escape_into(&mut line, &m.line[last..], false, true);
+    SearchMatch {
+        line_number: m.line_number,
+        offsets: m.offsets.clone(),
+        line: line.into(),
+        spans,
+        severity: m.severity,
+        scope: m
+            .scope
+            .as_deref()
+            .map(|scope| ascii_escape(scope).into_owned()),
+    }
+}
+
+// 输出格式：默认的文本格式，或者每行一条 JSON 记录（NDJSON），方便其他程序解析
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<OutputFormat, &'static str> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err("Output format must be text or json"),
+        }
+    }
+}
+
+impl OutputFormat {
+    // JSON 是给程序读的，不需要颜色。ascii 对应 --ascii-output
+    pub fn formatter(
+        &self,
+        show_file_name: bool,
+        color: bool,
+        ascii: bool,
+    ) -> Box<dyn OutputFormatter> {
+        match self {
+            OutputFormat::Text => Box::new(PlainFormatter {
+                show_file_name,
+                color,
+                ascii,
+            }),
+            OutputFormat::Json => Box::new(JsonFormatter { ascii }),
+        }
+    }
+}
+
+// 把搜索结果转换成输出的文本，返回的字符串不带换行符。
+// 需要在线程池的工作线程之间共享，所以要求 Send + Sync
+pub trait OutputFormatter: Send + Sync {
+    // 匹配行或上下文行，返回 None 表示这种行不输出
+    fn line(&self, file: &str, line: &HunkLine) -> Option<String>;
+
+    // 带有 blame 信息（最后修改这一行的提交）的匹配行
+    fn blamed_line(&self, file: &str, m: &SearchMatch, blame: &BlameLine) -> Option<String>;
+
+    // 不相连的片段之间的分隔符
+    fn separator(&self) -> Option<String>;
+
+    // 计数模式下每个文件的结果
+    fn count(&self, file: &str, count: usize) -> String;
+
+    // 二进制文件中有匹配，不输出具体的行
+    fn binary_match(&self, file: &str) -> String;
+
+    // -l / -L 列出的文件
+    fn file(&self, file: &str) -> String;
+
+    // 批量查询中某个查询的匹配行，带上查询的名字
+    fn query_line(&self, query: &str, file: &str, m: &SearchMatch) -> Option<String>;
+
+    // --fuzzy 的匹配行，带上 0 到 1 之间的相似度
+    fn scored_line(&self, file: &str, m: &SearchMatch, score: f64) -> Option<String>;
+
+    // 规则包找到的匹配行，带上规则的 id 和其中最高的严重程度
+    fn rule_line(
+        &self,
+        rules: &[&str],
+        severity: Severity,
+        file: &str,
+        m: &SearchMatch,
+    ) -> Option<String>;
+}
+
+// 与 grep 相同的文本格式：文件名:行号:内容，上下文行用 - 分隔，片段之间用 -- 分隔
+pub struct PlainFormatter {
+    pub show_file_name: bool,
+    pub color: bool,
+    // 文件名和行的内容都用 ascii_escape 转义
+    pub ascii: bool,
+}
+
+impl PlainFormatter {
+    fn text<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        if self.ascii {
+            ascii_escape(s)
+        } else {
+            Cow::Borrowed(s)
+        }
+    }
+}
+
+impl OutputFormatter for PlainFormatter {
+    fn line(&self, file: &str, line: &HunkLine) -> Option<String> {
+        // 先转义再高亮，颜色的转义序列不能被转义
+        if self.ascii {
+            let line = match line {
+                HunkLine::Match(m) => HunkLine::Match(ascii_match(m)),
+                HunkLine::Context {
+                    line_number,
+                    offset,
+                    line,
+                } => HunkLine::Context {
+                    line_number: *line_number,
+                    offset: *offset,
+                    line: ascii_escape(line).into_owned().into(),
+                },
+            };
+            let plain = PlainFormatter {
+                ascii: false,
+                ..*self
+            };
+            return plain.line(&ascii_escape(file), &line);
+        }
+        Some(match line {
+            HunkLine::Match(m) if self.color => {
+                let m = SearchMatch {
+                    line: highlight(&m.line, &m.spans).into(),
+                    ..m.clone()
+                };
+                format_line(file, &m, self.show_file_name)
+            }
+            line => format_hunk_line(file, line, self.show_file_name),
+        })
+    }
+
+    // 和 git blame 的默认输出类似，把 提交 (作者 日期) 放在行内容的前面
+    fn blamed_line(&self, file: &str, m: &SearchMatch, blame: &BlameLine) -> Option<String> {
+        let prefix = format!(
+            "{} ({} {}) ",
+            blame.short_commit(),
+            blame.author,
+            blame.date()
+        );
+        let m = SearchMatch {
+            line_number: m.line_number,
+            offsets: m.offsets.clone(),
+            severity: m.severity,
+            scope: m.scope.clone(),
+            line: format!("{prefix}{}", m.line).into(),
+            spans: m
+                .spans
+                .iter()
+                .map(|span| span.start + prefix.len()..span.end + prefix.len())
+                .collect(),
+        };
+        self.line(file, &HunkLine::Match(m))
+    }
+
+    fn separator(&self) -> Option<String> {
+        Some(String::from("--"))
+    }
+
+    fn count(&self, file: &str, count: usize) -> String {
+        if self.show_file_name {
+            format!("{}:{count}", self.text(file))
+        } else {
+            count.to_string()
+        }
+    }
+
+    fn binary_match(&self, file: &str) -> String {
+        format!("Binary file {} matches", self.text(file))
+    }
+
+    fn file(&self, file: &str) -> String {
+        self.text(file).into_owned()
+    }
+
+    // [名字] 放在整行的最前面，方便按查询筛选
+    fn query_line(&self, query: &str, file: &str, m: &SearchMatch) -> Option<String> {
+        let line = self.line(file, &HunkLine::Match(m.clone()))?;
+        Some(format!("[{}] {line}", self.text(query)))
+    }
+
+    // 和批量查询的名字一样放在最前面，结果按相似度排序，一眼就能看出从哪里开始变差
+    fn scored_line(&self, file: &str, m: &SearchMatch, score: f64) -> Option<String> {
+        let line = self.line(file, &HunkLine::Match(m.clone()))?;
+        Some(format!("[{score:.2}] {line}"))
+    }
+
+    fn rule_line(
+        &self,
+        rules: &[&str],
+        severity: Severity,
+        file: &str,
+        m: &SearchMatch,
+    ) -> Option<String> {
+        let line = self.line(file, &HunkLine::Match(m.clone()))?;
+        Some(format!(
+            "[{severity} {}] {line}",
+            self.text(&rules.join(","))
+        ))
+    }
+}
+
+// 每个匹配行一条记录：{"file": ..., "line_number": ..., "line": ...}，上下文行和分隔符不输出
+pub struct JsonFormatter {
+    // 非 ASCII 字符也按 \uXXXX 转义，输出只有 ASCII 字符
+    pub ascii: bool,
+}
+
+impl JsonFormatter {
+    fn string(&self, s: &str) -> String {
+        quote(s, self.ascii)
+    }
+
+    // 有所在范围时输出 ,"scope":...，没有时不输出这个字段
+    fn scope(&self, m: &SearchMatch) -> String {
+        match &m.scope {
+            Some(scope) => format!(",\"scope\":{}", self.string(scope)),
+            None => String::new(),
+        }
+    }
+
+    // 同样只在有字节偏移时输出 ,"byte_offset":...
+    fn offset(&self, m: &SearchMatch) -> String {
+        match &m.offsets {
+            Some(offsets) => format!(",\"byte_offset\":{}", offsets.line),
+            None => String::new(),
+        }
+    }
+}
+
+impl OutputFormatter for JsonFormatter {
+    fn line(&self, file: &str, line: &HunkLine) -> Option<String> {
+        match line {
+            HunkLine::Match(m) => Some(format!(
+                "{{\"file\":{},\"line_number\":{}{},\"line\":{}{}}}",
+                self.string(file),
+                m.line_number,
+                self.offset(m),
+                self.string(&m.line),
+                self.scope(m)
+            )),
+            HunkLine::Context { .. } => None,
+        }
+    }
+
+    fn blamed_line(&self, file: &str, m: &SearchMatch, blame: &BlameLine) -> Option<String> {
+        Some(format!(
+            "{{\"file\":{},\"line_number\":{}{},\"line\":{}{},\"commit\":{},\"author\":{},\"date\":{}}}",
+            self.string(file),
+            m.line_number,
+            self.offset(m),
+            self.string(&m.line),
+            self.scope(m),
+            self.string(&blame.commit),
+            self.string(&blame.author),
+            self.string(&blame.date())
+        ))
+    }
+
+    fn separator(&self) -> Option<String> {
+        None
+    }
+
+    fn count(&self, file: &str, count: usize) -> String {
+        format!("{{\"file\":{},\"count\":{count}}}", self.string(file))
+    }
+
+    fn binary_match(&self, file: &str) -> String {
+        format!("{{\"file\":{},\"binary\":true}}", self.string(file))
+    }
+
+    fn file(&self, file: &str) -> String {
+        format!("{{\"file\":{}}}", self.string(file))
+    }
+
+    fn query_line(&self, query: &str, file: &str, m: &SearchMatch) -> Option<String> {
+        let line = self.line(file, &HunkLine::Match(m.clone()))?;
+        // 在记录的最前面加上 "query" 字段
+        Some(format!("{{\"query\":{},{}", self.string(query), &line[1..]))
+    }
+
+    fn scored_line(&self, file: &str, m: &SearchMatch, score: f64) -> Option<String> {
+        let line = self.line(file, &HunkLine::Match(m.clone()))?;
+        // 在记录的最后加上 "score" 字段
+        Some(format!(
+            "{},\"score\":{score:.2}}}",
+            &line[..line.len() - 1]
+        ))
+    }
+
+    fn rule_line(
+        &self,
+        rules: &[&str],
+        severity: Severity,
+        file: &str,
+        m: &SearchMatch,
+    ) -> Option<String> {
+        let line = self.line(file, &HunkLine::Match(m.clone()))?;
+        let rules: Vec<String> = rules.iter().map(|rule| self.string(rule)).collect();
+        // 在记录的最前面加上 "rules" 和 "severity" 字段
+        Some(format!(
+            "{{\"rules\":[{}],\"severity\":\"{severity}\",{}",
+            rules.join(","),
+            &line[1..]
+        ))
+    }
+}
+
+// -o：匹配行只输出其中匹配的部分，每个部分单独一行，格式和整行相同（仍然带文件名和行号，
+// JSON 记录的 line 是匹配的部分）。一行中的匹配互不重叠，空的匹配不输出。
+// 包装在遮盖的里面：遮盖之后匹配位置已经调整过，被遮盖的部分输出 ***
+pub fn only_matching(inner: Box<dyn OutputFormatter>) -> Box<dyn OutputFormatter> {
+    Box::new(OnlyMatchingFormatter { inner })
+}
+
+struct OnlyMatchingFormatter {
+    inner: Box<dyn OutputFormatter>,
+}
+
+impl OnlyMatchingFormatter {
+    // 每个匹配的部分当作一个匹配行交给 format；一个部分都没有输出时这一行不输出。
+    // 部分的偏移是这个匹配在输入中的开头
+    fn parts(
+        &self,
+        m: &SearchMatch,
+        format: impl Fn(&SearchMatch) -> Option<String>,
+    ) -> Option<String> {
+        let parts: Vec<String> = m
+            .spans
+            .iter()
+            .enumerate()
+            .filter(|(_, span)| !span.is_empty())
+            .filter_map(|(index, span)| {
+                let part = &m.line[span.clone()];
+                format(&SearchMatch {
+                    line_number: m.line_number,
+                    offsets: m.offsets.as_ref().map(|offsets| {
+                        let span = offsets.spans[index].clone();
+                        ByteOffsets {
+                            line: span.start,
+                            spans: std::iter::once(span).collect(),
+                        }
+                    }),
+                    line: part.into(),
+                    spans: std::iter::once(0..part.len()).collect(),
+                    severity: m.severity,
+                    scope: m.scope.clone(),
+                })
+            })
+            .collect();
+        (!parts.is_empty()).then(|| parts.join("\n"))
+    }
+}
+
+impl OutputFormatter for OnlyMatchingFormatter {
+    fn line(&self, file: &str, line: &HunkLine) -> Option<String> {
+        match line {
+            HunkLine::Match(m) => self.parts(m, |part| {
+                self.inner.line(file, &HunkLine::Match(part.clone()))
+            }),
+            HunkLine::Context { .. } => None,
+        }
+    }
+
+    fn blamed_line(&self, file: &str, m: &SearchMatch, blame: &BlameLine) -> Option<String> {
+        self.parts(m, |part| self.inner.blamed_line(file, part, blame))
+    }
+
+    fn separator(&self) -> Option<String> {
+        self.inner.separator()
+    }
+
+    fn count(&self, file: &str, count: usize) -> String {
+        self.inner.count(file, count)
+    }
+
+    fn binary_match(&self, file: &str) -> String {
+        self.inner.binary_match(file)
+    }
+
+    fn file(&self, file: &str) -> String {
+        self.inner.file(file)
+    }
+
+    fn query_line(&self, query: &str, file: &str, m: &SearchMatch) -> Option<String> {
+        self.parts(m, |part| self.inner.query_line(query, file, part))
+    }
+
+    fn scored_line(&self, file: &str, m: &SearchMatch, score: f64) -> Option<String> {
+        self.parts(m, |part| self.inner.scored_line(file, part, score))
+    }
+
+    fn rule_line(
+        &self,
+        rules: &[&str],
+        severity: Severity,
+        file: &str,
+        m: &SearchMatch,
+    ) -> Option<String> {
+        self.parts(m, |part| self.inner.rule_line(rules, severity, file, part))
+    }
+}
+
+// 转换成带引号的 JSON 字符串，控制字符按 \uXXXX 转义
+pub(crate) fn json_string(s: &str) -> String {
+    quote(s, false)
+}
+
+fn quote(s: &str, ascii: bool) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    escape_into(&mut out, s, true, ascii);
+    out.push('"');
+    out
+}
+
+// 反斜杠和控制字符总是要转义，ascii 为 true 时非 ASCII 字符也要转义
+fn needs_escape(c: char, ascii: bool) -> bool {
+    c == '\\' || c.is_control() || (ascii && !c.is_ascii())
+}
+
+// JSON 字符串和 --ascii-output 共用的转义。JSON 中用 \n 等简写和 \uXXXX（BMP 以外的字符写成
+// UTF-16 代理对），文本中用 \xNN 和 \u{...}，和 Rust 字符串字面量的写法相同
+fn escape_into(out: &mut String, s: &str, json: bool, ascii: bool) {
+    for c in s.chars() {
+        match c {
+            '"' if json => out.push_str("\\\""),
+            '\n' if json => out.push_str("\\n"),
+            '\r' if json => out.push_str("\\r"),
+            '\t' if json => out.push_str("\\t"),
+            '\\' => out.push_str("\\\\"),
+            c if !needs_escape(c, ascii) => out.push(c),
+            c if json => {
+                for unit in c.encode_utf16(&mut [0; 2]) {
+                    write!(out, "\\u{unit:04x}").unwrap();
+                }
+            }
+            c if c.is_ascii() => write!(out, "\\x{:02x}", c as u32).unwrap(),
+            c => write!(out, "\\u{{{:x}}}", c as u32).unwrap(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod output_tests {
+    use super::*;
+
+    #[test]
+    fn test_color_choice() {
+        assert_eq!("always".parse(), Ok(ColorChoice::Always));
+        assert_eq!("never".parse(), Ok(ColorChoice::Never));
+        assert_eq!(ColorChoice::default(), ColorChoice::Auto);
+        assert!("yes".parse::<ColorChoice>().is_err());
+        assert!(ColorChoice::Always.enabled());
+        assert!(!ColorChoice::Never.enabled());
+    }
+
+    #[test]
+    fn test_highlight() {
+        assert_eq!(
+            highlight("trust the rust", &[1..5, 10..14]),
+            "t\x1b[1;31mrust\x1b[0m the \x1b[1;31mrust\x1b[0m"
+        );
+        // 空匹配（例如空查询）不需要高亮
+        assert_eq!(highlight("abc", &[0..0, 1..1]), "abc");
+        assert_eq!(highlight("abc", &[]), "abc");
+    }
+
+    #[test]
+    fn test_json_formatter() {
+        let m = HunkLine::Match(SearchMatch {
+            line_number: 3,
+            offsets: None,
+            line: "say \"hi\"\t\u{1}".into(),
+            spans: vec![4..8, 9..9],
+            severity: None,
+            scope: None,
+        });
+        let json = OutputFormat::Json.formatter(false, true, false);
+        assert_eq!(
+            json.line("a\\b.txt", &m).unwrap(),
+            r#"{"file":"a\\b.txt","line_number":3,"line":"say \"hi\"\t\u0001"}"#
+        );
+        let context = HunkLine::Context {
+            line_number: 4,
+            offset: None,
+            line: "ctx".into(),
+        };
+        assert_eq!(json.line("a.txt", &context), None);
+        assert_eq!(json.separator(), None);
+        assert_eq!(json.count("a.txt", 2), r#"{"file":"a.txt","count":2}"#);
+        assert_eq!(json.file("a.txt"), r#"{"file":"a.txt"}"#);
+        assert_eq!(
+            json.binary_match("a.bin"),
+            r#"{"file":"a.bin","binary":true}"#
+        );
+        assert_eq!("json".parse(), Ok(OutputFormat::Json));
+        assert!("xml".parse::<OutputFormat>().is_err());
+    }
+
+    #[test]
+    fn test_plain_formatter() {
+        let plain = OutputFormat::Text.formatter(true, false, false);
+        let context = HunkLine::Context {
+            line_number: 4,
+            offset: None,
+            line: "ctx".into(),
+        };
+        assert_eq!(plain.line("a.txt", &context).unwrap(), "a.txt-4-ctx");
+        assert_eq!(plain.separator().unwrap(), "--");
+        assert_eq!(plain.count("a.txt", 2), "a.txt:2");
+        assert_eq!(plain.binary_match("a.bin"), "Binary file a.bin matches");
+        assert_eq!(plain.file("a.txt"), "a.txt");
+        let m = SearchMatch {
+            line_number: 3,
+            offsets: None,
+            line: "// TODO".into(),
+            spans: vec![3..5, 5..7],
+            severity: None,
+            scope: None,
+        };
+        assert_eq!(
+            plain.query_line("todo", "a.rs", &m).unwrap(),
+            "[todo] a.rs:3:// TODO"
+        );
+        let json = OutputFormat::Json.formatter(false, false, false);
+        assert_eq!(
+            json.query_line("todo", "a.rs", &m).unwrap(),
+            r#"{"query":"todo","file":"a.rs","line_number":3,"line":"// TODO"}"#
+        );
+        assert_eq!(
+            plain.scored_line("a.rs", &m, 0.8).unwrap(),
+            "[0.80] a.rs:3:// TODO"
+        );
+        assert_eq!(
+            json.scored_line("a.rs", &m, 1.0).unwrap(),
+            r#"{"file":"a.rs","line_number":3,"line":"// TODO","score":1.00}"#
+        );
+    }
+
+    #[test]
+    fn test_only_matching() {
+        let m = SearchMatch {
+            line_number: 3,
+            offsets: None,
+            line: "trust the rust".into(),
+            spans: vec![0..0, 1..5, 10..14],
+            severity: None,
+            scope: None,
+        };
+        let plain = only_matching(OutputFormat::Text.formatter(true, false, false));
+        assert_eq!(
+            plain.line("a.txt", &HunkLine::Match(m.clone())).unwrap(),
+            "a.txt:3:rust\na.txt:3:rust"
+        );
+        assert_eq!(
+            plain.query_line("r", "a.txt", &m).unwrap(),
+            "[r] a.txt:3:rust\n[r] a.txt:3:rust"
+        );
+        let color = only_matching(OutputFormat::Text.formatter(false, true, false));
+        assert_eq!(
+            color.line("a.txt", &HunkLine::Match(m.clone())).unwrap(),
+            "3:\x1b[1;31mrust\x1b[0m\n3:\x1b[1;31mrust\x1b[0m"
+        );
+        let json = only_matching(OutputFormat::Json.formatter(false, false, false));
+        assert_eq!(
+            json.line("a.txt", &HunkLine::Match(m.clone())).unwrap(),
+            "{\"file\":\"a.txt\",\"line_number\":3,\"line\":\"rust\"}\n\
+             {\"file\":\"a.txt\",\"line_number\":3,\"line\":\"rust\"}"
+        );
+        // 有行的偏移时，每个部分输出自己在文件中的偏移
+        let located = HunkLine::Match(SearchMatch {
+            offsets: Some(ByteOffsets {
+                line: 100,
+                spans: vec![100..100, 101..105, 110..114],
+            }),
+            ..m.clone()
+        });
+        assert_eq!(
+            plain.line("a.txt", &located).unwrap(),
+            "a.txt:3:101:rust\na.txt:3:110:rust"
+        );
+        assert_eq!(
+            json.line("a.txt", &located).unwrap(),
+            "{\"file\":\"a.txt\",\"line_number\":3,\"byte_offset\":101,\"line\":\"rust\"}\n\
+             {\"file\":\"a.txt\",\"line_number\":3,\"byte_offset\":110,\"line\":\"rust\"}"
+        );
+        // 只有空匹配时不输出，上下文行也不输出
+        let empty = SearchMatch {
+            spans: vec![0..0, 5..5],
+            ..m
+        };
+        assert_eq!(plain.line("a.txt", &HunkLine::Match(empty)), None);
+        let context = HunkLine::Context {
+            line_number: 4,
+            offset: None,
+            line: "ctx".into(),
+        };
+        assert_eq!(plain.line("a.txt", &context), None);
+    }
+
+    #[test]
+    fn test_ascii_output() {
+        assert_eq!(ascii_escape("plain text"), "plain text");
+        assert_eq!(
+            ascii_escape("caf\u{e9}\t\x1b[0m C:\\tmp \u{1f980}"),
+            "caf\\u{e9}\\x09\\x1b[0m C:\\\\tmp \\u{1f980}"
+        );
+        // 高亮的位置跟着转义移动，颜色的转义序列原样输出
+        let m = HunkLine::Match(SearchMatch {
+            line_number: 1,
+            offsets: None,
+            line: "\u{e9}t\u{e9} \u{e9}t\u{e9}".into(),
+            spans: vec![0..3, 6..11],
+            severity: None,
+            scope: Some("fn \u{e9}".into()),
+        });
+        let plain = OutputFormat::Text.formatter(true, true, true);
+        assert_eq!(
+            plain.line("d\u{e9}j\u{e0}.txt", &m).unwrap(),
+            "d\\u{e9}j\\u{e0}.txt:1:[fn \\u{e9}] \x1b[1;31m\\u{e9}t\x1b[0m\\u{e9} \
+             \x1b[1;31m\\u{e9}t\\u{e9}\x1b[0m"
+        );
+        assert_eq!(plain.file("\u{e9}.txt"), "\\u{e9}.txt");
+        // JSON 中用 \uXXXX，BMP 以外的字符写成代理对
+        let json = OutputFormat::Json.formatter(false, false, true);
+        assert_eq!(
+            json.line("a.txt", &m).unwrap(),
+            r#"{"file":"a.txt","line_number":1,"line":"\u00e9t\u00e9 \u00e9t\u00e9","scope":"fn \u00e9"}"#
+        );
+        assert_eq!(json.file("\u{1f980}"), r#"{"file":"\ud83e\udd80"}"#);
+    }
+
+    #[test]
+    fn test_blamed_line() {
+        let m = SearchMatch {
+            line_number: 2,
+            offsets: None,
+            line: "let rust".into(),
+            spans: vec![0..3, 4..8],
+            severity: None,
+            scope: Some("fn main".into()),
+        };
+        let blame = BlameLine {
+            commit: "0123456789abcdef".into(),
+            author: "Ferris".into(),
+            time: 1714521600,
+        };
+        let plain = OutputFormat::Text.formatter(false, true, false);
+        assert_eq!(
+            plain.blamed_line("a.txt", &m, &blame).unwrap(),
+            "2:[fn main] 0123456 (Ferris 2024-05-01) \x1b[1;31mlet\x1b[0m \x1b[1;31mrust\x1b[0m"
+        );
+        let json = OutputFormat::Json.formatter(false, false, false);
+        assert_eq!(
+            json.blamed_line("a.txt", &m, &blame).unwrap(),
+            r#"{"file":"a.txt","line_number":2,"line":"let rust","scope":"fn main","commit":"0123456789abcdef","author":"Ferris","date":"2024-05-01"}"#
+        );
+    }
+}
@@ -0,0 +1,121 @@
+// 多个线程共享的输出：每次写入一整块（一个文件的全部结果，或者一个带标题和上下文的片段），
+// 写入期间持有锁，所以不同线程的块不会交错在一起。块之间需要分隔符（例如上下文模式下的 --）时，
+// 也在同一次加锁中写出，分隔符只出现在两个非空的块之间
+use std::io::{self, Write};
+use std::sync::Mutex;
+
+pub struct OutputLock<W> {
+    inner: Mutex<Inner<W>>,
+}
+
+struct Inner<W> {
+    writer: W,
+    separator: Option<String>,
+    // 已经写出过非空的块，之后的块前面要加分隔符
+    wrote_block: bool,
+}
+
+impl<W: Write> OutputLock<W> {
+    pub fn new(writer: W, separator: Option<String>) -> OutputLock<W> {
+        OutputLock {
+            inner: Mutex::new(Inner {
+                writer,
+                separator,
+                wrote_block: false,
+            }),
+        }
+    }
+
+    // 空的块什么也不写，也不会引出分隔符
+    pub fn write_block(&self, block: &[u8]) -> io::Result<()> {
+        if block.is_empty() {
+            return Ok(());
+        }
+        // 其他线程写到一半 panic 时锁会中毒，已经写出的内容仍然是完整的块，可以继续使用
+        let mut inner = self
+            .inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let Inner {
+            writer,
+            separator,
+            wrote_block,
+        } = &mut *inner;
+        if let Some(separator) = separator.as_deref().filter(|_| *wrote_block) {
+            writeln!(writer, "{separator}")?;
+        }
+        writer.write_all(block)?;
+        *wrote_block = true;
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+            .into_inner()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .writer
+    }
+}
+
+#[cfg(test)]
+mod output_lock_tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn test_separators() {
+        let lock = OutputLock::new(Vec::new(), Some(String::from("--")));
+        lock.write_block(b"").unwrap();
+        lock.write_block(b"a\n").unwrap();
+        lock.write_block(b"").unwrap();
+        lock.write_block(b"b\n").unwrap();
+        assert_eq!(lock.into_inner(), b"a\n--\nb\n");
+        let lock = OutputLock::new(Vec::new(), None);
+        lock.write_block(b"a\n").unwrap();
+        lock.write_block(b"b\n").unwrap();
+        assert_eq!(lock.into_inner(), b"a\nb\n");
+    }
+
+    // 几百个线程同时写多行的块，每个块必须完整、连续地出现在输出中
+    #[test]
+    fn test_concurrent_blocks_do_not_interleave() {
+        const THREADS: usize = 300;
+        const LINES: usize = 40;
+        let lock = Arc::new(OutputLock::new(Vec::new(), Some(String::from("--"))));
+        let handles: Vec<_> = (0..THREADS)
+            .map(|t| {
+                let lock = Arc::clone(&lock);
+                thread::spawn(move || {
+                    let mut block = format!("file{t}.txt\n");
+                    for line in 0..LINES {
+                        block.push_str(&format!("file{t}.txt:{line}:match\n"));
+                    }
+                    lock.write_block(block.as_bytes()).unwrap();
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        let out = String::from_utf8(Arc::into_inner(lock).unwrap().into_inner()).unwrap();
+        let mut seen = vec![false; THREADS];
+        for block in out.split("--\n") {
+            let mut lines = block.lines();
+            let heading = lines.next().unwrap();
+            let t: usize = heading
+                .strip_prefix("file")
+                .and_then(|rest| rest.strip_suffix(".txt"))
+                .unwrap()
+                .parse()
+                .unwrap();
+            let expected: Vec<String> = (0..LINES)
+                .map(|line| format!("{heading}:{line}:match"))
+                .collect();
+            assert_eq!(lines.collect::<Vec<_>>(), expected);
+            assert!(!seen[t], "block {t} printed twice");
+            seen[t] = true;
+        }
+        assert!(seen.iter().all(|&s| s));
+    }
+}
@@ -0,0 +1,210 @@
+// 固定大小的线程池：任务通过 mpsc 通道发给工作线程，多个工作线程共享同一个接收端，
+// 所以接收端要用 Arc<Mutex<..>> 包起来，谁先拿到锁谁就取走下一个任务
+use std::mem;
+use std::sync::{mpsc, Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// 任务是只执行一次、可以跨线程传递的闭包
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+pub struct ThreadPool {
+    workers: Vec<Worker>,
+    // 用 Option 包装，drop 时先把发送端丢掉，工作线程的 recv 才会返回错误并退出循环
+    sender: Option<mpsc::Sender<Job>>,
+    // 线程池正在销毁，工作线程不再按 Throttle 休息
+    shutdown: Arc<(Mutex<bool>, Condvar)>,
+}
+
+impl ThreadPool {
+    // size 为 0 时没有线程能执行任务，按 1 处理
+    pub fn new(size: usize) -> ThreadPool {
+        ThreadPool::with_max_cpu(size, None)
+    }
+
+    // max_cpu 为 Some(P) 时每个工作线程只用 P% 的时间执行任务，见 Throttle
+    pub fn with_max_cpu(size: usize, max_cpu: Option<u32>) -> ThreadPool {
+        let (sender, receiver) = mpsc::channel();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let shutdown = Arc::new((Mutex::new(false), Condvar::new()));
+        let workers = (0..size.max(1))
+            .map(|_| {
+                let throttle = max_cpu.map(Throttle::new);
+                Worker::new(Arc::clone(&receiver), Arc::clone(&shutdown), throttle)
+            })
+            .collect();
+        ThreadPool {
+            workers,
+            sender: Some(sender),
+            shutdown,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.workers.len()
+    }
+
+    pub fn execute<F>(&self, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.sender.as_ref().unwrap().send(Box::new(f)).unwrap();
+    }
+}
+
+// 线程池离开作用域时等待所有已提交的任务执行完
+impl Drop for ThreadPool {
+    fn drop(&mut self) {
+        drop(self.sender.take());
+        // 正在休息的工作线程立即醒来，剩下的任务不再限速，不让调用方白等
+        let (stopping, wake) = &*self.shutdown;
+        *stopping.lock().unwrap() = true;
+        wake.notify_all();
+        for worker in &mut self.workers {
+            if let Some(handle) = worker.handle.take() {
+                handle.join().unwrap();
+            }
+        }
+    }
+}
+
+struct Worker {
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+impl Worker {
+    fn new(
+        receiver: Arc<Mutex<mpsc::Receiver<Job>>>,
+        shutdown: Arc<(Mutex<bool>, Condvar)>,
+        mut throttle: Option<Throttle>,
+    ) -> Worker {
+        let handle = thread::spawn(move || loop {
+            // 取到任务后锁马上释放（临时变量在这条语句结束时被 drop），执行任务时不会阻塞其他工作线程
+            let job = receiver.lock().unwrap().recv();
+            let Ok(job) = job else { break };
+            let Some(throttle) = &mut throttle else {
+                job();
+                continue;
+            };
+            // 在开始下一个任务之前休息，而不是在任务刚结束时，这样最后一个任务之后不用空等
+            let (stopping, wake) = &*shutdown;
+            let pause = throttle.take_pause();
+            let _ = wake
+                .wait_timeout_while(stopping.lock().unwrap(), pause, |stopping| !*stopping)
+                .unwrap();
+            let started = Instant::now();
+            job();
+            throttle.worked(started.elapsed());
+        });
+        Worker {
+            handle: Some(handle),
+        }
+    }
+}
+
+// 限制 CPU 占用（--max-cpu）：记下干活用的时间，按比例算出之后要休息多久。
+// 例如 25% 时每干 1 秒活休息 3 秒。以任务（一个文件）为单位，一个文件内部不会停下来
+#[derive(Debug, Clone, PartialEq)]
+pub struct Throttle {
+    percent: u32,
+    owed: Duration,
+}
+
+impl Throttle {
+    // percent 取值 1 到 100，100 表示不限制
+    pub fn new(percent: u32) -> Throttle {
+        Throttle {
+            percent: percent.clamp(1, 100),
+            owed: Duration::ZERO,
+        }
+    }
+
+    pub fn worked(&mut self, busy: Duration) {
+        self.owed += busy * (100 - self.percent) / self.percent;
+    }
+
+    // 到目前为止欠下的休息时间，取出后清零
+    pub fn take_pause(&mut self) -> Duration {
+        mem::take(&mut self.owed)
+    }
+}
+
+#[cfg(test)]
+mod pool_tests {
+    use super::*;
+
+    #[test]
+    fn test_runs_all_jobs() {
+        let counter = Arc::new(Mutex::new(0));
+        {
+            let pool = ThreadPool::new(4);
+            assert_eq!(pool.size(), 4);
+            for _ in 0..20 {
+                let counter = Arc::clone(&counter);
+                pool.execute(move || *counter.lock().unwrap() += 1);
+            }
+            // pool 在这里被 drop，会等待所有任务结束
+        }
+        assert_eq!(*counter.lock().unwrap(), 20);
+    }
+
+    fn busy(duration: Duration) {
+        let started = Instant::now();
+        while started.elapsed() < duration {}
+    }
+
+    #[test]
+    fn test_throttle() {
+        let mut throttle = Throttle::new(25);
+        throttle.worked(Duration::from_millis(100));
+        throttle.worked(Duration::from_millis(20));
+        assert_eq!(throttle.take_pause(), Duration::from_millis(360));
+        assert_eq!(throttle.take_pause(), Duration::ZERO);
+        let mut unlimited = Throttle::new(100);
+        unlimited.worked(Duration::from_secs(1));
+        assert_eq!(unlimited.take_pause(), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_max_cpu() {
+        // 一个工作线程、50%：第二个任务之前要休息和第一个任务一样长的时间。
+        // 销毁线程池时不再限速，所以先等两个任务都完成
+        let started = Instant::now();
+        let pool = ThreadPool::with_max_cpu(1, Some(50));
+        let (tx, rx) = mpsc::channel();
+        for _ in 0..2 {
+            let tx = tx.clone();
+            pool.execute(move || {
+                busy(Duration::from_millis(50));
+                tx.send(()).unwrap();
+            });
+        }
+        rx.iter().take(2).for_each(drop);
+        assert!(started.elapsed() >= Duration::from_millis(150));
+        drop(pool);
+
+        // 最后一个任务之后欠下的休息时间不会拖慢销毁
+        let started = Instant::now();
+        {
+            let pool = ThreadPool::with_max_cpu(1, Some(1));
+            pool.execute(|| busy(Duration::from_millis(20)));
+            pool.execute(|| {});
+        }
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_results_through_channel() {
+        let pool = ThreadPool::new(0);
+        assert_eq!(pool.size(), 1);
+        let (tx, rx) = mpsc::channel();
+        for i in 0..5 {
+            let tx = tx.clone();
+            pool.execute(move || tx.send(i * i).unwrap());
+        }
+        drop(tx);
+        let mut results: Vec<i32> = rx.iter().collect();
+        results.sort();
+        assert_eq!(results, vec![0, 1, 4, 9, 16]);
+    }
+}
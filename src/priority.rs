@@ -0,0 +1,46 @@
+// 降低进程的调度优先级（--nice）：长时间在后台运行的扫描让出 CPU，不拖慢交互式的工作。
+// 标准库没有提供这个功能，直接调用 C 库的 nice(2)（标准库本来就链接了 C 库）
+use std::io;
+
+// 和 nice 命令一样，不指定时降低 10
+pub const DEFAULT_NICE: i32 = 10;
+
+#[cfg(unix)]
+mod sys {
+    use std::os::raw::c_int;
+
+    extern "C" {
+        pub fn nice(inc: c_int) -> c_int;
+    }
+}
+
+// 把当前线程（Linux 上 nice 值属于线程，之后创建的线程会继承）的 nice 值增加 increment，
+// 最大到 19。nice 返回新的 nice 值，出错时返回 -1；这里只会把 nice 值调高，
+// 新值为 -1 只可能是原来就有管理员设置的负值，可以不考虑，把 -1 都当作出错
+#[cfg(unix)]
+pub fn lower_priority(increment: i32) -> io::Result<()> {
+    // SAFETY: nice 只读写调用线程的调度参数和 errno，没有指针参数
+    match unsafe { sys::nice(increment) } {
+        -1 => Err(io::Error::last_os_error()),
+        _ => Ok(()),
+    }
+}
+
+// 其他平台上没有 nice，照常运行
+#[cfg(not(unix))]
+pub fn lower_priority(_increment: i32) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod priority_tests {
+    use super::*;
+
+    // 在单独的线程中调用，Linux 上只影响这个线程，不影响同时运行的其他测试
+    #[test]
+    fn test_lower_priority() {
+        std::thread::spawn(|| lower_priority(1).unwrap())
+            .join()
+            .unwrap();
+    }
+}
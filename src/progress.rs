@@ -0,0 +1,105 @@
+// 搜索进度：已完成的文件数 / 总数、吞吐量以及预计剩余时间，以 NDJSON 的形式输出，方便图形界面绘制进度条
+use std::time::{Duration, Instant};
+
+// 两次进度事件之间的最短间隔，避免文件很多时输出过于频繁
+const INTERVAL: Duration = Duration::from_millis(200);
+
+pub struct Progress {
+    files_total: usize,
+    // 标准输入的大小未知，按 0 字节计算
+    bytes_total: u64,
+    files_done: usize,
+    bytes_done: u64,
+    started: Instant,
+    last_emit: Option<Instant>,
+}
+
+impl Progress {
+    pub fn new(files_total: usize, bytes_total: u64, started: Instant) -> Progress {
+        Progress {
+            files_total,
+            bytes_total,
+            files_done: 0,
+            bytes_done: 0,
+            started,
+            last_emit: None,
+        }
+    }
+
+    // 开始时输出一次初始事件
+    pub fn start(&mut self) -> String {
+        self.last_emit = Some(self.started);
+        self.event(self.started)
+    }
+
+    // 记录一个文件完成，距离上次输出超过间隔或者全部完成时返回新的事件
+    pub fn file_done(&mut self, bytes: u64, now: Instant) -> Option<String> {
+        self.files_done += 1;
+        self.bytes_done += bytes;
+        let finished = self.files_done == self.files_total;
+        let due = self
+            .last_emit
+            .is_none_or(|last| now.duration_since(last) >= INTERVAL);
+        if !(finished || due) {
+            return None;
+        }
+        self.last_emit = Some(now);
+        Some(self.event(now))
+    }
+
+    fn event(&self, now: Instant) -> String {
+        let elapsed = now.duration_since(self.started).as_secs_f64();
+        let rate = if elapsed > 0.0 {
+            self.bytes_done as f64 / elapsed
+        } else {
+            0.0
+        };
+        // 还没有吞吐量数据时无法估算剩余时间，输出 null
+        let eta = if self.files_done == self.files_total {
+            String::from("0")
+        } else if rate > 0.0 {
+            format!(
+                "{:.1}",
+                self.bytes_total.saturating_sub(self.bytes_done) as f64 / rate
+            )
+        } else {
+            String::from("null")
+        };
+        format!(
+            "{{\"type\":\"progress\",\"files_done\":{},\"files_total\":{},\"bytes_done\":{},\"bytes_total\":{},\"bytes_per_sec\":{:.0},\"eta_secs\":{}}}",
+            self.files_done, self.files_total, self.bytes_done, self.bytes_total, rate, eta
+        )
+    }
+}
+
+#[cfg(test)]
+mod progress_tests {
+    use super::*;
+
+    #[test]
+    fn test_events() {
+        let start = Instant::now();
+        let mut progress = Progress::new(3, 3000, start);
+        assert_eq!(
+            progress.start(),
+            r#"{"type":"progress","files_done":0,"files_total":3,"bytes_done":0,"bytes_total":3000,"bytes_per_sec":0,"eta_secs":null}"#
+        );
+        // 距离上次输出不到 200ms，不输出
+        assert_eq!(
+            progress.file_done(1000, start + Duration::from_millis(100)),
+            None
+        );
+        assert_eq!(
+            progress
+                .file_done(1000, start + Duration::from_secs(2))
+                .unwrap(),
+            r#"{"type":"progress","files_done":2,"files_total":3,"bytes_done":2000,"bytes_total":3000,"bytes_per_sec":1000,"eta_secs":1.0}"#
+        );
+        // 最后一个文件完成时总是输出
+        let last = progress
+            .file_done(1000, start + Duration::from_millis(2010))
+            .unwrap();
+        assert!(last.contains("\"files_done\":3"));
+        assert!(last.ends_with("\"eta_secs\":0}"));
+    }
+}
@@ -0,0 +1,249 @@
+// --redact REGEX：输出之前把行中匹配敏感信息模式（邮箱、令牌、IP 等）的部分换成 ***，
+// 搜索结果可以直接贴出去而不泄露秘密。通过包装 OutputFormatter 实现，
+// 所有输出格式以及批量查询、blame、抽样、git 历史的结果都经过这里。文件名不遮盖
+use crate::git::BlameLine;
+use crate::output::OutputFormatter;
+use crate::regex::Regex;
+use crate::rules::Severity;
+use crate::{ByteOffsets, HunkLine, SearchMatch};
+use std::borrow::Cow;
+use std::ops::Range;
+
+pub const MASK: &str = "***";
+
+#[derive(Debug, Clone, Default)]
+pub struct Redactor {
+    patterns: Vec<Regex>,
+}
+
+impl Redactor {
+    pub fn new(patterns: Vec<Regex>) -> Redactor {
+        Redactor { patterns }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    // 需要遮盖的范围：所有模式的匹配合并之后按顺序排列，互不重叠
+    fn ranges(&self, line: &str) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> = self
+            .patterns
+            .iter()
+            .flat_map(|re| re.find_iter(line))
+            .filter(|range| !range.is_empty())
+            .collect();
+        ranges.sort_by_key(|range| range.start);
+        let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+        merged
+    }
+
+    pub fn redact<'a>(&self, line: &'a str) -> Cow<'a, str> {
+        let ranges = self.ranges(line);
+        if ranges.is_empty() {
+            return Cow::Borrowed(line);
+        }
+        Cow::Owned(mask(line, &ranges))
+    }
+
+    // 遮盖匹配行，高亮的范围跟着移动；和遮盖部分重叠的高亮扩大到整个 ***
+    pub fn redact_match(&self, m: &SearchMatch) -> SearchMatch<'static> {
+        let ranges = self.ranges(&m.line);
+        // 原来的位置换算成遮盖之后的位置，落在遮盖部分中间的开头和结尾分别移到 *** 的两端
+        let position = |pos: usize, end: bool| {
+            let mut at = pos;
+            for range in ranges.iter().rev().filter(|range| range.start < pos) {
+                if pos < range.end {
+                    at = range.start + if end { MASK.len() } else { 0 };
+                } else {
+                    at = at - range.len() + MASK.len();
+                }
+            }
+            at
+        };
+        let mut spans: Vec<Range<usize>> = Vec::with_capacity(m.spans.len());
+        // 输入中的位置不受遮盖影响，只是和高亮的范围一起合并，保持一一对应
+        let mut byte_spans: Vec<Range<u64>> = Vec::new();
+        for (index, span) in m.spans.iter().enumerate() {
+            let span = position(span.start, false)..position(span.end, true);
+            let original = m
+                .offsets
+                .as_ref()
+                .map(|offsets| offsets.spans[index].clone());
+            match spans.last_mut() {
+                Some(last) if span.start < last.end => {
+                    last.end = last.end.max(span.end);
+                    if let (Some(last), Some(original)) = (byte_spans.last_mut(), original) {
+                        last.end = last.end.max(original.end);
+                    }
+                }
+                _ => {
+                    spans.push(span);
+                    byte_spans.extend(original);
+                }
+            }
+        }
+        SearchMatch {
+            line_number: m.line_number,
+            offsets: m.offsets.as_ref().map(|offsets| ByteOffsets {
+                line: offsets.line,
+                spans: byte_spans,
+            }),
+            line: mask(&m.line, &ranges).into(),
+            spans,
+            severity: m.severity,
+            scope: m
+                .scope
+                .as_deref()
+                .map(|scope| self.redact(scope).into_owned()),
+        }
+    }
+
+    // 没有 --redact 时原样返回，不增加包装的开销
+    pub fn wrap(&self, inner: Box<dyn OutputFormatter>) -> Box<dyn OutputFormatter> {
+        if self.is_empty() {
+            return inner;
+        }
+        Box::new(RedactingFormatter {
+            inner,
+            redactor: self.clone(),
+        })
+    }
+}
+
+fn mask(line: &str, ranges: &[Range<usize>]) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut last = 0;
+    for range in ranges {
+        out.push_str(&line[last..range.start]);
+        out.push_str(MASK);
+        last = range.end;
+    }
+    out.push_str(&line[last..]);
+    out
+}
+
+// 先遮盖再交给实际的格式，高亮和 --ascii-output 的转义都在遮盖之后进行
+pub struct RedactingFormatter {
+    inner: Box<dyn OutputFormatter>,
+    redactor: Redactor,
+}
+
+impl OutputFormatter for RedactingFormatter {
+    fn line(&self, file: &str, line: &HunkLine) -> Option<String> {
+        let line = match line {
+            HunkLine::Match(m) => HunkLine::Match(self.redactor.redact_match(m)),
+            HunkLine::Context {
+                line_number,
+                offset,
+                line,
+            } => HunkLine::Context {
+                line_number: *line_number,
+                offset: *offset,
+                line: self.redactor.redact(line).into_owned().into(),
+            },
+        };
+        self.inner.line(file, &line)
+    }
+
+    fn blamed_line(&self, file: &str, m: &SearchMatch, blame: &BlameLine) -> Option<String> {
+        self.inner
+            .blamed_line(file, &self.redactor.redact_match(m), blame)
+    }
+
+    fn separator(&self) -> Option<String> {
+        self.inner.separator()
+    }
+
+    fn count(&self, file: &str, count: usize) -> String {
+        self.inner.count(file, count)
+    }
+
+    fn binary_match(&self, file: &str) -> String {
+        self.inner.binary_match(file)
+    }
+
+    fn file(&self, file: &str) -> String {
+        self.inner.file(file)
+    }
+
+    fn query_line(&self, query: &str, file: &str, m: &SearchMatch) -> Option<String> {
+        self.inner
+            .query_line(query, file, &self.redactor.redact_match(m))
+    }
+
+    fn scored_line(&self, file: &str, m: &SearchMatch, score: f64) -> Option<String> {
+        self.inner
+            .scored_line(file, &self.redactor.redact_match(m), score)
+    }
+
+    fn rule_line(
+        &self,
+        rules: &[&str],
+        severity: Severity,
+        file: &str,
+        m: &SearchMatch,
+    ) -> Option<String> {
+        self.inner
+            .rule_line(rules, severity, file, &self.redactor.redact_match(m))
+    }
+}
+
+#[cfg(test)]
+mod redact_tests {
+    use super::*;
+    use crate::output::OutputFormat;
+
+    fn redactor(patterns: &[&str]) -> Redactor {
+        Redactor::new(patterns.iter().map(|p| Regex::new(p).unwrap()).collect())
+    }
+
+    #[test]
+    fn test_redact() {
+        let redactor = redactor(&[r"\w+@\w+\.com", r"\d+\.\d+\.\d+\.\d+", "tok_\\w+"]);
+        assert_eq!(
+            redactor.redact("mail bob@example.com from 10.0.0.1 with tok_abc123"),
+            "mail *** from *** with ***"
+        );
+        assert!(matches!(redactor.redact("nothing here"), Cow::Borrowed(_)));
+        // 重叠的匹配合并成一个 ***
+        let overlapping = self::redactor(&["ab", "bc"]);
+        assert_eq!(overlapping.redact("xabcx"), "x***x");
+        assert!(Redactor::default().is_empty());
+    }
+
+    #[test]
+    fn test_redact_match() {
+        let redactor = redactor(&["secret\\w*"]);
+        let m = SearchMatch {
+            line_number: 7,
+            offsets: None,
+            line: "key = secret123 # key".into(),
+            // 第一个 key、secret123 中间的 ret，和最后一个 key
+            spans: vec![0..3, 9..12, 18..21],
+            severity: None,
+            scope: None,
+        };
+        let redacted = redactor.redact_match(&m);
+        assert_eq!(redacted.line, "key = *** # key");
+        assert_eq!(redacted.spans, vec![0..3, 6..9, 12..15]);
+
+        let formatter = redactor.wrap(OutputFormat::Text.formatter(false, true, false));
+        assert_eq!(
+            formatter.line("a.txt", &HunkLine::Match(m)).unwrap(),
+            "7:\x1b[1;31mkey\x1b[0m = \x1b[1;31m***\x1b[0m # \x1b[1;31mkey\x1b[0m"
+        );
+        let context = HunkLine::Context {
+            line_number: 8,
+            offset: None,
+            line: "secret_two".into(),
+        };
+        assert_eq!(formatter.line("a.txt", &context).unwrap(), "8-***");
+    }
+}
@@ -0,0 +1,918 @@
+// 一个简易的正则表达式引擎，流程是：解析成语法树 -> 编译成指令序列 -> 用 Pike VM 执行
+// Pike VM 同时模拟 NFA 的所有分支（线程），每个输入字符只处理一次，因此不会像回溯实现那样出现指数级的耗时
+//
+// 支持的语法：
+// - 字面字符、. （不匹配换行）、^ 和 $（输入的开头和结尾）
+// - 字符类 [abc]、[^a-z]、转义 \d \w \s \D \W \S，以及单词边界 \b \B
+// - 分组 (...)、非捕获分组 (?:...)、命名分组 (?P<name>...) 或 (?<name>...)
+// - 选择 a|b，量词 * + ? {n} {n,} {n,m}，量词后加 ? 表示非贪婪
+use std::error::Error;
+use std::fmt;
+use std::ops::Range;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegexError {
+    pub pattern: String,
+    // 出错位置（按字符计数）
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for RegexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "invalid regex \"{}\" at position {}: {}",
+            self.pattern, self.position, self.message
+        )
+    }
+}
+
+impl Error for RegexError {}
+
+// 单次重复的上限，避免 a{100000} 这样的写法编译出巨大的指令序列
+const MAX_REPEAT: u32 = 1000;
+// 编译后指令数的上限。重复的内容会被复制，嵌套的 ((a{1000}){1000}){1000} 每一层都不超过
+// MAX_REPEAT，展开后却有 10 亿条指令，所以还要限制总数
+const MAX_PROGRAM: usize = 100_000;
+// 分组和量词嵌套层数的上限。解析、编译和释放语法树都是递归的，层数太多会栈溢出
+const MAX_NESTING: usize = 250;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Perl {
+    Digit,
+    Word,
+    Space,
+}
+
+impl Perl {
+    fn matches(self, c: char) -> bool {
+        match self {
+            Perl::Digit => c.is_ascii_digit(),
+            Perl::Word => is_word_char(c),
+            Perl::Space => c.is_whitespace(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum ClassItem {
+    Range(char, char),
+    // 第二个字段表示是否取反，例如 \D
+    Perl(Perl, bool),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Class {
+    items: Vec<ClassItem>,
+    negated: bool,
+}
+
+impl Class {
+    fn matches(&self, c: char, ignore_case: bool) -> bool {
+        let hit = |c: char| {
+            self.items.iter().any(|item| match *item {
+                ClassItem::Range(lo, hi) => lo <= c && c <= hi,
+                ClassItem::Perl(p, negated) => p.matches(c) != negated,
+            })
+        };
+        let found = hit(c) || ignore_case && (hit(lower(c)) || hit(upper(c)));
+        found != self.negated
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Assertion {
+    Start,
+    End,
+    WordBoundary,
+    NotWordBoundary,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Empty,
+    Char(char),
+    Any,
+    Class(Class),
+    Assert(Assertion),
+    // 第二个字段是捕获组编号，None 表示非捕获分组
+    Group(Box<Node>, Option<usize>),
+    Concat(Vec<Node>),
+    Alternate(Vec<Node>),
+    Repeat {
+        node: Box<Node>,
+        min: u32,
+        max: Option<u32>,
+        greedy: bool,
+    },
+}
+
+impl Node {
+    // 分组和量词的嵌套层数，Concat 和 Alternate 不算
+    fn nesting(&self) -> usize {
+        match self {
+            Node::Group(inner, _) => inner.nesting() + 1,
+            Node::Repeat { node, .. } => node.nesting() + 1,
+            Node::Concat(nodes) | Node::Alternate(nodes) => {
+                nodes.iter().map(Node::nesting).max().unwrap_or(0)
+            }
+            _ => 0,
+        }
+    }
+}
+
+struct Parser<'p> {
+    pattern: &'p str,
+    chars: Vec<char>,
+    pos: usize,
+    // 当前所在分组的层数
+    depth: usize,
+    // 下标即捕获组编号，0 号代表整个匹配
+    names: Vec<Option<String>>,
+}
+
+impl<'p> Parser<'p> {
+    fn error(&self, message: &str) -> RegexError {
+        RegexError {
+            pattern: self.pattern.to_string(),
+            position: self.pos,
+            message: message.to_string(),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn parse_alternate(&mut self) -> Result<Node, RegexError> {
+        let mut branches = vec![self.parse_concat()?];
+        while self.eat('|') {
+            branches.push(self.parse_concat()?);
+        }
+        Ok(if branches.len() == 1 {
+            branches.pop().unwrap()
+        } else {
+            Node::Alternate(branches)
+        })
+    }
+
+    fn parse_concat(&mut self) -> Result<Node, RegexError> {
+        let mut nodes = Vec::new();
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let atom = self.parse_atom()?;
+            nodes.push(self.parse_quantifier(atom)?);
+        }
+        Ok(match nodes.len() {
+            0 => Node::Empty,
+            1 => nodes.pop().unwrap(),
+            _ => Node::Concat(nodes),
+        })
+    }
+
+    fn parse_quantifier(&mut self, mut node: Node) -> Result<Node, RegexError> {
+        // a??? 这样连续的量词每个都包一层
+        let mut nesting = node.nesting();
+        loop {
+            let start = self.pos;
+            let (min, max) = match self.peek() {
+                Some('*') => (0, None),
+                Some('+') => (1, None),
+                Some('?') => (0, Some(1)),
+                Some('{') => match self.parse_counted()? {
+                    Some(range) => range,
+                    None => return Ok(node),
+                },
+                _ => return Ok(node),
+            };
+            nesting += 1;
+            if nesting > MAX_NESTING {
+                return Err(self.error("repetitions are nested too deeply"));
+            }
+            if self.pos == start {
+                self.pos += 1;
+            }
+            let greedy = !self.eat('?');
+            node = Node::Repeat {
+                node: Box::new(node),
+                min,
+                max,
+                greedy,
+            };
+        }
+    }
+
+    // 解析 {n}、{n,}、{n,m}，不是合法量词时返回 None，此时 { 按普通字符处理
+    fn parse_counted(&mut self) -> Result<Option<(u32, Option<u32>)>, RegexError> {
+        let start = self.pos;
+        self.pos += 1;
+        let min = self.parse_number();
+        let max = if self.eat(',') {
+            self.parse_number()
+        } else {
+            min
+        };
+        if min.is_none() || !self.eat('}') {
+            self.pos = start;
+            return Ok(None);
+        }
+        let min = min.unwrap();
+        if min > MAX_REPEAT || max.is_some_and(|m| m > MAX_REPEAT) {
+            return Err(self.error("repetition count is too large"));
+        }
+        if max.is_some_and(|m| m < min) {
+            return Err(self.error("invalid repetition range"));
+        }
+        Ok(Some((min, max)))
+    }
+
+    fn parse_number(&mut self) -> Option<u32> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        let digits: String = self.chars[start..self.pos].iter().collect();
+        digits.parse().ok()
+    }
+
+    fn parse_atom(&mut self) -> Result<Node, RegexError> {
+        let c = self.peek().unwrap();
+        self.pos += 1;
+        match c {
+            '(' => self.parse_group(),
+            '[' => self.parse_class().map(Node::Class),
+            '.' => Ok(Node::Any),
+            '^' => Ok(Node::Assert(Assertion::Start)),
+            '$' => Ok(Node::Assert(Assertion::End)),
+            '\\' => self.parse_escape(),
+            '*' | '+' | '?' => {
+                self.pos -= 1;
+                Err(self.error("nothing to repeat"))
+            }
+            c => Ok(Node::Char(c)),
+        }
+    }
+
+    fn parse_group(&mut self) -> Result<Node, RegexError> {
+        let index = if self.eat('?') {
+            if self.eat(':') {
+                None
+            } else {
+                self.eat('P');
+                if !self.eat('<') {
+                    return Err(self.error("unknown group flag"));
+                }
+                let start = self.pos;
+                while self.peek().is_some_and(is_word_char) {
+                    self.pos += 1;
+                }
+                let name: String = self.chars[start..self.pos].iter().collect();
+                if name.is_empty() || !self.eat('>') {
+                    return Err(self.error("invalid group name"));
+                }
+                self.names.push(Some(name));
+                Some(self.names.len() - 1)
+            }
+        } else {
+            self.names.push(None);
+            Some(self.names.len() - 1)
+        };
+        self.depth += 1;
+        if self.depth > MAX_NESTING {
+            return Err(self.error("groups are nested too deeply"));
+        }
+        let inner = self.parse_alternate()?;
+        if !self.eat(')') {
+            return Err(self.error("unclosed group"));
+        }
+        self.depth -= 1;
+        Ok(Node::Group(Box::new(inner), index))
+    }
+
+    fn parse_escape(&mut self) -> Result<Node, RegexError> {
+        let c = self
+            .peek()
+            .ok_or_else(|| self.error("trailing backslash"))?;
+        self.pos += 1;
+        let perl = |p, negated| {
+            Node::Class(Class {
+                items: vec![ClassItem::Perl(p, negated)],
+                negated: false,
+            })
+        };
+        Ok(match c {
+            'd' => perl(Perl::Digit, false),
+            'D' => perl(Perl::Digit, true),
+            'w' => perl(Perl::Word, false),
+            'W' => perl(Perl::Word, true),
+            's' => perl(Perl::Space, false),
+            'S' => perl(Perl::Space, true),
+            'b' => Node::Assert(Assertion::WordBoundary),
+            'B' => Node::Assert(Assertion::NotWordBoundary),
+            _ => Node::Char(self.escaped_char(c)?),
+        })
+    }
+
+    // 转义后代表单个字符的写法：\n \t \r 以及各种标点
+    fn escaped_char(&mut self, c: char) -> Result<char, RegexError> {
+        match c {
+            'n' => Ok('\n'),
+            't' => Ok('\t'),
+            'r' => Ok('\r'),
+            c if c.is_ascii_alphanumeric() => {
+                self.pos -= 1;
+                Err(self.error("unknown escape sequence"))
+            }
+            c => Ok(c),
+        }
+    }
+
+    fn parse_class(&mut self) -> Result<Class, RegexError> {
+        let negated = self.eat('^');
+        let mut items = Vec::new();
+        let mut first = true;
+        loop {
+            let c = self
+                .peek()
+                .ok_or_else(|| self.error("unclosed character class"))?;
+            self.pos += 1;
+            if c == ']' && !first {
+                break;
+            }
+            first = false;
+            let lo = if c == '\\' {
+                let e = self
+                    .peek()
+                    .ok_or_else(|| self.error("trailing backslash"))?;
+                self.pos += 1;
+                match e {
+                    'd' | 'D' | 'w' | 'W' | 's' | 'S' => {
+                        let p = match e.to_ascii_lowercase() {
+                            'd' => Perl::Digit,
+                            'w' => Perl::Word,
+                            _ => Perl::Space,
+                        };
+                        items.push(ClassItem::Perl(p, e.is_ascii_uppercase()));
+                        continue;
+                    }
+                    e => self.escaped_char(e)?,
+                }
+            } else {
+                c
+            };
+            // a-z 形式的范围，- 出现在末尾时按普通字符处理
+            if self.peek() == Some('-') && self.chars.get(self.pos + 1).is_some_and(|&n| n != ']') {
+                self.pos += 1;
+                let mut hi = self.peek().unwrap();
+                self.pos += 1;
+                if hi == '\\' {
+                    let e = self
+                        .peek()
+                        .ok_or_else(|| self.error("trailing backslash"))?;
+                    self.pos += 1;
+                    hi = self.escaped_char(e)?;
+                }
+                if hi < lo {
+                    return Err(self.error("invalid class range"));
+                }
+                items.push(ClassItem::Range(lo, hi));
+            } else {
+                items.push(ClassItem::Range(lo, lo));
+            }
+        }
+        Ok(Class { items, negated })
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Inst {
+    Char(char),
+    Any,
+    Class(Class),
+    Assert(Assertion),
+    // 两个分支都尝试，前者优先级更高
+    Split(usize, usize),
+    Jmp(usize),
+    Save(usize),
+    Match,
+}
+
+struct Compiler {
+    prog: Vec<Inst>,
+}
+
+// 指令数超过了 MAX_PROGRAM
+struct ProgramTooLarge;
+
+impl Compiler {
+    fn emit(&mut self, inst: Inst) -> usize {
+        self.prog.push(inst);
+        self.prog.len() - 1
+    }
+
+    fn compile(&mut self, node: &Node) -> Result<(), ProgramTooLarge> {
+        // 每个节点编译之前检查一次，重复的每个副本都会经过这里，超出上限后立即停止
+        if self.prog.len() > MAX_PROGRAM {
+            return Err(ProgramTooLarge);
+        }
+        match node {
+            Node::Empty => {}
+            Node::Char(c) => {
+                self.emit(Inst::Char(*c));
+            }
+            Node::Any => {
+                self.emit(Inst::Any);
+            }
+            Node::Class(class) => {
+                self.emit(Inst::Class(class.clone()));
+            }
+            Node::Assert(a) => {
+                self.emit(Inst::Assert(*a));
+            }
+            Node::Group(inner, None) => self.compile(inner)?,
+            Node::Group(inner, Some(index)) => {
+                self.emit(Inst::Save(index * 2));
+                self.compile(inner)?;
+                self.emit(Inst::Save(index * 2 + 1));
+            }
+            Node::Concat(nodes) => {
+                for n in nodes {
+                    self.compile(n)?;
+                }
+            }
+            Node::Alternate(branches) => {
+                // split L1, next; L1: a; jmp end; next: split L2, ...
+                let mut jumps = Vec::new();
+                for (i, branch) in branches.iter().enumerate() {
+                    if i + 1 < branches.len() {
+                        let split = self.emit(Inst::Split(0, 0));
+                        self.compile(branch)?;
+                        jumps.push(self.emit(Inst::Jmp(0)));
+                        let next = self.prog.len();
+                        self.prog[split] = Inst::Split(split + 1, next);
+                    } else {
+                        self.compile(branch)?;
+                    }
+                }
+                let end = self.prog.len();
+                for j in jumps {
+                    self.prog[j] = Inst::Jmp(end);
+                }
+            }
+            Node::Repeat {
+                node,
+                min,
+                max,
+                greedy,
+            } => {
+                for _ in 0..*min {
+                    self.compile(node)?;
+                }
+                match max {
+                    None => {
+                        // loop: split body, end; body; jmp loop
+                        let split = self.emit(Inst::Split(0, 0));
+                        self.compile(node)?;
+                        self.emit(Inst::Jmp(split));
+                        let end = self.prog.len();
+                        self.prog[split] = self.split(split + 1, end, *greedy);
+                    }
+                    Some(max) => {
+                        // 每个可选的副本都可以直接跳到末尾
+                        let mut splits = Vec::new();
+                        for _ in *min..*max {
+                            splits.push(self.emit(Inst::Split(0, 0)));
+                            self.compile(node)?;
+                        }
+                        let end = self.prog.len();
+                        for s in splits {
+                            self.prog[s] = self.split(s + 1, end, *greedy);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn split(&self, body: usize, skip: usize, greedy: bool) -> Inst {
+        if greedy {
+            Inst::Split(body, skip)
+        } else {
+            Inst::Split(skip, body)
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Regex {
+    pattern: String,
+    prog: Vec<Inst>,
+    names: Vec<Option<String>>,
+    ignore_case: bool,
+}
+
+// 一次匹配中各个捕获组的位置，未参与匹配的分组为 None
+#[derive(Debug, Clone, PartialEq)]
+pub struct Captures<'t> {
+    text: &'t str,
+    slots: Vec<Option<usize>>,
+    names: Vec<Option<String>>,
+}
+
+impl<'t> Captures<'t> {
+    pub fn get(&self, index: usize) -> Option<Range<usize>> {
+        match (self.slots.get(index * 2)?, self.slots.get(index * 2 + 1)?) {
+            (Some(start), Some(end)) => Some(*start..*end),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self, name: &str) -> Option<&'t str> {
+        let index = self.names.iter().position(|n| n.as_deref() == Some(name))?;
+        self.get(index).map(|r| &self.text[r])
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+impl Regex {
+    pub fn new(pattern: &str) -> Result<Regex, RegexError> {
+        Regex::build(pattern, false)
+    }
+
+    // 大小写不敏感的版本，对字面字符和字符类都生效
+    pub fn new_case_insensitive(pattern: &str) -> Result<Regex, RegexError> {
+        Regex::build(pattern, true)
+    }
+
+    fn build(pattern: &str, ignore_case: bool) -> Result<Regex, RegexError> {
+        let mut parser = Parser {
+            pattern,
+            chars: pattern.chars().collect(),
+            pos: 0,
+            depth: 0,
+            names: vec![None],
+        };
+        let ast = parser.parse_alternate()?;
+        if parser.pos < parser.chars.len() {
+            return Err(parser.error("unmatched closing parenthesis"));
+        }
+        let mut compiler = Compiler { prog: Vec::new() };
+        compiler.emit(Inst::Save(0));
+        if compiler.compile(&ast).is_err() {
+            parser.pos = 0;
+            return Err(parser.error("regex is too large after expanding repetitions"));
+        }
+        compiler.emit(Inst::Save(1));
+        compiler.emit(Inst::Match);
+        Ok(Regex {
+            pattern: pattern.to_string(),
+            prog: compiler.prog,
+            names: parser.names,
+            ignore_case,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    // 各个捕获组的名字，下标即分组编号
+    pub fn group_names(&self) -> &[Option<String>] {
+        &self.names
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        self.find(text).is_some()
+    }
+
+    pub fn find(&self, text: &str) -> Option<Range<usize>> {
+        self.find_at(text, 0)
+    }
+
+    // 从 start 开始查找，^ \b 等断言仍然参考 start 之前的字符
+    pub fn find_at(&self, text: &str, start: usize) -> Option<Range<usize>> {
+        let slots = self.exec(text, start)?;
+        Some(slots[0]?..slots[1]?)
+    }
+
+    pub fn captures<'t>(&self, text: &'t str) -> Option<Captures<'t>> {
+        self.captures_at(text, 0)
+    }
+
+    // 和 find_at 一样从 start 开始查找，分组的位置仍然是在整个 text 中的位置
+    pub fn captures_at<'t>(&self, text: &'t str, start: usize) -> Option<Captures<'t>> {
+        let slots = self.exec(text, start)?;
+        Some(Captures {
+            text,
+            slots,
+            names: self.names.clone(),
+        })
+    }
+
+    // 依次返回所有互不重叠的匹配
+    pub fn find_iter<'r, 't>(&'r self, text: &'t str) -> impl Iterator<Item = Range<usize>> + 'r
+    where
+        't: 'r,
+    {
+        let mut at = 0;
+        std::iter::from_fn(move || {
+            if at > text.len() {
+                return None;
+            }
+            let m = self.find_at(text, at)?;
+            // 空匹配时向前推进一个字符，避免死循环
+            at = if m.is_empty() {
+                m.end + text[m.end..].chars().next().map_or(1, char::len_utf8)
+            } else {
+                m.end
+            };
+            Some(m)
+        })
+    }
+
+    fn exec(&self, text: &str, start: usize) -> Option<Vec<Option<usize>>> {
+        let slot_count = self.names.len() * 2;
+        let mut vm = Vm {
+            regex: self,
+            text,
+            visited: vec![usize::MAX; self.prog.len()],
+            stack: Vec::new(),
+        };
+        let mut current: Vec<Thread> = Vec::new();
+        let mut next: Vec<Thread> = Vec::new();
+        let mut matched = None;
+        let mut pos = start;
+        let mut step = 0;
+
+        loop {
+            // 还没有找到匹配时，在当前位置开启一个新线程（优先级最低），相当于从每个位置都尝试一次
+            if matched.is_none() {
+                vm.add_thread(&mut current, 0, pos, vec![None; slot_count], step);
+            }
+            if current.is_empty() && (matched.is_some() || pos == text.len()) {
+                break;
+            }
+            let c = text[pos..].chars().next();
+            let next_pos = pos + c.map_or(0, char::len_utf8);
+            step += 1;
+            for thread in current.drain(..) {
+                let advance = match &self.prog[thread.pc] {
+                    Inst::Match => {
+                        // 优先级更低的线程全部丢弃，这就是"最左优先"语义
+                        matched = Some(thread.slots);
+                        break;
+                    }
+                    Inst::Char(x) => c.is_some_and(|c| self.char_eq(*x, c)),
+                    Inst::Any => c.is_some_and(|c| c != '\n'),
+                    Inst::Class(class) => c.is_some_and(|c| class.matches(c, self.ignore_case)),
+                    _ => false,
+                };
+                if advance {
+                    vm.add_thread(&mut next, thread.pc + 1, next_pos, thread.slots, step);
+                }
+            }
+            if c.is_none() {
+                break;
+            }
+            pos = next_pos;
+            std::mem::swap(&mut current, &mut next);
+        }
+        matched
+    }
+
+    fn char_eq(&self, expected: char, c: char) -> bool {
+        expected == c || self.ignore_case && lower(expected) == lower(c)
+    }
+}
+
+struct Thread {
+    pc: usize,
+    slots: Vec<Option<usize>>,
+}
+
+struct Vm<'r, 't> {
+    regex: &'r Regex,
+    text: &'t str,
+    // 记录每条指令在哪一步被加入过，同一步内重复加入的线程没有意义
+    visited: Vec<usize>,
+    // add_thread 待展开的 (指令, 捕获位置)，复用同一块内存
+    stack: Vec<(usize, Vec<Option<usize>>)>,
+}
+
+impl Vm<'_, '_> {
+    // 沿着 Jmp、Split、Save、断言这些不消耗字符的指令展开，把停在消耗字符指令上的线程加入列表。
+    // 用显式的栈代替递归，(a?){1000} 这样很长的 Split 链也不会耗尽调用栈；
+    // Split 先压入优先级低的分支，保证展开顺序和递归时一样
+    fn add_thread(
+        &mut self,
+        list: &mut Vec<Thread>,
+        pc: usize,
+        pos: usize,
+        slots: Vec<Option<usize>>,
+        step: usize,
+    ) {
+        let mut stack = std::mem::take(&mut self.stack);
+        stack.push((pc, slots));
+        while let Some((pc, mut slots)) = stack.pop() {
+            if self.visited[pc] == step {
+                continue;
+            }
+            self.visited[pc] = step;
+            match self.regex.prog[pc] {
+                Inst::Jmp(x) => stack.push((x, slots)),
+                Inst::Split(x, y) => {
+                    stack.push((y, slots.clone()));
+                    stack.push((x, slots));
+                }
+                Inst::Save(n) => {
+                    slots[n] = Some(pos);
+                    stack.push((pc + 1, slots));
+                }
+                Inst::Assert(a) => {
+                    if self.holds(a, pos) {
+                        stack.push((pc + 1, slots));
+                    }
+                }
+                _ => list.push(Thread { pc, slots }),
+            }
+        }
+        self.stack = stack;
+    }
+
+    fn holds(&self, assertion: Assertion, pos: usize) -> bool {
+        let before = self.text[..pos].chars().next_back();
+        let after = self.text[pos..].chars().next();
+        let boundary = before.is_some_and(is_word_char) != after.is_some_and(is_word_char);
+        match assertion {
+            Assertion::Start => pos == 0,
+            Assertion::End => pos == self.text.len(),
+            Assertion::WordBoundary => boundary,
+            Assertion::NotWordBoundary => !boundary,
+        }
+    }
+}
+
+pub fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn lower(c: char) -> char {
+    let mut it = c.to_lowercase();
+    match (it.next(), it.next()) {
+        (Some(l), None) => l,
+        _ => c,
+    }
+}
+
+fn upper(c: char) -> char {
+    let mut it = c.to_uppercase();
+    match (it.next(), it.next()) {
+        (Some(u), None) => u,
+        _ => c,
+    }
+}
+
+#[cfg(test)]
+mod regex_tests {
+    use super::*;
+
+    fn find<'t>(pattern: &str, text: &'t str) -> Option<&'t str> {
+        Regex::new(pattern).unwrap().find(text).map(|r| &text[r])
+    }
+
+    #[test]
+    fn test_basic_syntax() {
+        assert_eq!(find("abc", "xxabcxx"), Some("abc"));
+        assert_eq!(find("a.c", "abc"), Some("abc"));
+        assert_eq!(find("a.c", "a\nc"), None);
+        assert_eq!(find("^ab", "cab"), None);
+        assert_eq!(find("ab$", "cab"), Some("ab"));
+        assert_eq!(find("colou?r", "color"), Some("color"));
+        assert_eq!(find("cat|dog", "hotdog"), Some("dog"));
+        assert_eq!(find("a\\.b", "axb a.b"), Some("a.b"));
+    }
+
+    #[test]
+    fn test_classes_and_escapes() {
+        assert_eq!(find("[0-9]+", "abc 123 def"), Some("123"));
+        assert_eq!(find("[^a-z ]+", "abc XY1 def"), Some("XY1"));
+        assert_eq!(find("\\d{3}-\\d{4}", "call 555-1234 now"), Some("555-1234"));
+        assert_eq!(find("\\s\\S+", "hello world"), Some(" world"));
+        assert_eq!(find("[\\w.]+@\\w+", "mail a.b@c now"), Some("a.b@c"));
+        assert_eq!(find("[a-]+", "x-a-y"), Some("-a-"));
+        assert_eq!(
+            find("fn \\w+\\(", "pub fn search(query"),
+            Some("fn search(")
+        );
+    }
+
+    #[test]
+    fn test_quantifiers() {
+        assert_eq!(find("a{2,3}", "aaaa"), Some("aaa"));
+        assert_eq!(find("a{2}", "a aa"), Some("aa"));
+        assert_eq!(find("a{2,}", "aaaaa"), Some("aaaaa"));
+        assert_eq!(find("<.+>", "<a><b>"), Some("<a><b>"));
+        assert_eq!(find("<.+?>", "<a><b>"), Some("<a>"));
+        assert_eq!(find("x*", "aaa"), Some(""));
+        // { 不构成量词时按普通字符处理
+        assert_eq!(find("a{x", "a{x"), Some("a{x"));
+        // 嵌套的星号不会让引擎死循环
+        assert_eq!(find("(a*)*b", "aaab"), Some("aaab"));
+        assert_eq!(find("(a|aa)*c", &"a".repeat(30)), None);
+        // 很长的 Split 链逐个展开，不会耗尽调用栈
+        assert_eq!(find("(?:(?:a?){1000}){30}b", "aaab"), Some("aaab"));
+    }
+
+    #[test]
+    fn test_word_boundary() {
+        assert_eq!(find("\\bcat\\b", "concat cat"), Some("cat"));
+        assert_eq!(
+            Regex::new("\\bcat\\b").unwrap().find("concat cat"),
+            Some(7..10)
+        );
+        assert_eq!(find("\\Bcat", "cat concat"), Some("cat"));
+        assert_eq!(find("\\bcafé\\b", "un café."), Some("café"));
+    }
+
+    #[test]
+    fn test_captures() {
+        let re = Regex::new("(?P<key>\\w+)=(\\d+)").unwrap();
+        let caps = re.captures("a: retries=3").unwrap();
+        assert_eq!(caps.name("key"), Some("retries"));
+        assert_eq!(caps.get(2), Some(11..12));
+        assert_eq!(caps.len(), 3);
+        let caps = Regex::new("(a)|(b)").unwrap().captures("b").unwrap();
+        assert_eq!(caps.get(1), None);
+        assert_eq!(caps.get(2), Some(0..1));
+        assert_eq!(
+            Regex::new("(?:ab)+(?<tail>c)").unwrap().group_names(),
+            &[None, Some("tail".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_find_iter_and_case() {
+        let re = Regex::new("\\d+").unwrap();
+        let found: Vec<_> = re.find_iter("a1 b22 c333").collect();
+        assert_eq!(found, vec![1..2, 4..6, 8..11]);
+        let empty: Vec<_> = Regex::new("x*").unwrap().find_iter("ab").collect();
+        assert_eq!(empty, vec![0..0, 1..1, 2..2]);
+
+        let re = Regex::new_case_insensitive("rust[a-z]*").unwrap();
+        assert_eq!(re.find("I love RUSTACEANS"), Some(7..17));
+        assert!(Regex::new_case_insensitive("É").unwrap().is_match("café"));
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = |p: &str| Regex::new(p).unwrap_err();
+        assert_eq!(err("(ab").message, "unclosed group");
+        assert_eq!(err("ab)").message, "unmatched closing parenthesis");
+        assert_eq!(err("*a").message, "nothing to repeat");
+        assert_eq!(err("[a-").message, "unclosed character class");
+        assert_eq!(err("[z-a]").message, "invalid class range");
+        assert_eq!(err("a{5,2}").message, "invalid repetition range");
+        assert_eq!(err("a{1001}").message, "repetition count is too large");
+        // 每个量词都不超过上限，嵌套之后展开的指令太多
+        assert_eq!(
+            err("((a{1000}){1000}){1000}").message,
+            "regex is too large after expanding repetitions"
+        );
+        // 嵌套太深时报错而不是栈溢出
+        let nested = format!("{}a{}", "(".repeat(20000), ")".repeat(20000));
+        assert_eq!(err(&nested).message, "groups are nested too deeply");
+        assert_eq!(err(&nested).position, 251);
+        let stacked = format!("a{}", "?".repeat(50000));
+        assert_eq!(err(&stacked).message, "repetitions are nested too deeply");
+        // 分组和量词交替嵌套时两者一起计算
+        let mixed = format!("{}a{}", "(".repeat(200), ")*".repeat(200));
+        assert_eq!(err(&mixed).message, "repetitions are nested too deeply");
+        let deepest = format!("{}a{}", "(?:".repeat(250), ")".repeat(250));
+        assert!(Regex::new(&deepest).unwrap().is_match("a"));
+        assert_eq!(err("\\q").position, 1);
+        assert_eq!(
+            err("(?x)").to_string(),
+            "invalid regex \"(?x)\" at position 2: unknown group flag"
+        );
+    }
+}
@@ -0,0 +1,146 @@
+// 替换模式：把每一行中匹配的部分换成给定的字符串，类似 sed 's/query/replacement/g'
+// 逐行处理并原样保留每一行的换行符（\n、\r\n 或者最后一行没有换行符），匹配不会跨越换行符
+use crate::Pattern;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+// 替换文本按字面量处理，不支持 $1 这样的分组引用
+pub fn replace_line(pattern: &Pattern, line: &str, replacement: &str) -> (String, usize) {
+    let spans = pattern.find_spans(line);
+    let mut out = String::with_capacity(line.len());
+    let mut last = 0;
+    for span in &spans {
+        out.push_str(&line[last..span.start]);
+        out.push_str(replacement);
+        last = span.end;
+    }
+    out.push_str(&line[last..]);
+    (out, spans.len())
+}
+
+// 流式替换，返回替换的次数
+pub fn replace_reader<R: BufRead, W: Write + ?Sized>(
+    pattern: &Pattern,
+    mut reader: R,
+    replacement: &str,
+    out: &mut W,
+) -> io::Result<usize> {
+    let mut total = 0;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(total);
+        }
+        // read_line 读到的内容最多只在末尾带一个换行符
+        let ending = if line.ends_with("\r\n") {
+            2
+        } else {
+            usize::from(line.ends_with('\n'))
+        };
+        let (body, ending) = line.split_at(line.len() - ending);
+        let (replaced, count) = replace_line(pattern, body, replacement);
+        out.write_all(replaced.as_bytes())?;
+        out.write_all(ending.as_bytes())?;
+        total += count;
+    }
+}
+
+pub fn replace(pattern: &Pattern, contents: &str, replacement: &str) -> (String, usize) {
+    let mut out = Vec::with_capacity(contents.len());
+    // 在内存中读写不会出现 IO 错误
+    let count = replace_reader(pattern, contents.as_bytes(), replacement, &mut out).unwrap();
+    (String::from_utf8(out).unwrap(), count)
+}
+
+// 原地修改文件：先写到同一目录下的临时文件，再用 rename 替换原文件，
+// 中途出错时原文件保持不变。没有任何替换时不会改动文件
+pub fn replace_in_place(pattern: &Pattern, path: &str, replacement: &str) -> io::Result<usize> {
+    let path = Path::new(path);
+    let file_name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("");
+    let tmp = path.with_file_name(format!(".{file_name}.replace-tmp"));
+
+    let result = (|| {
+        let reader = BufReader::new(File::open(path)?);
+        let mut writer = BufWriter::new(File::create(&tmp)?);
+        let count = replace_reader(pattern, reader, replacement, &mut writer)?;
+        writer.flush()?;
+        Ok(count)
+    })();
+    match result {
+        Ok(count) if count > 0 => {
+            // 保留原文件的权限
+            fs::set_permissions(&tmp, fs::metadata(path)?.permissions())?;
+            fs::rename(&tmp, path)?;
+            Ok(count)
+        }
+        _ => {
+            let _ = fs::remove_file(&tmp);
+            result
+        }
+    }
+}
+
+#[cfg(test)]
+mod replace_tests {
+    use super::*;
+
+    fn literal(query: &str) -> Pattern {
+        Pattern::new(query, false, false).unwrap()
+    }
+
+    #[test]
+    fn test_replace() {
+        let (out, count) = replace(&literal("rust"), "rust\ntrust me\nno\n", "go");
+        assert_eq!(out, "go\ntgo me\nno\n");
+        assert_eq!(count, 2);
+        // 部分匹配：重叠的候选只替换最左边的那个
+        assert_eq!(replace(&literal("aa"), "aaa", "b"), ("ba".to_string(), 1));
+        // 整词模式下 trust 中的 rust 不会被替换
+        let word = literal("rust").with_whole_word(true);
+        assert_eq!(replace(&word, "rust trust", "go").0, "go trust");
+        let regex = Pattern::new(r"\d+", true, false).unwrap();
+        assert_eq!(replace(&regex, "a1b22c", "#").0, "a#b#c");
+        let ignore_case = Pattern::new("RUST", false, true).unwrap();
+        assert_eq!(replace(&ignore_case, "Rust rUsT", "Go").0, "Go Go");
+    }
+
+    #[test]
+    fn test_line_endings() {
+        let pattern = literal("a");
+        assert_eq!(replace(&pattern, "a\r\na\na", "b").0, "b\r\nb\nb");
+        assert_eq!(replace(&pattern, "a\n\r\n", "b").0, "b\n\r\n");
+        // 单独的 \r 不是换行符，属于行的内容
+        assert_eq!(replace(&pattern, "a\ra\n", "b").0, "b\rb\n");
+        // 换行符本身不参与匹配
+        let newline = Pattern::new(r"\s", true, false).unwrap();
+        assert_eq!(replace(&newline, "a b\r\n", "_").0, "a_b\r\n");
+        assert_eq!(replace(&pattern, "", "b"), (String::new(), 0));
+    }
+
+    #[test]
+    fn test_in_place() {
+        let path = std::env::temp_dir().join("minigrep-replace-test.txt");
+        let path = path.to_str().unwrap();
+        fs::write(path, "hello world\r\nbye world\n").unwrap();
+        assert_eq!(
+            replace_in_place(&literal("world"), path, "rust").unwrap(),
+            2
+        );
+        assert_eq!(
+            fs::read_to_string(path).unwrap(),
+            "hello rust\r\nbye rust\n"
+        );
+        assert_eq!(replace_in_place(&literal("nope"), path, "x").unwrap(), 0);
+        assert_eq!(
+            fs::read_to_string(path).unwrap(),
+            "hello rust\r\nbye rust\n"
+        );
+        fs::remove_file(path).unwrap();
+        assert!(replace_in_place(&literal("a"), path, "b").is_err());
+    }
+}
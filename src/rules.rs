@@ -0,0 +1,566 @@
+// 规则包：在 TOML 文件中定义一组带名字、严重程度和说明的查询规则，用 --rules FILE 或者
+// minigrep rules run FILE 扫描，搜索就成了一个简单的静态扫描工具。内置的预设（--preset）也是规则包。
+//
+//     name = "my-rules"
+//     version = 2
+//
+//     [[rule]]
+//     id = "todo"
+//     pattern = '\bTODO\b'
+//     severity = "low"
+//     description = "Unfinished work"
+//
+// 只支持 TOML 的一个子集：顶层的键值对和 [[rule]] 表，值可以是单行的字符串（"..." 支持转义，
+// '...' 和 '''...''' 原样保留，适合写正则表达式）、整数、小数和 true / false。
+// min_entropy 的含义见 preset 模块
+use crate::git::BlameLine;
+use crate::output::OutputFormatter;
+use crate::preset::RuleMatcher;
+use crate::regex::Regex;
+use crate::{HunkLine, SearchMatch};
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Low,
+    #[default]
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    // 从低到高
+    pub const ALL: [Severity; 5] = [
+        Severity::Info,
+        Severity::Low,
+        Severity::Medium,
+        Severity::High,
+        Severity::Critical,
+    ];
+}
+
+impl FromStr for Severity {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Severity, &'static str> {
+        match s {
+            "info" => Ok(Severity::Info),
+            "low" => Ok(Severity::Low),
+            "medium" => Ok(Severity::Medium),
+            "high" => Ok(Severity::High),
+            "critical" => Ok(Severity::Critical),
+            _ => Err("severity must be info, low, medium, high or critical"),
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Severity::Info => "info",
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        };
+        // 用 pad 才能支持 {:<8} 这样的对齐
+        f.pad(name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    pub id: String,
+    pub pattern: String,
+    pub severity: Severity,
+    pub description: String,
+    pub min_entropy: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RulePack {
+    pub name: String,
+    pub version: u32,
+    pub description: String,
+    pub rules: Vec<Rule>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RulePackError {
+    // 从 1 开始的行号
+    pub line: usize,
+    pub message: String,
+}
+
+impl RulePackError {
+    fn new(line: usize, message: impl Into<String>) -> RulePackError {
+        RulePackError {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for RulePackError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for RulePackError {}
+
+impl RulePack {
+    // 有任何问题都不能使用，返回第一个问题
+    pub fn parse(text: &str) -> Result<RulePack, RulePackError> {
+        let (pack, mut problems) = check(text)?;
+        match problems.is_empty() {
+            true => Ok(pack),
+            false => Err(problems.swap_remove(0)),
+        }
+    }
+
+    // 在匹配行前面注明是哪些规则找到的
+    pub fn tag(&self, inner: Box<dyn OutputFormatter>) -> Box<dyn OutputFormatter> {
+        Box::new(TaggingFormatter {
+            inner,
+            matcher: RuleMatcher::new(self),
+            rules: self
+                .rules
+                .iter()
+                .map(|rule| (rule.id.clone(), rule.severity))
+                .collect(),
+        })
+    }
+}
+
+impl fmt::Display for RulePack {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = if self.rules.len() == 1 { "" } else { "s" };
+        write!(
+            f,
+            "{} v{} ({} rule{s})",
+            self.name,
+            self.version,
+            self.rules.len()
+        )
+    }
+}
+
+// rules lint：列出所有问题，而不是只报告第一个。语法错误之后无法继续，只有这一个问题
+pub fn lint(text: &str) -> Vec<RulePackError> {
+    match check(text) {
+        Ok((_, problems)) => problems,
+        Err(err) => vec![err],
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Bool(bool),
+}
+
+// 一个表：顶层或者一个 [[rule]]，line 是表头所在的行
+struct Table {
+    line: usize,
+    entries: Vec<(usize, String, Value)>,
+}
+
+// 语法错误直接返回；内容上的问题（缺少字段、正则表达式写错等）全部收集起来
+fn check(text: &str) -> Result<(RulePack, Vec<RulePackError>), RulePackError> {
+    let mut tables = parse_tables(text)?.into_iter();
+    let mut problems = Vec::new();
+    let top = tables.next().expect("the top-level table always exists");
+    let mut pack = RulePack {
+        name: String::new(),
+        version: 1,
+        description: String::new(),
+        rules: Vec::new(),
+    };
+    let mut invalid_name = false;
+    for (line, key, value) in top.entries {
+        invalid_name |= key == "name";
+        let result = match key.as_str() {
+            "name" => string(&key, value).and_then(|name| {
+                if name.is_empty() {
+                    return Err(String::from("name must not be empty"));
+                }
+                pack.name = name;
+                Ok(())
+            }),
+            "version" => match value {
+                Value::Integer(v) if (0..=u32::MAX as i64).contains(&v) => {
+                    pack.version = v as u32;
+                    Ok(())
+                }
+                _ => Err(String::from("version must be a non-negative integer")),
+            },
+            "description" => string(&key, value).map(|v| pack.description = v),
+            _ => Err(format!("unknown key '{key}'")),
+        };
+        if let Err(message) = result {
+            problems.push(RulePackError::new(line, message));
+        }
+    }
+    if pack.name.is_empty() && !invalid_name {
+        problems.push(RulePackError::new(1, "the pack needs a name"));
+    }
+    for table in tables {
+        let mut rule = Rule {
+            id: String::new(),
+            pattern: String::new(),
+            severity: Severity::default(),
+            description: String::new(),
+            min_entropy: None,
+        };
+        // 有问题的字段，不再重复报告缺少这些字段
+        let mut invalid = Vec::new();
+        for (line, key, value) in table.entries {
+            let result = match key.as_str() {
+                "id" => string(&key, value).and_then(|id| {
+                    if id.is_empty()
+                        || !id
+                            .chars()
+                            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+                    {
+                        return Err(String::from(
+                            "id must only contain letters, digits, '-' and '_'",
+                        ));
+                    }
+                    if pack.rules.iter().any(|other| other.id == id) {
+                        return Err(format!("duplicate rule id '{id}'"));
+                    }
+                    rule.id = id;
+                    Ok(())
+                }),
+                "pattern" => string(&key, value).and_then(|pattern| {
+                    let re = Regex::new(&pattern).map_err(|err| err.to_string())?;
+                    // 能匹配空字符串的规则每一行都会报告
+                    if re.is_match("") {
+                        return Err(String::from("pattern matches the empty string"));
+                    }
+                    rule.pattern = pattern;
+                    Ok(())
+                }),
+                "severity" => string(&key, value).and_then(|severity| {
+                    rule.severity = severity.parse()?;
+                    Ok(())
+                }),
+                "description" => string(&key, value).map(|v| rule.description = v),
+                "min_entropy" => match value {
+                    Value::Float(v) if v >= 0.0 => {
+                        rule.min_entropy = Some(v);
+                        Ok(())
+                    }
+                    Value::Integer(v) if v >= 0 => {
+                        rule.min_entropy = Some(v as f64);
+                        Ok(())
+                    }
+                    _ => Err(String::from("min_entropy must be a non-negative number")),
+                },
+                _ => Err(format!("unknown key '{key}'")),
+            };
+            if let Err(message) = result {
+                problems.push(RulePackError::new(line, message));
+                invalid.push(key);
+            }
+        }
+        let missing = |key: &str| !invalid.iter().any(|k| k == key);
+        if rule.id.is_empty() && missing("id") {
+            problems.push(RulePackError::new(table.line, "the rule needs an id"));
+        }
+        if rule.pattern.is_empty() && missing("pattern") {
+            problems.push(RulePackError::new(table.line, "the rule needs a pattern"));
+        }
+        pack.rules.push(rule);
+    }
+    if pack.rules.is_empty() {
+        problems.push(RulePackError::new(1, "the pack has no [[rule]] tables"));
+    }
+    problems.sort_by_key(|p| p.line);
+    Ok((pack, problems))
+}
+
+fn string(key: &str, value: Value) -> Result<String, String> {
+    match value {
+        Value::String(s) => Ok(s),
+        _ => Err(format!("{key} must be a string")),
+    }
+}
+
+fn parse_tables(text: &str) -> Result<Vec<Table>, RulePackError> {
+    let mut tables = vec![Table {
+        line: 1,
+        entries: Vec::new(),
+    }];
+    for (index, line) in text.lines().enumerate() {
+        let number = index + 1;
+        let error = |message: &str| RulePackError::new(number, message);
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if trimmed.starts_with('[') {
+            let header = strip_comment(trimmed).trim_end();
+            if header != "[[rule]]" {
+                return Err(error("only [[rule]] tables are supported"));
+            }
+            tables.push(Table {
+                line: number,
+                entries: Vec::new(),
+            });
+            continue;
+        }
+        let (key, value) = trimmed
+            .split_once('=')
+            .ok_or_else(|| error("expected KEY = VALUE"))?;
+        let key = key.trim();
+        if key.is_empty()
+            || !key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(error("invalid key"));
+        }
+        let (value, rest) = parse_value(value.trim_start()).map_err(error)?;
+        if !strip_comment(rest).trim().is_empty() {
+            return Err(error("unexpected text after the value"));
+        }
+        let table = tables.last_mut().unwrap();
+        if table.entries.iter().any(|(_, k, _)| k == key) {
+            return Err(error("duplicate key"));
+        }
+        table.entries.push((number, key.to_string(), value));
+    }
+    Ok(tables)
+}
+
+// 值后面只能是注释
+fn strip_comment(s: &str) -> &str {
+    match s.trim_start().strip_prefix('#') {
+        Some(_) => "",
+        None => s,
+    }
+}
+
+// 返回解析出的值和剩下的文本
+fn parse_value(s: &str) -> Result<(Value, &str), &'static str> {
+    if let Some(rest) = s.strip_prefix("'''") {
+        let end = rest.find("'''").ok_or("unterminated string")?;
+        return Ok((Value::String(rest[..end].to_string()), &rest[end + 3..]));
+    }
+    if let Some(rest) = s.strip_prefix('\'') {
+        let end = rest.find('\'').ok_or("unterminated string")?;
+        return Ok((Value::String(rest[..end].to_string()), &rest[end + 1..]));
+    }
+    if let Some(rest) = s.strip_prefix('"') {
+        let mut value = String::new();
+        let mut chars = rest.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => return Ok((Value::String(value), &rest[i + 1..])),
+                '\\' => match chars.next().map(|(_, c)| c) {
+                    Some('"') => value.push('"'),
+                    Some('\\') => value.push('\\'),
+                    Some('n') => value.push('\n'),
+                    Some('t') => value.push('\t'),
+                    Some('r') => value.push('\r'),
+                    Some('u') => {
+                        let hex: String = chars.by_ref().take(4).map(|(_, c)| c).collect();
+                        let c = u32::from_str_radix(&hex, 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .ok_or("invalid \\u escape")?;
+                        value.push(c);
+                    }
+                    _ => return Err("invalid escape (use '...' for regular expressions)"),
+                },
+                c => value.push(c),
+            }
+        }
+        return Err("unterminated string");
+    }
+    let end = s
+        .find(|c: char| c.is_whitespace() || c == '#')
+        .unwrap_or(s.len());
+    let (word, rest) = s.split_at(end);
+    let value = match word {
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        _ if word.contains(['.', 'e', 'E']) => {
+            Value::Float(word.parse().map_err(|_| "invalid value")?)
+        }
+        _ => Value::Integer(word.replace('_', "").parse().map_err(|_| "invalid value")?),
+    };
+    Ok((value, rest))
+}
+
+// 匹配行交给 rule_line，注明找到它的规则和其中最高的严重程度；其他输出原样交给内层
+struct TaggingFormatter {
+    inner: Box<dyn OutputFormatter>,
+    matcher: RuleMatcher,
+    rules: Vec<(String, Severity)>,
+}
+
+impl OutputFormatter for TaggingFormatter {
+    fn line(&self, file: &str, line: &HunkLine) -> Option<String> {
+        let HunkLine::Match(m) = line else {
+            return self.inner.line(file, line);
+        };
+        let found = self.matcher.matching_rules(&m.line);
+        // -v 时匹配行恰恰是没有规则匹配的行
+        let Some(severity) = found.iter().map(|&i| self.rules[i].1).max() else {
+            return self.inner.line(file, line);
+        };
+        let ids: Vec<&str> = found.iter().map(|&i| self.rules[i].0.as_str()).collect();
+        self.inner.rule_line(&ids, severity, file, m)
+    }
+
+    fn blamed_line(&self, file: &str, m: &SearchMatch, blame: &BlameLine) -> Option<String> {
+        self.inner.blamed_line(file, m, blame)
+    }
+
+    fn separator(&self) -> Option<String> {
+        self.inner.separator()
+    }
+
+    fn count(&self, file: &str, count: usize) -> String {
+        self.inner.count(file, count)
+    }
+
+    fn binary_match(&self, file: &str) -> String {
+        self.inner.binary_match(file)
+    }
+
+    fn file(&self, file: &str) -> String {
+        self.inner.file(file)
+    }
+
+    fn query_line(&self, query: &str, file: &str, m: &SearchMatch) -> Option<String> {
+        self.inner.query_line(query, file, m)
+    }
+
+    fn scored_line(&self, file: &str, m: &SearchMatch, score: f64) -> Option<String> {
+        self.inner.scored_line(file, m, score)
+    }
+
+    fn rule_line(
+        &self,
+        rules: &[&str],
+        severity: Severity,
+        file: &str,
+        m: &SearchMatch,
+    ) -> Option<String> {
+        self.inner.rule_line(rules, severity, file, m)
+    }
+}
+
+#[cfg(test)]
+mod rules_tests {
+    use super::*;
+    use crate::output::OutputFormat;
+
+    const PACK: &str = r#"
+# 注释和空行
+name = "team"
+version = 3
+
+[[rule]]
+id = "todo"
+pattern = 'TODO\b'   # 行尾注释
+severity = "low"
+description = "Unfinished \"work\""
+
+[[rule]]
+id = "fixme"
+pattern = '''FIXME|XXX'''
+severity = "high"
+"#;
+
+    #[test]
+    fn test_parse() {
+        let pack = RulePack::parse(PACK).unwrap();
+        assert_eq!(pack.name, "team");
+        assert_eq!(pack.version, 3);
+        assert_eq!(pack.to_string(), "team v3 (2 rules)");
+        assert_eq!(pack.rules[0].pattern, r"TODO\b");
+        assert_eq!(pack.rules[0].description, "Unfinished \"work\"");
+        assert_eq!(pack.rules[1].severity, Severity::High);
+        assert_eq!(pack.rules[1].min_entropy, None);
+        assert!(Severity::Critical > Severity::High);
+    }
+
+    #[test]
+    fn test_lint() {
+        let text = "\
+name = \"bad\"
+colour = 1
+
+[[rule]]
+id = \"a\"
+pattern = '('
+
+[[rule]]
+id = \"a\"
+pattern = 'x*'
+severity = \"urgent\"
+
+[[rule]]
+description = \"nothing else\"
+";
+        let problems: Vec<String> = lint(text).iter().map(ToString::to_string).collect();
+        assert_eq!(
+            problems,
+            [
+                "line 2: unknown key 'colour'",
+                "line 6: invalid regex \"(\" at position 1: unclosed group",
+                "line 9: duplicate rule id 'a'",
+                "line 10: pattern matches the empty string",
+                "line 11: severity must be info, low, medium, high or critical",
+                "line 13: the rule needs an id",
+                "line 13: the rule needs a pattern",
+            ]
+        );
+        // 语法错误只报告一个
+        assert_eq!(
+            lint("name = \"x\"\n[rules]\n")[0].to_string(),
+            "line 2: only [[rule]] tables are supported"
+        );
+        assert_eq!(
+            RulePack::parse("name = 'x'\n").unwrap_err().to_string(),
+            "line 1: the pack has no [[rule]] tables"
+        );
+        assert!(RulePack::parse("name = \"unterminated\n").is_err());
+        assert!(RulePack::parse("name = \"x\" trailing\n").is_err());
+    }
+
+    #[test]
+    fn test_tag() {
+        let pack = RulePack::parse(PACK).unwrap();
+        let formatter = pack.tag(OutputFormat::Text.formatter(true, false, false));
+        let m = SearchMatch {
+            line_number: 4,
+            offsets: None,
+            line: "// TODO: FIXME".into(),
+            spans: vec![3..7, 9..14],
+            severity: None,
+            scope: None,
+        };
+        assert_eq!(
+            formatter.line("a.rs", &HunkLine::Match(m)).unwrap(),
+            "[high todo,fixme] a.rs:4:// TODO: FIXME"
+        );
+        let context = HunkLine::Context {
+            line_number: 5,
+            offset: None,
+            line: "TODO".into(),
+        };
+        assert_eq!(formatter.line("a.rs", &context).unwrap(), "a.rs-5-TODO");
+    }
+}
@@ -0,0 +1,120 @@
+// 结果抽样：匹配结果很多时只输出一部分，快速了解大致情况
+// 按比例抽样时每个结果独立地以概率 p 保留，可以边搜索边输出；
+// 按数量抽样使用蓄水池算法（reservoir sampling），只扫描一遍就能从未知总数的结果中均匀地选出 n 个
+use crate::math::gen::Rng;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sample {
+    // 保留的比例，范围是 (0, 1]
+    Fraction(f64),
+    // 最多保留的结果数
+    Count(usize),
+}
+
+impl Sample {
+    pub fn fraction(value: &str) -> Result<Sample, &'static str> {
+        match f64::from_str(value) {
+            Ok(p) if p > 0.0 && p <= 1.0 => Ok(Sample::Fraction(p)),
+            _ => Err("Sample must be a fraction between 0 and 1"),
+        }
+    }
+
+    pub fn count(value: &str) -> Result<Sample, &'static str> {
+        match usize::from_str(value) {
+            Ok(n) if n > 0 => Ok(Sample::Count(n)),
+            _ => Err("Sample size must be a positive number"),
+        }
+    }
+}
+
+pub struct Sampler<T> {
+    sample: Sample,
+    rng: Rng,
+    seen: usize,
+    // 蓄水池中的结果，同时记下它是第几个结果，最后按原来的顺序输出
+    kept: Vec<(usize, T)>,
+}
+
+impl<T> Sampler<T> {
+    pub fn new(sample: Sample, seed: u64) -> Sampler<T> {
+        Sampler {
+            sample,
+            rng: Rng::seed(seed),
+            seen: 0,
+            kept: Vec::new(),
+        }
+    }
+
+    // 按比例抽样时，被选中的结果直接返回给调用方输出；按数量抽样时结果先放进蓄水池，总是返回 None
+    pub fn offer(&mut self, item: T) -> Option<T> {
+        let index = self.seen;
+        self.seen += 1;
+        match self.sample {
+            Sample::Fraction(p) => (self.rng.unit() < p).then_some(item),
+            Sample::Count(n) if self.kept.len() < n => {
+                self.kept.push((index, item));
+                None
+            }
+            Sample::Count(n) => {
+                // 第 k 个结果（从 1 开始）以 n / k 的概率替换蓄水池中随机的一个
+                let j = self.rng.int(0..self.seen as i64) as usize;
+                if j < n {
+                    self.kept[j] = (index, item);
+                }
+                None
+            }
+        }
+    }
+
+    // 蓄水池中剩下的结果，按出现的先后排序
+    pub fn finish(mut self) -> Vec<T> {
+        self.kept.sort_by_key(|(index, _)| *index);
+        self.kept.into_iter().map(|(_, item)| item).collect()
+    }
+}
+
+#[cfg(test)]
+mod sample_tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Sample::fraction("0.01"), Ok(Sample::Fraction(0.01)));
+        assert!(Sample::fraction("0").is_err());
+        assert!(Sample::fraction("1.5").is_err());
+        assert_eq!(Sample::count("100"), Ok(Sample::Count(100)));
+        assert!(Sample::count("0").is_err());
+    }
+
+    #[test]
+    fn test_reservoir() {
+        let mut sampler = Sampler::new(Sample::Count(10), 7);
+        for i in 0..1000 {
+            assert_eq!(sampler.offer(i), None);
+        }
+        let kept = sampler.finish();
+        assert_eq!(kept.len(), 10);
+        assert!(kept.windows(2).all(|w| w[0] < w[1]));
+        // 结果不足 n 个时全部保留
+        let mut sampler = Sampler::new(Sample::Count(10), 7);
+        (0..3).for_each(|i| _ = sampler.offer(i));
+        assert_eq!(sampler.finish(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_uniform() {
+        // 每个位置被选中的概率都应该接近 n / total = 0.1
+        let mut hits = [0; 20];
+        for seed in 0..2000 {
+            let mut sampler = Sampler::new(Sample::Count(2), seed);
+            (0..20).for_each(|i| _ = sampler.offer(i));
+            sampler.finish().into_iter().for_each(|i| hits[i] += 1);
+        }
+        assert!(hits.iter().all(|&h| (140..260).contains(&h)), "{hits:?}");
+
+        let mut sampler = Sampler::new(Sample::Fraction(0.25), 3);
+        let kept = (0..4000).filter(|&i| sampler.offer(i).is_some()).count();
+        assert!((900..1100).contains(&kept), "{kept}");
+    }
+}
@@ -0,0 +1,165 @@
+// 所在范围：记下最近一个函数定义（Rust 的 fn、Python 的 def）或 Markdown 标题，
+// 输出匹配时一并给出，不用再打开文件去找这一行属于哪个函数。
+// 只看"前面最近的一个"，不分析代码块在哪里结束，所以函数结束之后的行仍然归到这个函数
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScopeSyntax {
+    Rust,
+    Python,
+    Markdown,
+}
+
+impl ScopeSyntax {
+    pub fn from_path(path: &str) -> Option<ScopeSyntax> {
+        let extension = Path::new(path).extension()?.to_str()?;
+        match extension {
+            "rs" => Some(ScopeSyntax::Rust),
+            "py" | "pyi" => Some(ScopeSyntax::Python),
+            "md" | "markdown" => Some(ScopeSyntax::Markdown),
+            _ => None,
+        }
+    }
+}
+
+// fn 前面可以出现的修饰符
+const RUST_MODIFIERS: &[&str] = &[
+    "pub",
+    "pub(crate)",
+    "pub(super)",
+    "pub(self)",
+    "async",
+    "const",
+    "unsafe",
+    "extern",
+    "\"C\"",
+    "default",
+];
+
+pub struct ScopeTracker {
+    syntax: ScopeSyntax,
+    current: Option<String>,
+    // Markdown 代码块中以 # 开头的行不是标题
+    in_fence: bool,
+}
+
+impl ScopeTracker {
+    pub fn new(syntax: ScopeSyntax) -> ScopeTracker {
+        ScopeTracker {
+            syntax,
+            current: None,
+            in_fence: false,
+        }
+    }
+
+    // 按顺序传入每一行，返回这一行所在的范围（这一行本身是定义时就是它自己）
+    pub fn line(&mut self, line: &str) -> Option<&str> {
+        let scope = match self.syntax {
+            ScopeSyntax::Rust => rust_fn(line),
+            ScopeSyntax::Python => python_def(line),
+            ScopeSyntax::Markdown => self.markdown_heading(line),
+        };
+        if scope.is_some() {
+            self.current = scope;
+        }
+        self.current()
+    }
+
+    pub fn current(&self) -> Option<&str> {
+        self.current.as_deref()
+    }
+
+    fn markdown_heading(&mut self, line: &str) -> Option<String> {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            self.in_fence = !self.in_fence;
+            return None;
+        }
+        let level = trimmed.bytes().take_while(|&b| b == b'#').count();
+        if self.in_fence || !(1..=6).contains(&level) {
+            return None;
+        }
+        let title = &trimmed[level..];
+        if !title.is_empty() && !title.starts_with(' ') {
+            return None;
+        }
+        // 结尾可以有可选的 #
+        let title = title.trim().trim_end_matches('#').trim_end();
+        Some(
+            format!("{} {title}", &trimmed[..level])
+                .trim_end()
+                .to_string(),
+        )
+    }
+}
+
+fn rust_fn(line: &str) -> Option<String> {
+    let mut words = line.split_whitespace();
+    loop {
+        match words.next()? {
+            "fn" => break,
+            word if RUST_MODIFIERS.contains(&word) => {}
+            _ => return None,
+        }
+    }
+    let name = identifier(words.next()?)?;
+    Some(format!("fn {name}"))
+}
+
+fn python_def(line: &str) -> Option<String> {
+    let trimmed = line.trim_start();
+    let rest = trimmed.strip_prefix("async ").unwrap_or(trimmed);
+    let name = identifier(rest.strip_prefix("def ")?.trim_start())?;
+    Some(format!("def {name}"))
+}
+
+// 开头的标识符，例如 "new<T>(x: T)" 中的 new
+fn identifier(s: &str) -> Option<&str> {
+    let end = s
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(s.len());
+    (end > 0).then(|| &s[..end])
+}
+
+#[cfg(test)]
+mod scope_tests {
+    use super::*;
+
+    fn scopes(syntax: ScopeSyntax, text: &str) -> Vec<Option<String>> {
+        let mut tracker = ScopeTracker::new(syntax);
+        text.lines()
+            .map(|line| tracker.line(line).map(String::from))
+            .collect()
+    }
+
+    #[test]
+    fn test_rust_and_python() {
+        let rust = "use x;\npub(crate) async fn run<T>(t: T) {\n    let f = 1;\n}\n// fn note\nconst unsafe fn b() {}";
+        let names: Vec<_> = scopes(ScopeSyntax::Rust, rust);
+        assert_eq!(names[0], None);
+        assert_eq!(names[2].as_deref(), Some("fn run"));
+        // 注释中的 fn 不是定义
+        assert_eq!(names[4].as_deref(), Some("fn run"));
+        assert_eq!(names[5].as_deref(), Some("fn b"));
+
+        let python = "import os\nclass A:\n    async def go(self):\n        pass";
+        let names = scopes(ScopeSyntax::Python, python);
+        assert_eq!(names[1], None);
+        assert_eq!(names[3].as_deref(), Some("def go"));
+    }
+
+    #[test]
+    fn test_markdown() {
+        let text = "intro\n# Title #\n## Usage\n```sh\n# not a heading\n```\n#hashtag\nend";
+        let names = scopes(ScopeSyntax::Markdown, text);
+        assert_eq!(names[0], None);
+        assert_eq!(names[1].as_deref(), Some("# Title"));
+        assert_eq!(names[4].as_deref(), Some("## Usage"));
+        assert_eq!(names[7].as_deref(), Some("## Usage"));
+        assert_eq!(
+            ScopeSyntax::from_path("README.md"),
+            Some(ScopeSyntax::Markdown)
+        );
+        assert_eq!(ScopeSyntax::from_path("poem.txt"), None);
+    }
+}
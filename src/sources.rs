@@ -0,0 +1,120 @@
+// 把标准输入拆成多个逻辑来源（--split-on REGEX）：几个程序的输出拼在一起通过管道传进来时，
+// 例如逐个容器执行 docker logs 前先 echo 一行 "==> 容器名 <=="，
+// 匹配 REGEX 的行开始一个新的来源，来源的名字是第一个捕获组（没有捕获组时是匹配的文本）。
+// 分隔行本身不参与搜索，第一个分隔行之前的内容属于标准输入本身
+use crate::regex::Regex;
+use std::io::{self, BufRead};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Source {
+    // None 表示第一个分隔行之前的内容
+    pub name: Option<String>,
+    pub content: Vec<u8>,
+}
+
+// 每次读出一个来源的全部内容，内存占用取决于最大的那个来源，而不是整个输入
+pub struct Sources<R> {
+    reader: R,
+    separator: Regex,
+    // 正在读取的来源的名字
+    name: Option<String>,
+    done: bool,
+}
+
+impl<R: BufRead> Sources<R> {
+    pub fn new(reader: R, separator: Regex) -> Sources<R> {
+        Sources {
+            reader,
+            separator,
+            name: None,
+            done: false,
+        }
+    }
+
+    // 分隔行的来源名字，不是分隔行时返回 None
+    fn source_name(&self, line: &[u8]) -> Option<String> {
+        let line = String::from_utf8_lossy(line);
+        let line = line.trim_end_matches('\n').trim_end_matches('\r');
+        let captures = self.separator.captures(line)?;
+        let span = captures.get(1).or_else(|| captures.get(0))?;
+        Some(line[span].to_string())
+    }
+}
+
+impl<R: BufRead> Iterator for Sources<R> {
+    type Item = io::Result<Source>;
+
+    fn next(&mut self) -> Option<io::Result<Source>> {
+        if self.done {
+            return None;
+        }
+        let mut content = Vec::new();
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            match self.reader.read_until(b'\n', &mut line) {
+                Ok(0) => break,
+                Ok(_) => {}
+                Err(error) => {
+                    self.done = true;
+                    return Some(Err(error));
+                }
+            }
+            if let Some(name) = self.source_name(&line) {
+                let name = self.name.replace(name);
+                // 输入直接以分隔行开头时，前面没有属于标准输入本身的内容，不单独算一个来源
+                if name.is_none() && content.is_empty() {
+                    continue;
+                }
+                return Some(Ok(Source { name, content }));
+            }
+            content.extend_from_slice(&line);
+        }
+        self.done = true;
+        Some(Ok(Source {
+            name: self.name.take(),
+            content,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod sources_tests {
+    use super::*;
+
+    fn split(pattern: &str, input: &str) -> Vec<(Option<String>, String)> {
+        Sources::new(input.as_bytes(), Regex::new(pattern).unwrap())
+            .map(|source| {
+                let source = source.unwrap();
+                (source.name, String::from_utf8(source.content).unwrap())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_split_sources() {
+        let input = "==> web <==\nGET /\nerror: timeout\n==> db <==\n==> web <==\nerror: 500\n";
+        assert_eq!(
+            split("^==> (.*) <==$", input),
+            vec![
+                (Some("web".into()), "GET /\nerror: timeout\n".into()),
+                (Some("db".into()), String::new()),
+                // 同一个来源可以出现多次
+                (Some("web".into()), "error: 500\n".into()),
+            ]
+        );
+        // 没有捕获组时用匹配的文本作为名字；第一个分隔行之前的内容没有名字
+        assert_eq!(
+            split("^#[a-z]+", "boot\n#api ready\nok\n"),
+            vec![
+                (None, "boot\n".into()),
+                (Some("#api".into()), "ok\n".into())
+            ]
+        );
+        assert_eq!(
+            split("^-- (x)?", "a\n-- \nb"),
+            vec![(None, "a\n".into()), (Some("-- ".into()), "b".into())]
+        );
+        assert_eq!(split("^==>", ""), vec![(None, String::new())]);
+    }
+}
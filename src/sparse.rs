@@ -0,0 +1,165 @@
+// 稀疏文件（例如大部分空白的虚拟机镜像）中的空洞不占磁盘空间，但照常读取时会读出大量的 0。
+// 用 lseek 的 SEEK_DATA / SEEK_HOLE 找出有数据的区域，只读取这些区域，每个空洞只读出一个 0：
+// 文件仍然会被当作二进制文件，空洞两边的内容也不会拼在一起形成匹配
+use crate::io_backend::Reader;
+use std::fs::File;
+use std::io;
+
+// 文件有空洞时包装成跳过空洞的读取器，其他文件原样返回
+pub fn skip_holes(file: File) -> io::Result<Reader> {
+    if imp::is_sparse(&file.metadata()?) {
+        return Ok(Box::new(imp::SparseFile::new(file)?));
+    }
+    Ok(Box::new(file))
+}
+
+#[cfg(all(target_os = "linux", target_pointer_width = "64"))]
+mod imp {
+    use std::fs::{File, Metadata};
+    use std::io::{self, Read, Seek, SeekFrom};
+    use std::os::fd::AsRawFd;
+    use std::os::raw::c_int;
+    use std::os::unix::fs::MetadataExt;
+
+    const SEEK_DATA: c_int = 3;
+    const SEEK_HOLE: c_int = 4;
+    // 后面没有数据时 SEEK_DATA 返回的错误
+    const ENXIO: i32 = 6;
+
+    extern "C" {
+        fn lseek(fd: c_int, offset: i64, whence: c_int) -> i64;
+    }
+
+    // 占用的磁盘空间比文件长度小，说明有空洞（也可能是压缩的文件系统，按空洞读取同样正确）
+    pub fn is_sparse(metadata: &Metadata) -> bool {
+        metadata.is_file() && metadata.blocks() * 512 < metadata.len()
+    }
+
+    pub struct SparseFile {
+        file: File,
+        len: u64,
+        // 下一次读取的位置，和 file 的读写位置相同
+        pos: u64,
+        // 当前数据区域的结尾，pos 到这里之间可以直接读取
+        data_end: u64,
+    }
+
+    impl SparseFile {
+        pub fn new(file: File) -> io::Result<SparseFile> {
+            Ok(SparseFile {
+                len: file.metadata()?.len(),
+                file,
+                pos: 0,
+                data_end: 0,
+            })
+        }
+
+        // 从 offset 开始找下一个数据区域（SEEK_DATA）或者空洞（SEEK_HOLE）的开头，
+        // 后面没有数据时返回 None。lseek 会移动读写位置，调用方负责移回来
+        fn seek(&self, offset: u64, whence: c_int) -> io::Result<Option<u64>> {
+            // SAFETY: 只改变这个文件描述符的读写位置
+            match unsafe { lseek(self.file.as_raw_fd(), offset as i64, whence) } {
+                -1 => {
+                    let err = io::Error::last_os_error();
+                    match err.raw_os_error() {
+                        Some(ENXIO) => Ok(None),
+                        _ => Err(err),
+                    }
+                }
+                at => Ok(Some(at as u64)),
+            }
+        }
+    }
+
+    impl Read for SparseFile {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if buf.is_empty() || self.pos >= self.len {
+                return Ok(0);
+            }
+            if self.pos < self.data_end {
+                let want = buf.len().min((self.data_end - self.pos) as usize);
+                let read = self.file.read(&mut buf[..want])?;
+                self.pos += read as u64;
+                return Ok(read);
+            }
+            // 到了一个数据区域的结尾，找下一个数据区域，中间的空洞只读出一个 0
+            let data = self.seek(self.pos, SEEK_DATA)?.unwrap_or(self.len);
+            let in_hole = data > self.pos;
+            self.pos = data;
+            self.data_end = match self.seek(data, SEEK_HOLE)? {
+                Some(hole) if data < self.len => hole,
+                _ => self.len,
+            };
+            self.file.seek(SeekFrom::Start(data))?;
+            if in_hole {
+                buf[0] = 0;
+                return Ok(1);
+            }
+            self.read(buf)
+        }
+    }
+}
+
+// 其他平台上不检测空洞，照常读取
+#[cfg(not(all(target_os = "linux", target_pointer_width = "64")))]
+mod imp {
+    use std::fs::{File, Metadata};
+    use std::io::{self, Read};
+
+    pub fn is_sparse(_metadata: &Metadata) -> bool {
+        false
+    }
+
+    pub struct SparseFile(File);
+
+    impl SparseFile {
+        pub fn new(file: File) -> io::Result<SparseFile> {
+            Ok(SparseFile(file))
+        }
+    }
+
+    impl Read for SparseFile {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.0.read(buf)
+        }
+    }
+}
+
+#[cfg(test)]
+mod sparse_tests {
+    use super::*;
+    use std::env;
+    use std::io::{Read, Seek, SeekFrom, Write};
+
+    #[test]
+    fn test_skip_holes() {
+        let path = env::temp_dir().join("minigrep-sparse.img");
+        let mut file = File::create(&path).unwrap();
+        file.set_len(64 << 20).unwrap();
+        file.write_all(b"boot").unwrap();
+        // 紧挨着空洞的两段内容，跳过空洞后也不能拼成 "ab" + "cd"
+        file.seek(SeekFrom::Start((1 << 20) - 2)).unwrap();
+        file.write_all(b"ab").unwrap();
+        file.seek(SeekFrom::Start(32 << 20)).unwrap();
+        file.write_all(b"cd\nneedle\n").unwrap();
+        drop(file);
+
+        let file = File::open(&path).unwrap();
+        let sparse = imp::is_sparse(&file.metadata().unwrap());
+        let mut contents = Vec::new();
+        skip_holes(file)
+            .unwrap()
+            .read_to_end(&mut contents)
+            .unwrap();
+        let text = String::from_utf8_lossy(&contents);
+        assert!(text.starts_with("boot\0"));
+        assert!(text.contains("cd\nneedle\n"));
+        // 文件系统不支持空洞时照常读取全部内容
+        if sparse {
+            assert!(contents.len() < 1 << 20);
+            assert!(text.contains("ab\0cd\n"));
+        } else {
+            assert_eq!(contents.len(), 64 << 20);
+        }
+    }
+}
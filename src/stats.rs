@@ -0,0 +1,72 @@
+// 搜索统计：扫描了多少文件、多少文件有匹配、一共多少匹配行、用了多长时间。
+// 由 run 在搜索过程中逐个文件累加，通过 RunResult 返回，调用方可以自己决定怎么展示
+use crate::units::Duration;
+use std::fmt;
+use std::time;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Stats {
+    pub files_scanned: usize,
+    pub files_with_matches: usize,
+    pub matching_lines: usize,
+    pub elapsed: time::Duration,
+}
+
+impl Stats {
+    // 记录搜索完的一个文件
+    pub fn add_file(&mut self, matching_lines: usize) {
+        self.files_scanned += 1;
+        self.files_with_matches += usize::from(matching_lines > 0);
+        self.matching_lines += matching_lines;
+    }
+
+    // 与 --progress-json 的进度事件格式一致，用 type 字段区分
+    pub fn json(&self) -> String {
+        format!(
+            "{{\"type\":\"stats\",\"files_scanned\":{},\"files_with_matches\":{},\"matching_lines\":{},\"elapsed_secs\":{:.3}}}",
+            self.files_scanned,
+            self.files_with_matches,
+            self.matching_lines,
+            self.elapsed.as_secs_f64()
+        )
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} matching lines", self.matching_lines)?;
+        writeln!(f, "{} files with matches", self.files_with_matches)?;
+        writeln!(f, "{} files scanned", self.files_scanned)?;
+        write!(f, "{} elapsed", Duration(self.elapsed))
+    }
+}
+
+#[cfg(test)]
+mod stats_tests {
+    use super::*;
+
+    #[test]
+    fn test_stats() {
+        let mut stats = Stats::default();
+        stats.add_file(3);
+        stats.add_file(0);
+        stats.add_file(2);
+        stats.elapsed = time::Duration::from_millis(12);
+        assert_eq!(
+            (
+                stats.files_scanned,
+                stats.files_with_matches,
+                stats.matching_lines
+            ),
+            (3, 2, 5)
+        );
+        assert_eq!(
+            stats.to_string(),
+            "5 matching lines\n2 files with matches\n3 files scanned\n12 ms elapsed"
+        );
+        assert_eq!(
+            stats.json(),
+            r#"{"type":"stats","files_scanned":3,"files_with_matches":2,"matching_lines":5,"elapsed_secs":0.012}"#
+        );
+    }
+}
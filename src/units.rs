@@ -0,0 +1,379 @@
+// 带单位的数值类型（newtype 模式），统一负责格式化输出，例如 1.4 MiB、2.3 s、87%
+// 用元组结构体包装原始数值，既能获得类型检查（字节数不会被误当成毫秒数），又几乎没有运行时开销
+use crate::numbers::NumberFormat;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Bytes(pub u64);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Duration(pub std::time::Duration);
+
+// 保存的是比例（0.87 表示 87%）
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Percentage(pub f64);
+
+impl Percentage {
+    // 分母为 0 时按 0% 处理，避免出现 NaN
+    pub fn of(part: u64, whole: u64) -> Percentage {
+        if whole == 0 {
+            Percentage(0.0)
+        } else {
+            Percentage(part as f64 / whole as f64)
+        }
+    }
+}
+
+const BYTE_UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+
+impl Bytes {
+    // 按给定的数字格式输出，主要是小数点的写法不同，例如德语环境下是 1,4 MiB
+    pub fn localized(&self, format: &NumberFormat) -> String {
+        if self.0 < 1024 {
+            return format!("{} B", self.0);
+        }
+        let mut value = self.0 as f64 / 1024.0;
+        let mut unit = 0;
+        // 按保留一位小数之后的值选单位，1023.96 KiB 显示成 1.0 MiB 而不是 1024.0 KiB
+        while round_one_decimal(value) >= 1024.0 && unit < BYTE_UNITS.len() - 1 {
+            value /= 1024.0;
+            unit += 1;
+        }
+        format!("{} {}", one_decimal(value, format), BYTE_UNITS[unit])
+    }
+}
+
+impl Duration {
+    pub fn localized(&self, format: &NumberFormat) -> String {
+        // 先按每个单位显示的精度四舍五入，再决定用哪个单位：
+        // 59.96 s 进位后是 60.0 s，应当显示成 1m 00s；999.6 ms 同理显示成 1.0 s
+        let nanos = self.0.as_nanos();
+        let rounded = |unit: u128| (nanos + unit / 2) / unit;
+        let micros = rounded(1_000);
+        if micros < 1000 {
+            return format!("{micros} µs");
+        }
+        let millis = rounded(1_000_000);
+        if millis < 1000 {
+            return format!("{millis} ms");
+        }
+        let tenths = rounded(100_000_000);
+        if tenths < 600 {
+            return format!("{} s", one_decimal(tenths as f64 / 10.0, format));
+        }
+        let secs = rounded(1_000_000_000);
+        if secs < 3600 {
+            return format!("{}m {:02}s", secs / 60, secs % 60);
+        }
+        let minutes = rounded(60_000_000_000);
+        format!("{}h {:02}m", minutes / 60, minutes % 60)
+    }
+}
+
+impl Percentage {
+    pub fn localized(&self, format: &NumberFormat) -> String {
+        let percent = self.0 * 100.0;
+        // 大于 0 但不到 1% 的一律显示成 <1%，避免让人误以为完全没有
+        if percent > 0.0 && percent < 1.0 {
+            return "<1%".to_string();
+        }
+        // 10% 以下保留一位小数，按保留之后的值判断：9.99% 是 10%，2.04% 是 2%
+        let rounded = round_one_decimal(percent);
+        if rounded < 10.0 && rounded.fract() != 0.0 {
+            format!("{}%", one_decimal(percent, format))
+        } else {
+            format!("{:.0}%", percent)
+        }
+    }
+}
+
+fn round_one_decimal(value: f64) -> f64 {
+    (value * 10.0).round() / 10.0
+}
+
+fn one_decimal(value: f64, format: &NumberFormat) -> String {
+    let s = format!("{:.1}", value);
+    if format.decimal == '.' {
+        s
+    } else {
+        s.replace('.', &format.decimal.to_string())
+    }
+}
+
+// Display 使用默认格式，因此可以直接写 format!("{}", Bytes(1024))
+impl fmt::Display for Bytes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.localized(&NumberFormat::default()))
+    }
+}
+
+impl fmt::Display for Duration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.localized(&NumberFormat::default()))
+    }
+}
+
+impl fmt::Display for Percentage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.localized(&NumberFormat::default()))
+    }
+}
+
+// 解析命令行中人性化的写法：大小如 10M、1.5GiB，时长如 250ms、2h30m
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseUnitError {
+    pub input: String,
+    pub reason: String,
+}
+
+impl fmt::Display for ParseUnitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid value \"{}\": {}", self.input, self.reason)
+    }
+}
+
+impl Error for ParseUnitError {}
+
+fn parse_error(input: &str, reason: impl Into<String>) -> ParseUnitError {
+    ParseUnitError {
+        input: input.to_string(),
+        reason: reason.into(),
+    }
+}
+
+// 把 "1.5GiB" 拆成数字部分 1.5 和单位部分 GiB
+fn split_number(s: &str) -> (&str, &str) {
+    let end = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    s.split_at(end)
+}
+
+fn parse_number(input: &str, digits: &str) -> Result<f64, ParseUnitError> {
+    if digits.is_empty() {
+        return Err(parse_error(input, "expected a number"));
+    }
+    digits
+        .parse::<f64>()
+        .map_err(|_| parse_error(input, format!("\"{digits}\" is not a number")))
+}
+
+// 不带 i 的 KB/MB 按 1000 进制，K/KiB 这类写法按 1024 进制
+impl FromStr for Bytes {
+    type Err = ParseUnitError;
+
+    fn from_str(s: &str) -> Result<Bytes, ParseUnitError> {
+        let trimmed = s.trim();
+        let (digits, unit) = split_number(trimmed);
+        let value = parse_number(s, digits)?;
+        let multiplier: u64 = match unit.to_ascii_lowercase().as_str() {
+            "" | "b" => 1,
+            "k" | "kib" => 1 << 10,
+            "m" | "mib" => 1 << 20,
+            "g" | "gib" => 1 << 30,
+            "t" | "tib" => 1 << 40,
+            "kb" => 1_000,
+            "mb" => 1_000_000,
+            "gb" => 1_000_000_000,
+            "tb" => 1_000_000_000_000,
+            other => return Err(parse_error(s, format!("unknown size unit \"{other}\""))),
+        };
+        let bytes = value * multiplier as f64;
+        // u64::MAX as f64 向上取整成了 2^64，等于它时同样放不下
+        if bytes >= u64::MAX as f64 {
+            return Err(parse_error(s, "size is too large"));
+        }
+        if bytes.fract() != 0.0 {
+            return Err(parse_error(s, "size must be a whole number of bytes"));
+        }
+        Ok(Bytes(bytes as u64))
+    }
+}
+
+const DURATION_UNITS: &str = "(expected ns, us, ms, s, m, h or d)";
+
+fn unit_seconds(unit: &str) -> Option<f64> {
+    match unit {
+        "ns" => Some(1e-9),
+        "us" | "µs" => Some(1e-6),
+        "ms" => Some(1e-3),
+        "s" => Some(1.0),
+        "m" => Some(60.0),
+        "h" => Some(3600.0),
+        "d" => Some(86400.0),
+        _ => None,
+    }
+}
+
+// 时长可以由多段组成，例如 2h30m、1m30.5s，每一段都必须带单位
+impl FromStr for Duration {
+    type Err = ParseUnitError;
+
+    fn from_str(s: &str) -> Result<Duration, ParseUnitError> {
+        let mut rest = s.trim();
+        if rest.is_empty() {
+            return Err(parse_error(s, "expected a duration such as 250ms or 2h30m"));
+        }
+        let mut total = 0.0;
+        while !rest.is_empty() {
+            let (digits, tail) = split_number(rest);
+            let value = parse_number(s, digits)?;
+            let unit_len = tail
+                .find(|c: char| c.is_ascii_digit() || c == '.')
+                .unwrap_or(tail.len());
+            let (unit, next) = tail.split_at(unit_len);
+            let seconds = match unit {
+                "" => return Err(parse_error(s, format!("missing unit after \"{digits}\""))),
+                unit => unit_seconds(unit).ok_or_else(|| {
+                    parse_error(
+                        s,
+                        format!("unknown duration unit \"{unit}\" {DURATION_UNITS}"),
+                    )
+                })?,
+            };
+            total += value * seconds;
+            rest = next;
+        }
+        std::time::Duration::try_from_secs_f64(total)
+            .map(Duration)
+            .map_err(|_| parse_error(s, "duration is too large"))
+    }
+}
+
+#[cfg(test)]
+mod units_tests {
+    use super::*;
+    use std::time::Duration as StdDuration;
+
+    #[test]
+    fn test_bytes_display() {
+        assert_eq!(Bytes(0).to_string(), "0 B");
+        assert_eq!(Bytes(1023).to_string(), "1023 B");
+        assert_eq!(Bytes(1024).to_string(), "1.0 KiB");
+        assert_eq!(Bytes(1_468_006).to_string(), "1.4 MiB");
+        assert_eq!(Bytes(5 * 1024 * 1024 * 1024).to_string(), "5.0 GiB");
+        // 四舍五入之后进位到下一个单位
+        assert_eq!(Bytes(1_048_575).to_string(), "1.0 MiB");
+        assert_eq!(Bytes(1_048_524).to_string(), "1023.9 KiB");
+    }
+
+    #[test]
+    fn test_duration_display() {
+        let d = |ms| Duration(StdDuration::from_millis(ms));
+        assert_eq!(Duration(StdDuration::from_micros(42)).to_string(), "42 µs");
+        assert_eq!(d(250).to_string(), "250 ms");
+        assert_eq!(d(2_300).to_string(), "2.3 s");
+        assert_eq!(d(125_000).to_string(), "2m 05s");
+        assert_eq!(d(9_000_000).to_string(), "2h 30m");
+    }
+
+    #[test]
+    fn test_duration_display_rounds_before_choosing_unit() {
+        let micros = |n| Duration(StdDuration::from_micros(n)).to_string();
+        // 每个单位的边界上，进位之后要换成下一个单位
+        assert_eq!(
+            Duration(StdDuration::from_nanos(999_400)).to_string(),
+            "999 µs"
+        );
+        assert_eq!(
+            Duration(StdDuration::from_nanos(999_600)).to_string(),
+            "1 ms"
+        );
+        assert_eq!(micros(999_400), "999 ms");
+        assert_eq!(micros(999_600), "1.0 s");
+        assert_eq!(micros(59_940_000), "59.9 s");
+        assert_eq!(micros(59_960_000), "1m 00s");
+        assert_eq!(micros(3_599_400_000), "59m 59s");
+        assert_eq!(micros(3_599_600_000), "1h 00m");
+        // 同一单位内部也是四舍五入而不是截断
+        assert_eq!(micros(1_600), "2 ms");
+        assert_eq!(micros(89_500_000), "1m 30s");
+        assert_eq!(micros(5_429_000_000), "1h 30m");
+    }
+
+    #[test]
+    fn test_percentage_display() {
+        assert_eq!(Percentage::of(87, 100).to_string(), "87%");
+        assert_eq!(Percentage::of(1, 3).to_string(), "33%");
+        assert_eq!(Percentage::of(1, 40).to_string(), "2.5%");
+        assert_eq!(Percentage::of(1, 1000).to_string(), "<1%");
+        assert_eq!(Percentage::of(0, 0).to_string(), "0%");
+        // 1% 以下都是 <1%，10% 以下按一位小数四舍五入后决定写法
+        assert_eq!(Percentage(0.004).to_string(), "<1%");
+        assert_eq!(Percentage(0.006).to_string(), "<1%");
+        assert_eq!(Percentage(0.01).to_string(), "1%");
+        assert_eq!(Percentage(0.0204).to_string(), "2%");
+        assert_eq!(Percentage(0.0994).to_string(), "9.9%");
+        assert_eq!(Percentage(0.0999).to_string(), "10%");
+    }
+
+    #[test]
+    fn test_localized() {
+        let de = NumberFormat {
+            thousands: Some('.'),
+            decimal: ',',
+        };
+        assert_eq!(Bytes(1_468_006).localized(&de), "1,4 MiB");
+        assert_eq!(
+            Duration(StdDuration::from_millis(2_300)).localized(&de),
+            "2,3 s"
+        );
+        assert_eq!(Percentage::of(1, 40).localized(&de), "2,5%");
+    }
+
+    #[test]
+    fn test_parse_bytes() {
+        assert_eq!("512".parse(), Ok(Bytes(512)));
+        assert_eq!("10M".parse(), Ok(Bytes(10 * 1024 * 1024)));
+        assert_eq!("1.5GiB".parse(), Ok(Bytes(1_610_612_736)));
+        assert_eq!("2kb".parse(), Ok(Bytes(2_000)));
+        assert_eq!(
+            "10X".parse::<Bytes>().unwrap_err().to_string(),
+            "invalid value \"10X\": unknown size unit \"x\""
+        );
+        assert_eq!(
+            "M".parse::<Bytes>().unwrap_err().reason,
+            "expected a number"
+        );
+        assert_eq!(
+            "1.5B".parse::<Bytes>().unwrap_err().reason,
+            "size must be a whole number of bytes"
+        );
+        assert_eq!(
+            "18446744073709551616".parse::<Bytes>().unwrap_err().reason,
+            "size is too large"
+        );
+        assert_eq!(
+            "16777216T".parse::<Bytes>().unwrap_err().reason,
+            "size is too large"
+        );
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        let ms = |n| Ok(Duration(StdDuration::from_millis(n)));
+        assert_eq!("250ms".parse(), ms(250));
+        assert_eq!("2h30m".parse(), ms(9_000_000));
+        assert_eq!("1m30.5s".parse(), ms(90_500));
+        assert_eq!("1.5s".parse(), ms(1_500));
+        assert_eq!(
+            "10".parse::<Duration>().unwrap_err().reason,
+            "missing unit after \"10\""
+        );
+        assert_eq!(
+            "3w".parse::<Duration>().unwrap_err().reason,
+            "unknown duration unit \"w\" (expected ns, us, ms, s, m, h or d)"
+        );
+        assert!("".parse::<Duration>().is_err());
+        assert!("1..2s".parse::<Duration>().is_err());
+        assert_eq!(
+            "9999999999999999999999d"
+                .parse::<Duration>()
+                .unwrap_err()
+                .reason,
+            "duration is too large"
+        );
+    }
+}
@@ -0,0 +1,347 @@
+// 目录遍历：命令行上给出的目录会被递归展开成其中的文件，按名称排序，所以每次的输出顺序相同。
+// 默认跳过 .git 目录、隐藏文件和 .gitignore 中列出的路径（只读取遍历范围内的 .gitignore）。
+// 这些规则和 --include / --exclude 只作用于遍历时找到的路径，命令行上直接给出的文件总是会被搜索。
+// 硬链接、绑定挂载或者指向文件的符号链接让同一个文件出现在多个路径上，默认只搜索第一次找到的路径
+use crate::error::AppError;
+use crate::glob::Glob;
+use crate::ignore::IgnoreRules;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone)]
+pub struct Walker {
+    include: Vec<Glob>,
+    exclude: Vec<Glob>,
+    hidden: bool,
+    git_ignore: bool,
+    dedup: bool,
+}
+
+impl Default for Walker {
+    fn default() -> Walker {
+        Walker {
+            include: Vec::new(),
+            exclude: Vec::new(),
+            hidden: false,
+            git_ignore: true,
+            dedup: true,
+        }
+    }
+}
+
+// 已经找到的文件的 (设备号, inode)，用来认出出现在不同路径上的同一个文件
+#[derive(Debug, Default)]
+pub struct FileIds(HashSet<(u64, u64)>);
+
+impl FileIds {
+    // 记下 path 指向的文件，第一次见到这个文件时返回 true。
+    // 读不到文件信息时也返回 true，错误留到真正搜索时再报告
+    #[cfg(unix)]
+    pub fn insert(&mut self, path: &str) -> bool {
+        use std::os::unix::fs::MetadataExt;
+        match fs::metadata(path) {
+            Ok(metadata) => self.0.insert((metadata.dev(), metadata.ino())),
+            Err(_) => true,
+        }
+    }
+
+    // 其他平台上的标准库没有稳定的文件编号，不去重
+    #[cfg(not(unix))]
+    pub fn insert(&mut self, _path: &str) -> bool {
+        true
+    }
+}
+
+// 遍历过程中已经读取的 .gitignore：所在目录（相对于起点）和其中的规则，深层的在后面
+type IgnoreStack = Vec<(String, IgnoreRules)>;
+
+impl Walker {
+    pub fn new() -> Walker {
+        Walker::default()
+    }
+
+    // 是否搜索以 . 开头的文件和目录
+    pub fn hidden(mut self, hidden: bool) -> Walker {
+        self.hidden = hidden;
+        self
+    }
+
+    // 是否遵守 .gitignore
+    pub fn git_ignore(mut self, git_ignore: bool) -> Walker {
+        self.git_ignore = git_ignore;
+        self
+    }
+
+    // 同一个文件出现在多个路径上时是否只保留第一个
+    pub fn dedup(mut self, dedup: bool) -> Walker {
+        self.dedup = dedup;
+        self
+    }
+
+    // 给出 include 时，只搜索至少匹配其中一个的文件
+    pub fn include(mut self, globs: &[Glob]) -> Walker {
+        self.include.extend_from_slice(globs);
+        self
+    }
+
+    // 匹配任意一个 exclude 的文件不搜索，匹配的目录整个跳过（例如 --exclude target）
+    pub fn exclude(mut self, globs: &[Glob]) -> Walker {
+        self.exclude.extend_from_slice(globs);
+        self
+    }
+
+    // relative 是相对于遍历起点的路径，用 / 分隔。只看 --include / --exclude，隐藏文件和 .gitignore 在遍历时处理
+    pub fn is_wanted(&self, relative: &str, is_dir: bool) -> bool {
+        if self.exclude.iter().any(|glob| glob.is_match(relative)) {
+            return false;
+        }
+        is_dir || self.include.is_empty() || self.include.iter().any(|glob| glob.is_match(relative))
+    }
+
+    // 返回 root 下所有要搜索的文件，路径以 root 开头
+    pub fn walk(&self, root: &str) -> Result<Vec<String>, AppError> {
+        self.walk_with(root, &mut FileIds::default())
+    }
+
+    // 和 walk 相同，但跳过 ids 中已经有的文件，用来在多个起点之间去重
+    pub fn walk_with(&self, root: &str, ids: &mut FileIds) -> Result<Vec<String>, AppError> {
+        let mut files = Vec::new();
+        let mut ignores = IgnoreStack::new();
+        let mut found = Found {
+            files: &mut files,
+            ids,
+        };
+        self.walk_dir(root.trim_end_matches('/'), "", &mut ignores, &mut found)?;
+        Ok(files)
+    }
+
+    fn walk_dir(
+        &self,
+        dir: &str,
+        relative: &str,
+        ignores: &mut IgnoreStack,
+        found: &mut Found,
+    ) -> Result<(), AppError> {
+        // 起点读不了时返回错误；更深的目录和其中的条目读不了时只提醒一下，跳过它们接着遍历
+        let mut entries: Vec<_> = fs::read_dir(if dir.is_empty() { "/" } else { dir })
+            .map_err(|source| AppError::io(dir, source))?
+            .filter_map(|entry| entry.map_err(|source| warn(AppError::io(dir, source))).ok())
+            .collect();
+        entries.sort_by_key(|entry| entry.file_name());
+        // 读不到 .gitignore（通常是不存在）时当作没有规则
+        let rules = self
+            .git_ignore
+            .then(|| fs::read_to_string(format!("{dir}/.gitignore")).ok())
+            .flatten()
+            .map(|text| IgnoreRules::parse(&text))
+            .filter(|rules| !rules.is_empty());
+        let pushed = rules.is_some();
+        if let Some(rules) = rules {
+            ignores.push((relative.to_string(), rules));
+        }
+        for entry in entries {
+            // 文件名不是 UTF-8 时无法作为参数传给搜索，直接跳过
+            let Ok(name) = entry.file_name().into_string() else {
+                continue;
+            };
+            if name == ".git" || (!self.hidden && name.starts_with('.')) {
+                continue;
+            }
+            let path = format!("{dir}/{name}");
+            let relative = if relative.is_empty() {
+                name
+            } else {
+                format!("{relative}/{name}")
+            };
+            // 遍历的过程中文件可能被删掉
+            let Ok(file_type) = entry
+                .file_type()
+                .map_err(|source| warn(AppError::io(&path, source)))
+            else {
+                continue;
+            };
+            // 不进入指向目录的符号链接，避免出现循环；指向文件的符号链接照常搜索
+            let is_file =
+                file_type.is_file() || (file_type.is_symlink() && Path::new(&path).is_file());
+            let is_dir = file_type.is_dir();
+            if !(is_dir || is_file)
+                || is_ignored(ignores, &relative, is_dir)
+                || !self.is_wanted(&relative, is_dir)
+            {
+                continue;
+            }
+            if is_dir {
+                if let Err(err) = self.walk_dir(&path, &relative, ignores, found) {
+                    warn(err);
+                }
+            } else if !self.dedup || found.ids.insert(&path) {
+                found.files.push(path);
+            }
+        }
+        if pushed {
+            ignores.pop();
+        }
+        Ok(())
+    }
+}
+
+fn warn(err: AppError) {
+    eprintln!("warning: {err}");
+}
+
+// 遍历时找到的文件和它们的编号
+struct Found<'a> {
+    files: &'a mut Vec<String>,
+    ids: &'a mut FileIds,
+}
+
+// 从最深的 .gitignore 开始找，第一个有规则匹配的决定结果
+fn is_ignored(ignores: &IgnoreStack, relative: &str, is_dir: bool) -> bool {
+    ignores
+        .iter()
+        .rev()
+        .find_map(|(base, rules)| {
+            let path = if base.is_empty() {
+                relative
+            } else {
+                relative.strip_prefix(base.as_str())?.strip_prefix('/')?
+            };
+            rules.matched(path, is_dir)
+        })
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod walk_tests {
+    use super::*;
+    use std::env;
+
+    fn globs(patterns: &[&str]) -> Vec<Glob> {
+        patterns.iter().map(|p| Glob::new(p).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_walk() {
+        let dir = env::temp_dir().join("minigrep-walk-test");
+        let _ = fs::remove_dir_all(&dir);
+        for sub in ["src/bin", "target/debug", ".git", ".cache"] {
+            fs::create_dir_all(dir.join(sub)).unwrap();
+        }
+        for file in [
+            "README.md",
+            "src/lib.rs",
+            "src/bin/main.rs",
+            "src/notes.log",
+            "target/debug/out.rs",
+            ".git/config",
+            ".cache/data",
+            ".env",
+        ] {
+            fs::write(dir.join(file), "x").unwrap();
+        }
+        let root = dir.to_str().unwrap().to_string() + "/";
+        let found = |walker: Walker| -> Vec<String> {
+            walker
+                .walk(&root)
+                .unwrap()
+                .into_iter()
+                .map(|path| path[root.len()..].to_string())
+                .collect()
+        };
+
+        assert_eq!(
+            found(Walker::new()),
+            vec![
+                "README.md",
+                "src/bin/main.rs",
+                "src/lib.rs",
+                "src/notes.log",
+                "target/debug/out.rs"
+            ]
+        );
+        assert_eq!(
+            found(
+                Walker::new()
+                    .include(&globs(&["*.rs"]))
+                    .exclude(&globs(&["target"]))
+            ),
+            vec!["src/bin/main.rs", "src/lib.rs"]
+        );
+        assert_eq!(
+            found(Walker::new().exclude(&globs(&["*.log", "src/bin/**"]))),
+            vec!["README.md", "src/lib.rs", "target/debug/out.rs"]
+        );
+        assert!(Walker::new().walk("/no/such/minigrep/dir").is_err());
+
+        // .gitignore 中的规则，子目录中的 .gitignore 可以重新包含上层忽略的文件
+        fs::write(dir.join(".gitignore"), "target/\n*.log\n").unwrap();
+        fs::write(dir.join("src/.gitignore"), "!notes.log\nbin\n").unwrap();
+        assert_eq!(
+            found(Walker::new()),
+            vec!["README.md", "src/lib.rs", "src/notes.log"]
+        );
+        assert_eq!(
+            found(Walker::new().hidden(true)),
+            vec![
+                ".cache/data",
+                ".env",
+                ".gitignore",
+                "README.md",
+                "src/.gitignore",
+                "src/lib.rs",
+                "src/notes.log"
+            ]
+        );
+        assert_eq!(
+            found(Walker::new().git_ignore(false)),
+            vec![
+                "README.md",
+                "src/bin/main.rs",
+                "src/lib.rs",
+                "src/notes.log",
+                "target/debug/out.rs"
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_dedup() {
+        let dir = env::temp_dir().join("minigrep-walk-dedup");
+        let _ = fs::remove_dir_all(&dir);
+        for sub in ["a", "b"] {
+            fs::create_dir_all(dir.join(sub)).unwrap();
+        }
+        fs::write(dir.join("a/one.txt"), "x").unwrap();
+        fs::write(dir.join("a/two.txt"), "x").unwrap();
+        fs::hard_link(dir.join("a/one.txt"), dir.join("b/link.txt")).unwrap();
+        std::os::unix::fs::symlink(dir.join("a/two.txt"), dir.join("b/symlink.txt")).unwrap();
+        let root = dir.to_str().unwrap().to_string();
+        let found = |walker: Walker| -> Vec<String> {
+            walker
+                .walk(&root)
+                .unwrap()
+                .into_iter()
+                .map(|path| path[root.len() + 1..].to_string())
+                .collect()
+        };
+
+        assert_eq!(found(Walker::new()), vec!["a/one.txt", "a/two.txt"]);
+        assert_eq!(
+            found(Walker::new().dedup(false)),
+            vec!["a/one.txt", "a/two.txt", "b/link.txt", "b/symlink.txt"]
+        );
+        // 多个起点之间共用 ids，重叠的目录中的文件只出现一次
+        let mut ids = FileIds::default();
+        let walker = Walker::new();
+        assert_eq!(
+            walker
+                .walk_with(&format!("{root}/b"), &mut ids)
+                .unwrap()
+                .len(),
+            2
+        );
+        assert!(walker.walk_with(&root, &mut ids).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,88 @@
+// 配置文件的优先级：运行编译好的程序，用单独的 HOME 和当前目录，不受开发者自己的配置影响
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn fixture(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("minigrep-config-file-{name}"));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("home")).unwrap();
+    fs::create_dir_all(dir.join("project/src")).unwrap();
+    fs::write(dir.join("project/notes.txt"), "one\nTwo rust\nthree\n").unwrap();
+    dir
+}
+
+// 在 cwd 中运行，返回标准输出
+fn minigrep(dir: &Path, cwd: &str, args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-study"))
+        .args(args)
+        .env("HOME", dir.join("home"))
+        .env_remove("IGNORE_CASE")
+        .current_dir(dir.join(cwd))
+        .output()
+        .unwrap();
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn precedence() {
+    let dir = fixture("precedence");
+    let search = ["RUST", "notes.txt"];
+    let header = "Searching for RUST\nIn file notes.txt\n";
+    assert_eq!(minigrep(&dir, "project", &search), header);
+
+    // 用户配置
+    fs::write(
+        dir.join("home/.minigreprc"),
+        "ignore_case = true\noutput = json\n",
+    )
+    .unwrap();
+    assert_eq!(
+        minigrep(&dir, "project", &search),
+        "{\"file\":\"notes.txt\",\"line_number\":2,\"line\":\"Two rust\"}\n"
+    );
+
+    // 项目配置覆盖用户配置，在子目录中也能找到
+    fs::write(
+        dir.join("project/.minigreprc"),
+        "output = text\ncontext = 1\n",
+    )
+    .unwrap();
+    assert_eq!(
+        minigrep(&dir, "project/src", &["RUST", "../notes.txt"]),
+        "Searching for RUST\nIn file ../notes.txt\n1-one\n2:Two rust\n3-three\n"
+    );
+
+    // 命令行覆盖配置文件
+    assert_eq!(
+        minigrep(
+            &dir,
+            "project",
+            &["-C", "0", "--output", "json", "RUST", "notes.txt"]
+        ),
+        "{\"file\":\"notes.txt\",\"line_number\":2,\"line\":\"Two rust\"}\n"
+    );
+    assert_eq!(
+        minigrep(&dir, "project", &["--no-config", "RUST", "notes.txt"]),
+        header
+    );
+}
+
+#[test]
+fn invalid_config_file() {
+    let dir = fixture("invalid");
+    fs::write(dir.join(".minigreprc"), "colour = always\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-study"))
+        .args(["rust", "-"])
+        .env("HOME", dir.join("home"))
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.contains(".minigreprc: line 1: unknown setting"),
+        "{stdout}"
+    );
+}
@@ -0,0 +1,81 @@
+// --follow：运行编译好的程序跟踪一个不断追加内容的临时文件，检查新的匹配行及时输出，
+// 以及 --max-results 和 Ctrl-C 都能让它正常结束
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+fn log_file(name: &str, contents: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("minigrep-follow-{name}.log"));
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+fn append(path: &PathBuf, text: &str) {
+    let mut file = OpenOptions::new().append(true).open(path).unwrap();
+    file.write_all(text.as_bytes()).unwrap();
+}
+
+fn minigrep(args: &[&str]) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_rust-study"));
+    command
+        .args(["--no-config", "--output", "json"])
+        .args(args)
+        .stdout(Stdio::piped());
+    command
+}
+
+// 到达上限之前一直等待新内容，每追加一行匹配就能立即读到
+#[test]
+fn follow_until_max_results() {
+    let path = log_file("max-results", "error: one\nok\n");
+    let mut child = minigrep(&[
+        "--follow",
+        "--max-results",
+        "3",
+        "error",
+        path.to_str().unwrap(),
+    ])
+    .spawn()
+    .unwrap();
+    let mut lines = BufReader::new(child.stdout.take().unwrap()).lines();
+    assert!(lines.next().unwrap().unwrap().contains("error: one"));
+    append(&path, "ok again\nerror: two\n");
+    assert!(lines.next().unwrap().unwrap().contains("error: two"));
+    append(&path, "error: three\nerror: four\n");
+    assert!(lines.next().unwrap().unwrap().contains("error: three"));
+    assert_eq!(
+        lines.next().unwrap().unwrap(),
+        "{\"type\":\"truncated\",\"max_results\":3}"
+    );
+    assert!(lines.next().is_none());
+    assert!(child.wait().unwrap().success());
+}
+
+// 第一次 Ctrl-C 让搜索正常结束：输出统计信息，退出码按是否找到匹配决定
+#[cfg(unix)]
+#[test]
+fn follow_until_interrupted() {
+    let path = log_file("interrupted", "ok\n");
+    let mut child = minigrep(&["--follow", "--stats", "error", path.to_str().unwrap()])
+        .spawn()
+        .unwrap();
+    let mut lines = BufReader::new(child.stdout.take().unwrap()).lines();
+    thread::sleep(Duration::from_millis(300));
+    append(&path, "error: late\n");
+    assert!(lines.next().unwrap().unwrap().contains("error: late"));
+    let status = Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert!(lines
+        .next()
+        .unwrap()
+        .unwrap()
+        .contains("\"matching_lines\":1"));
+    assert!(child.wait().unwrap().success());
+}
@@ -0,0 +1,347 @@
+// run_with_writer 的集成测试：通过公开的接口运行完整的搜索，逐字节检查写出的结果
+use rust_study::rules::Severity;
+use rust_study::{run_with_writer, Config, Environment, RunResult};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// 每个测试使用自己的目录，测试可以并发运行
+fn fixture(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("minigrep-run-with-writer-{name}"));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let files: [(&str, &[u8]); 3] = [
+        (
+            "poem.txt",
+            b"Rust:\nsafe, fast, productive.\nPick three.\nTrust me.\n",
+        ),
+        ("notes.txt", b"one\ntwo rust\nthree\nfour\nfive\nsix rust\n"),
+        ("bin.dat", b"ELF\0rust\n"),
+    ];
+    for (file, contents) in files {
+        fs::write(dir.join(file), contents).unwrap();
+    }
+    dir
+}
+
+// args 中的 {dir} 换成测试目录，返回写出的内容和搜索结果。不读取运行者的 IGNORE_CASE 和配置文件
+fn run_args(dir: &Path, args: &[&str]) -> (String, RunResult) {
+    let dir = dir.to_str().unwrap();
+    let args = ["minigrep"]
+        .iter()
+        .chain(args)
+        .map(|arg| arg.replace("{dir}", dir));
+    let config = Config::build_with(args, Environment::default()).unwrap();
+    let mut out = Vec::new();
+    let result = run_with_writer(config, &mut out).unwrap();
+    (String::from_utf8(out).unwrap(), result)
+}
+
+fn output(dir: &Path, args: &[&str]) -> String {
+    run_args(dir, args).0
+}
+
+#[test]
+fn single_file() {
+    let dir = fixture("single");
+    assert_eq!(output(&dir, &["rust", "{dir}/poem.txt"]), "4:Trust me.\n");
+    // 找不到时什么都不写
+    let (out, result) = run_args(&dir, &["python", "{dir}/poem.txt"]);
+    assert_eq!(out, "");
+    assert!(!result.matches_found);
+}
+
+#[test]
+fn multiple_files() {
+    let dir = fixture("multiple");
+    let d = dir.to_str().unwrap();
+    assert_eq!(
+        output(&dir, &["-i", "rust", "{dir}/poem.txt", "{dir}/notes.txt"]),
+        format!(
+            "{d}/poem.txt:1:Rust:\n{d}/poem.txt:4:Trust me.\n\
+             {d}/notes.txt:2:two rust\n{d}/notes.txt:6:six rust\n"
+        )
+    );
+    // 并发搜索时的输出和顺序搜索相同
+    for threads in ["1", "4"] {
+        assert_eq!(
+            output(
+                &dir,
+                &[
+                    "-j",
+                    threads,
+                    "-c",
+                    "rust",
+                    "{dir}/poem.txt",
+                    "{dir}/notes.txt"
+                ]
+            ),
+            format!("{d}/poem.txt:1\n{d}/notes.txt:2\n")
+        );
+    }
+    // 目录中的文件按名称排序
+    assert_eq!(
+        output(&dir, &["-l", "rust", "{dir}"]),
+        format!("{d}/bin.dat\n{d}/notes.txt\n{d}/poem.txt\n")
+    );
+}
+
+#[test]
+fn missing_input() {
+    let dir = fixture("missing");
+    let d = dir.to_str().unwrap();
+    // 读不了的文件跳过，其他文件照常搜索，最后记下出错的个数
+    for threads in ["1", "4"] {
+        let (out, result) = run_args(
+            &dir,
+            &[
+                "-j",
+                threads,
+                "rust",
+                "{dir}/missing.txt",
+                "{dir}/poem.txt",
+                "{dir}/gone.txt",
+                "{dir}/notes.txt",
+            ],
+        );
+        assert_eq!(
+            out,
+            format!(
+                "{d}/poem.txt:4:Trust me.\n{d}/notes.txt:2:two rust\n{d}/notes.txt:6:six rust\n"
+            )
+        );
+        assert_eq!(result.input_errors, 2);
+        assert_eq!(result.stats.files_scanned, 2);
+    }
+}
+
+#[test]
+fn context() {
+    let dir = fixture("context");
+    assert_eq!(
+        output(&dir, &["-C", "1", "rust", "{dir}/notes.txt"]),
+        "1-one\n2:two rust\n3-three\n--\n5-five\n6:six rust\n"
+    );
+}
+
+#[test]
+fn formats() {
+    let dir = fixture("formats");
+    let d = dir.to_str().unwrap();
+    assert_eq!(
+        output(&dir, &["--output", "json", "-i", "rust", "{dir}/poem.txt"]),
+        format!(
+            "{{\"file\":\"{d}/poem.txt\",\"line_number\":1,\"line\":\"Rust:\"}}\n\
+             {{\"file\":\"{d}/poem.txt\",\"line_number\":4,\"line\":\"Trust me.\"}}\n"
+        )
+    );
+    // 写到其他地方时 --color=auto 不使用颜色，--color=always 照常高亮
+    assert_eq!(
+        output(&dir, &["--color", "always", "rust", "{dir}/notes.txt"]),
+        "2:two \x1b[1;31mrust\x1b[0m\n6:six \x1b[1;31mrust\x1b[0m\n"
+    );
+    assert_eq!(
+        output(&dir, &["--color", "auto", "rust", "{dir}/notes.txt"]),
+        "2:two rust\n6:six rust\n"
+    );
+    assert_eq!(
+        output(&dir, &["rust", "{dir}/bin.dat"]),
+        format!("Binary file {d}/bin.dat matches\n")
+    );
+}
+
+#[test]
+fn replace() {
+    let dir = fixture("replace");
+    let (out, result) = run_args(&dir, &["--replace", "RUST", "rust", "{dir}/notes.txt"]);
+    assert_eq!(out, "one\ntwo RUST\nthree\nfour\nfive\nsix RUST\n");
+    assert!(result.matches_found);
+}
+
+#[test]
+fn quiet() {
+    let dir = fixture("quiet");
+    let (out, result) = run_args(&dir, &["-q", "rust", "{dir}"]);
+    assert_eq!(out, "");
+    assert!(result.matches_found);
+}
+
+#[test]
+fn fuzzy() {
+    let dir = fixture("fuzzy");
+    let d = dir.to_str().unwrap();
+    let search = [
+        "-i",
+        "--fuzzy",
+        "trust",
+        "{dir}/poem.txt",
+        "{dir}/notes.txt",
+    ];
+    // 按相似度排序，相同时保持文件和行的顺序
+    let (out, result) = run_args(&dir, &search);
+    assert_eq!(
+        out,
+        format!(
+            "[1.00] {d}/poem.txt:4:Trust me.\n\
+             [0.80] {d}/poem.txt:1:Rust:\n\
+             [0.80] {d}/notes.txt:2:two rust\n\
+             [0.80] {d}/notes.txt:6:six rust\n"
+        )
+    );
+    assert_eq!(result.stats.matching_lines, 4);
+    // 只输出最相似的前两行
+    let (out, result) = run_args(&dir, &[&["--max-results", "2"][..], &search].concat());
+    assert_eq!(out.lines().count(), 2);
+    assert!(result.truncated);
+}
+
+#[test]
+fn only_matching() {
+    let dir = fixture("only-matching");
+    fs::write(dir.join("pairs.txt"), "aaaaa\nabab ba\nnone\n").unwrap();
+    // 匹配互不重叠，从左到右取，一行中的多个匹配各占一行
+    assert_eq!(
+        output(&dir, &["-o", "aa", "{dir}/pairs.txt"]),
+        "1:aa\n1:aa\n"
+    );
+    assert_eq!(
+        output(&dir, &["-o", "-E", "ab|ba", "{dir}/pairs.txt"]),
+        "2:ab\n2:ab\n2:ba\n"
+    );
+    assert_eq!(
+        output(&dir, &["-o", "-E", "a+", "{dir}/pairs.txt"]),
+        "1:aaaaa\n2:a\n2:a\n2:a\n"
+    );
+    let (out, result) = run_args(
+        &dir,
+        &["-o", "-i", "RUST", "{dir}/poem.txt", "{dir}/notes.txt"],
+    );
+    let d = dir.to_str().unwrap();
+    assert_eq!(
+        out,
+        format!("{d}/poem.txt:1:Rust\n{d}/poem.txt:4:rust\n{d}/notes.txt:2:rust\n{d}/notes.txt:6:rust\n")
+    );
+    // 统计的仍然是匹配的行数
+    assert_eq!(result.stats.matching_lines, 4);
+    assert_eq!(
+        output(
+            &dir,
+            &[
+                "-o",
+                "--redact",
+                "t\\w+",
+                "-E",
+                "\\w+ rust",
+                "{dir}/notes.txt"
+            ]
+        ),
+        "2:*** rust\n6:six rust\n"
+    );
+}
+
+#[test]
+fn byte_offset() {
+    let dir = fixture("byte-offset");
+    fs::write(dir.join("crlf.txt"), "one\r\nt\u{e9}a two\r\ntwo\n").unwrap();
+    // 偏移按字节计算，包括 \r\n 和多字节字符
+    assert_eq!(
+        output(&dir, &["-b", "two", "{dir}/crlf.txt"]),
+        "2:5:t\u{e9}a two\n3:15:two\n"
+    );
+    assert_eq!(
+        output(&dir, &["-b", "-o", "two", "{dir}/crlf.txt"]),
+        "2:10:two\n3:15:two\n"
+    );
+    // 上下文行也带偏移，和 grep 一样用 - 分隔
+    assert_eq!(
+        output(&dir, &["-b", "-B", "1", "t\u{e9}a", "{dir}/crlf.txt"]),
+        "1-0-one\n2:5:t\u{e9}a two\n"
+    );
+    // 遮盖和替换无效的字节都会改变行的内容，偏移仍然是匹配在文件中的位置
+    fs::write(dir.join("masked.txt"), "\u{e9}\u{e9}\u{e9} two\n").unwrap();
+    assert_eq!(
+        output(
+            &dir,
+            &["-b", "-o", "--redact", "\u{e9}+", "two", "{dir}/masked.txt"]
+        ),
+        "1:7:two\n"
+    );
+    fs::write(dir.join("invalid.txt"), b"a\xffb two\n").unwrap();
+    assert_eq!(
+        output(&dir, &["-b", "-o", "two", "{dir}/invalid.txt"]),
+        "1:4:two\n"
+    );
+    assert_eq!(
+        output(&dir, &["-b", "--output=json", "one", "{dir}/crlf.txt"]),
+        format!(
+            "{{\"file\":\"{}/crlf.txt\",\"line_number\":1,\"byte_offset\":0,\"line\":\"one\"}}\n",
+            dir.to_str().unwrap()
+        )
+    );
+}
+
+#[test]
+fn rule_pack() {
+    let dir = fixture("rules");
+    fs::write(
+        dir.join("team.toml"),
+        "name = \"team\"\n\n\
+         [[rule]]\nid = \"rust\"\npattern = '\\brust\\b'\nseverity = \"low\"\n\n\
+         [[rule]]\nid = \"six\"\npattern = '^six'\nseverity = \"high\"\n",
+    )
+    .unwrap();
+    assert_eq!(
+        output(&dir, &["--rules", "{dir}/team.toml", "{dir}/notes.txt"]),
+        "[low rust] 2:two rust\n[high rust,six] 6:six rust\n"
+    );
+    // 顺序搜索、并发搜索和计数模式都记下报告过的匹配中最高的严重程度
+    let rules = ["--rules", "{dir}/team.toml", "--fail-on", "warning"];
+    for extra in [&["-j", "1"][..], &["-j", "2"], &["-c"]] {
+        let search = [&rules[..], extra, &["{dir}/poem.txt", "{dir}/notes.txt"]].concat();
+        let (_, result) = run_args(&dir, &search);
+        assert_eq!(result.max_severity, Some(Severity::High));
+        assert!(result.failed(Some(Severity::High)));
+        assert!(!result.failed(Some(Severity::Critical)));
+    }
+    // 只有低严重程度的匹配时达不到 error
+    fs::write(dir.join("low.txt"), "rust only\n").unwrap();
+    let (_, result) = run_args(&dir, &[&rules[..], &["{dir}/low.txt"]].concat());
+    assert_eq!(result.max_severity, Some(Severity::Low));
+    assert!(!result.failed(Some(Severity::High)));
+    let (_, result) = run_args(&dir, &[&rules[..], &["{dir}/poem.txt"]].concat());
+    assert_eq!(result.max_severity, None);
+    assert!(!result.failed(Some(Severity::Info)));
+}
+
+#[test]
+fn baseline() {
+    let dir = fixture("baseline");
+    fs::write(
+        dir.join("team.toml"),
+        "name = \"team\"\n\n[[rule]]\nid = \"rust\"\npattern = '\\brust\\b'\nseverity = \"high\"\n",
+    )
+    .unwrap();
+    let search = [
+        "--rules",
+        "{dir}/team.toml",
+        "--baseline",
+        "{dir}/baseline.txt",
+        "--fail-on",
+        "error",
+        "{dir}/notes.txt",
+    ];
+    // 第一次运行记下已有的两个发现，什么都不报告
+    let (out, result) = run_args(&dir, &search);
+    assert_eq!(out, "");
+    assert!(!result.matches_found);
+    assert!(!result.failed(Some(Severity::High)));
+    assert!(dir.join("baseline.txt").exists());
+    // 移动和重新缩进已知的行不算新发现，新增的行才报告
+    fs::write(dir.join("notes.txt"), "six rust\n  two rust\nseven rust\n").unwrap();
+    let (out, result) = run_args(&dir, &search);
+    assert_eq!(out, "[high rust] 3:seven rust\n");
+    assert_eq!(result.stats.matching_lines, 1);
+    assert!(result.failed(Some(Severity::High)));
+    let (out, _) = run_args(&dir, &[&["-c"][..], &search].concat());
+    assert_eq!(out, "1\n");
+}